@@ -0,0 +1,199 @@
+#[cfg(any(feature = "hydrate", test))]
+use std::collections::HashMap;
+
+/// Wall-clock window price ticks are bucketed into before they reach the
+/// charts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AggWindow {
+    #[default]
+    Raw,
+    Ms100,
+    S1,
+}
+
+impl AggWindow {
+    pub fn name(self) -> &'static str {
+        match self {
+            AggWindow::Raw => "raw",
+            AggWindow::Ms100 => "100ms",
+            AggWindow::S1 => "1s",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "100ms" => AggWindow::Ms100,
+            "1s" => AggWindow::S1,
+            _ => AggWindow::Raw,
+        }
+    }
+
+    /// Window length in µs, `None` for raw ticks
+    #[cfg(any(feature = "hydrate", test))]
+    fn micros(self) -> Option<i64> {
+        match self {
+            AggWindow::Raw => None,
+            AggWindow::Ms100 => Some(100_000),
+            AggWindow::S1 => Some(1_000_000),
+        }
+    }
+}
+
+/// Value a window of ticks is reduced to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AggFn {
+    #[default]
+    Last,
+    Mean,
+}
+
+impl AggFn {
+    pub fn name(self) -> &'static str {
+        match self {
+            AggFn::Last => "last",
+            AggFn::Mean => "mean",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        if name == "mean" { AggFn::Mean } else { AggFn::Last }
+    }
+}
+
+/// Ticks of one symbol in the window being filled
+#[cfg(any(feature = "hydrate", test))]
+struct Bucket {
+    index: i64,
+    sum: f64,
+    count: u32,
+    last: f64,
+    ts: i64, // of the latest tick
+}
+
+#[cfg(any(feature = "hydrate", test))]
+impl Bucket {
+    fn new(index: i64, price: f64, ts: i64) -> Self {
+        Self { index, sum: price, count: 1, last: price, ts }
+    }
+
+    fn add(&mut self, price: f64, ts: i64) {
+        self.sum += price;
+        self.count += 1;
+        self.last = price;
+        self.ts = self.ts.max(ts);
+    }
+
+    fn value(&self, func: AggFn) -> (f64, i64) {
+        match func {
+            AggFn::Last => (self.last, self.ts),
+            AggFn::Mean => (self.sum / self.count as f64, self.ts),
+        }
+    }
+}
+
+/// Pending window per symbol. A window is emitted once a tick of a later
+/// window arrives, so in aggregated mode the charts trail by up to one
+/// window, or by [`PriceAggregator::flush_stale`] once no tick came for it.
+#[cfg(any(feature = "hydrate", test))]
+#[derive(Default)]
+pub struct PriceAggregator {
+    mode: (AggWindow, AggFn),
+    buckets: HashMap<String, Bucket>,
+}
+
+#[cfg(any(feature = "hydrate", test))]
+impl PriceAggregator {
+    /// Feeds one tick, `ts` in µs since the epoch. Returns the tick itself in
+    /// raw mode, the finished window as (price, ts of its latest tick) when
+    /// this tick starts a new one, otherwise `None`. Changing the mode drops
+    /// the pending windows; non-finite prices never enter a window.
+    pub fn push(&mut self, window: AggWindow, func: AggFn, symbol: &str, price: f64, ts: i64) -> Option<(f64, i64)> {
+        if self.mode != (window, func) {
+            self.mode = (window, func);
+            self.buckets.clear();
+        }
+        let Some(len) = window.micros() else { return Some((price, ts)) };
+        if !price.is_finite() {
+            return None;
+        }
+        let index = ts.div_euclid(len);
+        match self.buckets.get_mut(symbol) {
+            // A late tick still counts towards the open window
+            Some(bucket) if index <= bucket.index => {
+                bucket.add(price, ts);
+                None
+            }
+            Some(bucket) => {
+                let done = bucket.value(func);
+                *bucket = Bucket::new(index, price, ts);
+                Some(done)
+            }
+            None => {
+                self.buckets.insert(symbol.to_string(), Bucket::new(index, price, ts));
+                None
+            }
+        }
+    }
+
+    /// Takes the windows that ended at least one window length before `now`
+    /// (µs) as (symbol, price, ts), so a symbol whose ticks stopped still
+    /// gets its last window onto the charts.
+    pub fn flush_stale(&mut self, now: i64) -> Vec<(String, f64, i64)> {
+        let Some(len) = self.mode.0.micros() else { return Vec::new() };
+        let func = self.mode.1;
+        let mut done = Vec::new();
+        self.buckets.retain(|symbol, bucket| {
+            if (bucket.index + 2) * len > now {
+                return true;
+            }
+            let (price, ts) = bucket.value(func);
+            done.push((symbol.clone(), price, ts));
+            false
+        });
+        done
+    }
+
+    /// Drops the pending windows, e.g. when the price history is cleared
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_close_on_a_later_tick() {
+        let mut agg = PriceAggregator::default();
+        assert_eq!(agg.push(AggWindow::S1, AggFn::Mean, "BTC/USD", 10.0, 1_000_000), None);
+        assert_eq!(agg.push(AggWindow::S1, AggFn::Mean, "BTC/USD", 20.0, 1_500_000), None);
+        assert_eq!(agg.push(AggWindow::S1, AggFn::Mean, "BTC/USD", 5.0, 2_000_000), Some((15.0, 1_500_000)));
+        // Raw mode passes ticks through and drops the open windows
+        assert_eq!(agg.push(AggWindow::Raw, AggFn::Mean, "BTC/USD", 7.0, 2_100_000), Some((7.0, 2_100_000)));
+        assert!(agg.flush_stale(i64::MAX).is_empty());
+    }
+
+    #[test]
+    fn stale_windows_are_flushed_once() {
+        let mut agg = PriceAggregator::default();
+        agg.push(AggWindow::Ms100, AggFn::Last, "BTC/USD", 10.0, 1_000_000);
+        agg.push(AggWindow::Ms100, AggFn::Last, "ETH/USD", 20.0, 1_150_000);
+        // The BTC window [1.0 s, 1.1 s) is stale from 1.2 s on
+        assert!(agg.flush_stale(1_199_999).is_empty());
+        assert_eq!(agg.flush_stale(1_200_000), [("BTC/USD".to_string(), 10.0, 1_000_000)]);
+        assert!(agg.flush_stale(1_200_000).is_empty());
+        // The next BTC tick opens a new window instead of closing the flushed one
+        assert_eq!(agg.push(AggWindow::Ms100, AggFn::Last, "BTC/USD", 11.0, 1_250_000), None);
+    }
+
+    #[test]
+    fn clear_drops_pending_windows() {
+        let mut agg = PriceAggregator::default();
+        agg.push(AggWindow::S1, AggFn::Last, "BTC/USD", 10.0, 1_000_000);
+        agg.clear();
+        assert_eq!(agg.push(AggWindow::S1, AggFn::Last, "BTC/USD", 12.0, 2_000_000), None);
+        // Only the window opened after the clear is left
+        assert_eq!(agg.flush_stale(i64::MAX), [("BTC/USD".to_string(), 12.0, 2_000_000)]);
+    }
+}
@@ -7,13 +7,34 @@ use leptos_router::{
     StaticSegment,
 };
 
+use serde::{Deserialize, Serialize};
+use leptos_rust::can_types::format_can_id;
 #[cfg(feature = "hydrate")]
-use serde::Deserialize;
+use leptos_rust::error_code::ErrorCode;
+
+mod aggregate;
+mod measurement;
+mod price_format;
+mod sparkline;
+mod units;
+mod wire;
+use aggregate::{AggFn, AggWindow};
+use measurement::MeasurementDashboard;
+use price_format::FormatSpec;
+use sparkline::{
+    sparkline_area, sparkline_area_timed, sparkline_label, sparkline_points, sparkline_points_timed, sparkline_x,
+    sparkline_x_timed, time_axis_labels, SparkStyle,
+};
+use units::UnitSystem;
+pub use wire::{PriceHistory, SymbolInfo, WireMessage};
 
 #[cfg(feature = "hydrate")]
 use wasm_bindgen::JsCast;
 
 pub fn shell(options: LeptosOptions) -> impl IntoView {
+    let client_config = serde_json::to_string(&ClientConfig::from_env()).unwrap_or_default();
+    // Full data WebSocket URL, e.g. for a path prefix behind a reverse proxy
+    let ws_url = std::env::var("WS_URL").ok().filter(|url| !url.is_empty());
     view! {
         <!DOCTYPE html>
         <html lang="en">
@@ -23,6 +44,7 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
                 <AutoReload options=options.clone()/>
                 <HydrationScripts options/>
                 <MetaTags/>
+                <script type="application/json" id="leptos-client-config" data-ws-url=ws_url inner_html=client_config></script>
             </head>
             <body>
                 <App/>
@@ -31,164 +53,2259 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
     }
 }
 
+/// Version of the WebSocket message set. The server announces its version in
+/// the `hello` frame; bump this whenever a message changes incompatibly.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Symbols of the simulated price stream. The Control Panel offers these
+/// until the server's `hello` frame lists the symbols it actually serves.
+pub const SYMBOLS: &[&str] = &["BTC/USD", "ETH/USD", "SOL/USD", "AAPL", "TSLA"];
+
+/// Simulated streams that the `enabled_streams` control can pause.
+pub const STREAMS: &[&str] = &["price", "book", "trade", "system"];
+
+/// An empty subscription means "receive everything".
+fn is_subscribed(subscribed: &[String], symbol: &str) -> bool {
+    subscribed.is_empty() || subscribed.iter().any(|s| s == symbol)
+}
+
+/// One OHLC candle as aggregated by the server.
+#[derive(Clone, Debug, PartialEq)]
+struct Candle {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
+    ts: i64,
+}
+
+/// Symbol → completed candles, oldest first.
+type CandleMap = std::collections::HashMap<String, Vec<Candle>>;
+
+/// One second of a symbol's trades as summed up by the server.
+#[derive(Clone, Debug, PartialEq)]
+struct TradeSummary {
+    count: u64,
+    volume: f64,
+    vwap: f64,
+    buy_ratio: f64, // share of `volume` bought
+    ts: i64,        // start of the second
+}
+
+/// Symbol → its latest trade summary.
+type TradeSummaryMap = std::collections::BTreeMap<String, TradeSummary>;
+
+/// `(price, size)` levels of one book side, best price first.
+type BookLevels = Vec<(f64, f64)>;
+
+/// Symbol → (bids, asks) of the latest book update.
+type BookMap = std::collections::HashMap<String, (BookLevels, BookLevels)>;
+
+/// Signal name → (latest value, unit, source CAN ID) of decoded vehicle signals.
+type VehicleSignalMap = std::collections::HashMap<String, (f64, String, String)>;
+
+/// Symbol → `(price, size)` of its most recent trades, oldest first.
+type TradeWindowMap = std::collections::HashMap<String, Vec<(f64, f64)>>;
+
+/// Volume-weighted average price of `(price, size)` trades; 0 if there is
+/// no volume. Trades with a non-finite price or size are skipped.
+fn vwap(trades: &[(f64, f64)]) -> f64 {
+    let (notional, volume) = trades
+        .iter()
+        .filter(|(price, size)| price.is_finite() && size.is_finite())
+        .fold((0.0, 0.0), |(n, v), (price, size)| (n + price * size, v + size));
+    if volume > 0.0 { notional / volume } else { 0.0 }
+}
+
+/// Latest figures of one CAN bus from its `bus_status` and `can_stats`.
+#[derive(Clone, Debug, Default)]
+struct CanBusStats {
+    load_percent: f32,
+    messages_per_sec: f32,
+    bytes_per_sec: f32,
+    error_count: u32,
+    avg_latency_us: u64,
+    max_latency_us: u64,
+    frames: u64, // raw frames received since the last reset
+}
+
+/// Bus ID → latest statistics of the simulated CAN buses.
+type CanBusMap = std::collections::BTreeMap<u8, CanBusStats>;
+
+/// Formats a vehicle signal value with the precision that suits its unit.
+fn format_signal_value(value: f64, unit: &str) -> String {
+    match unit {
+        "rpm" => format!("{:.0} {}", value, unit),
+        "km/h" | "mph" | "°C" | "°F" => format!("{:.1} {}", value, unit),
+        "V" => format!("{:.2} {}", value, unit),
+        "" => format!("{:.2}", value),
+        _ => format!("{:.2} {}", value, unit),
+    }
+}
+
+/// Formats a byte rate with the largest unit that keeps it at or above 1.
+fn format_byte_rate(bytes_per_sec: f64) -> String {
+    if bytes_per_sec >= 1024.0 * 1024.0 {
+        format!("{:.2} MB/s", bytes_per_sec / (1024.0 * 1024.0))
+    } else if bytes_per_sec >= 1024.0 {
+        format!("{:.1} KB/s", bytes_per_sec / 1024.0)
+    } else {
+        format!("{:.0} B/s", bytes_per_sec)
+    }
+}
+
+/// `(price, cumulative size)` per level, accumulated from the mid outward.
+/// Bids are ordered by descending price, asks by ascending price. Levels
+/// with a non-finite price or size are left out.
+fn cumulative_depth(levels: &[(f64, f64)], bids: bool) -> Vec<(f64, f64)> {
+    let mut sorted: Vec<(f64, f64)> =
+        levels.iter().copied().filter(|(price, size)| price.is_finite() && size.is_finite()).collect();
+    sorted.sort_by(|a, b| if bids { b.0.total_cmp(&a.0) } else { a.0.total_cmp(&b.0) });
+    let mut total = 0.0;
+    sorted
+        .into_iter()
+        .map(|(price, size)| {
+            total += size;
+            (price, total)
+        })
+        .collect()
+}
+
+/// Top-of-book summary of one symbol.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SpreadMetrics {
+    best_bid: f64,
+    best_ask: f64,
+    spread: f64,
+    /// Spread relative to `mid` in basis points; `None` if `mid` is zero.
+    spread_bps: Option<f64>,
+    mid: f64,
+}
+
+/// Best bid/ask, spread and mid from bid and ask prices in any order,
+/// ignoring non-finite prices. `None` if either side has no finite price.
+fn spread_metrics(bids: &[f64], asks: &[f64]) -> Option<SpreadMetrics> {
+    let best_bid = bids.iter().copied().filter(|p| p.is_finite()).reduce(f64::max)?;
+    let best_ask = asks.iter().copied().filter(|p| p.is_finite()).reduce(f64::min)?;
+    let spread = best_ask - best_bid;
+    let mid = (best_bid + best_ask) / 2.0;
+    let spread_bps = (mid != 0.0).then(|| spread / mid * 10_000.0);
+    Some(SpreadMetrics { best_bid, best_ask, spread, spread_bps, mid })
+}
+
+impl SpreadMetrics {
+    /// Prices in the symbol's `spec`, the spread without prefix or suffix
+    fn summary(&self, spec: &FormatSpec) -> String {
+        let bps = self.spread_bps.map(|b| format!("{:.1} bps", b)).unwrap_or_else(|| "n/a".into());
+        format!(
+            "bid {} | ask {} | spread {:.*} ({}) | mid {}",
+            spec.format(self.best_bid), spec.format(self.best_ask), spec.decimals, self.spread, bps, spec.format(self.mid)
+        )
+    }
+}
+
+/// Copy of the chart data taken when rendering is paused.
+#[derive(Clone, Default)]
+struct Snapshot {
+    prices: std::collections::HashMap<String, Vec<f64>>,
+    price_times: std::collections::HashMap<String, Vec<i64>>,
+    trades: Vec<(String, f64, f64, String)>,
+    vwap_trades: TradeWindowMap,
+    trade_summaries: TradeSummaryMap,
+    book_depth: BookMap,
+    candles: CandleMap,
+    vehicle_signals: VehicleSignalMap,
+}
+
+/// Lifecycle of the client WebSocket, shown as an indicator in the header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnState {
+    Connecting,
+    Connected,
+    Reconnecting,
+    /// `max_reconnect_attempts` ran out; only "Reconnect now" tries again
+    Disconnected,
+}
+
+impl ConnState {
+    fn label(self) -> &'static str {
+        match self {
+            ConnState::Connecting => "connecting",
+            ConnState::Connected => "connected",
+            ConnState::Reconnecting => "reconnecting",
+            ConnState::Disconnected => "disconnected — click to retry",
+        }
+    }
+
+    fn color(self, theme: &Theme) -> &'static str {
+        match self {
+            ConnState::Connecting => theme.neutral,
+            ConnState::Connected => theme.up,
+            ConnState::Reconnecting => theme.warn,
+            ConnState::Disconnected => theme.down,
+        }
+    }
+}
+
+/// Color palette of the UI; chart strokes and card styles look their
+/// colors up here instead of hardcoding them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub dark: bool,
+    pub background: &'static str,
+    pub text: &'static str,
+    /// Secondary text: hints, axis labels, empty states
+    pub muted: &'static str,
+    /// Status lines and small print
+    pub subtle: &'static str,
+    pub border: &'static str,
+    pub input_border: &'static str,
+    pub input_background: &'static str,
+    /// Separator between list rows
+    pub divider: &'static str,
+    /// Background of the control panel
+    pub panel: &'static str,
+    /// Prices and primary buttons
+    pub accent: &'static str,
+    /// Rising prices, buys, bids
+    pub up: &'static str,
+    /// Falling prices, sells, asks, errors
+    pub down: &'static str,
+    /// Warnings, SMA, latency
+    pub warn: &'static str,
+    /// VWAP and the volume chart
+    pub volume: &'static str,
+    /// Message rate chart
+    pub rate: &'static str,
+    /// Inactive states
+    pub neutral: &'static str,
+    pub button_secondary: &'static str,
+    pub banner_warn_background: &'static str,
+    pub banner_warn_border: &'static str,
+    pub banner_error_background: &'static str,
+}
+
+impl Theme {
+    pub const LIGHT: Theme = Theme {
+        dark: false,
+        background: "#ffffff",
+        text: "#222222",
+        muted: "#888888",
+        subtle: "#666666",
+        border: "#dddddd",
+        input_border: "#cccccc",
+        input_background: "#ffffff",
+        divider: "#f0f0f0",
+        panel: "#f5f5f5",
+        accent: "#0066cc",
+        up: "#00cc66",
+        down: "#ff6666",
+        warn: "#ff9900",
+        volume: "#9933ff",
+        rate: "#cc3399",
+        neutral: "#999999",
+        button_secondary: "#666666",
+        banner_warn_background: "#fff3cd",
+        banner_warn_border: "#ffcc00",
+        banner_error_background: "#fde2e2",
+    };
+
+    /// Strokes are lighter than in `LIGHT` so thin chart lines stay
+    /// readable on the dark background.
+    pub const DARK: Theme = Theme {
+        dark: true,
+        background: "#121417",
+        text: "#e4e6eb",
+        muted: "#8b929c",
+        subtle: "#a8aeb7",
+        border: "#33383f",
+        input_border: "#4a5059",
+        input_background: "#1e2227",
+        divider: "#262a30",
+        panel: "#1b1f24",
+        accent: "#4da3ff",
+        up: "#33d68a",
+        down: "#ff7a7a",
+        warn: "#ffaa33",
+        volume: "#b57aff",
+        rate: "#e066b3",
+        neutral: "#6b727c",
+        button_secondary: "#4a5059",
+        banner_warn_background: "#3d3314",
+        banner_warn_border: "#b38f00",
+        banner_error_background: "#3d1c1c",
+    };
+
+    #[cfg(feature = "hydrate")]
+    fn name(&self) -> &'static str {
+        if self.dark { "dark" } else { "light" }
+    }
+
+    #[cfg(feature = "hydrate")]
+    fn from_name(name: &str) -> Self {
+        if name == "dark" { Theme::DARK } else { Theme::LIGHT }
+    }
+}
+
+/// First reconnect delay; doubled on every failed attempt.
+const RECONNECT_BASE_MS: u64 = 500;
+/// Upper bound for the reconnect delay.
+const RECONNECT_MAX_MS: u64 = 30_000;
+/// How often the client pings the server.
+const PING_INTERVAL_MS: u64 = 10_000;
+/// Unanswered pings after which the connection is considered dead.
+const MAX_MISSED_PINGS: u32 = 3;
+/// Failed reconnects before giving up; 0 retries forever.
+const MAX_RECONNECT_ATTEMPTS: u32 = 0;
+/// How far back "Dump" reaches into the received messages.
+const CAPTURE_SECS: u64 = 30;
+/// Browser console verbosity unless `?log=` says otherwise.
+const LOG_LEVEL: log::Level = log::Level::Info;
+
+/// Reconnect and heartbeat timings, tunable without recompiling. The server
+/// embeds them in `shell()` as
+/// `<script type="application/json" id="leptos-client-config">`, taken from
+/// the `CLIENT_CONFIG` environment variable. JSON shape, every field optional:
+///
+/// ```json
+/// {"reconnect_base_ms": 500, "reconnect_max_ms": 30000, "max_reconnect_attempts": 0,
+///  "ping_interval_ms": 10000, "max_missed_pings": 3, "capture_secs": 30, "log_level": "info"}
+/// ```
+///
+/// Missing fields keep their default; a malformed or nonsensical config
+/// (zero values, max below base) is replaced by the defaults entirely.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// First reconnect delay; doubled on every failed attempt
+    pub reconnect_base_ms: u64,
+    /// Upper bound for the reconnect delay
+    pub reconnect_max_ms: u64,
+    /// Failed reconnects before giving up; 0 retries forever
+    pub max_reconnect_attempts: u32,
+    /// How often the client pings the server
+    pub ping_interval_ms: u64,
+    /// Unanswered pings after which the connection is considered dead
+    pub max_missed_pings: u32,
+    /// Seconds of received messages kept for "Dump"
+    pub capture_secs: u64,
+    /// Browser console verbosity; a `?log=` query param overrides it
+    pub log_level: log::Level,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            reconnect_base_ms: RECONNECT_BASE_MS,
+            reconnect_max_ms: RECONNECT_MAX_MS,
+            max_reconnect_attempts: MAX_RECONNECT_ATTEMPTS,
+            ping_interval_ms: PING_INTERVAL_MS,
+            max_missed_pings: MAX_MISSED_PINGS,
+            capture_secs: CAPTURE_SECS,
+            log_level: LOG_LEVEL,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Parses the JSON shape above, falling back to the defaults on any error.
+    pub fn parse(json: &str) -> Self {
+        match serde_json::from_str::<Self>(json) {
+            Ok(config) if config.is_valid() => config,
+            Ok(config) => {
+                leptos::logging::warn!("ignoring invalid client config {:?}", config);
+                Self::default()
+            }
+            Err(e) => {
+                leptos::logging::warn!("ignoring malformed client config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Reads `CLIENT_CONFIG` on the server; defaults when unset.
+    pub fn from_env() -> Self {
+        std::env::var("CLIENT_CONFIG").map(|json| Self::parse(&json)).unwrap_or_default()
+    }
+
+    fn is_valid(&self) -> bool {
+        self.reconnect_base_ms > 0
+            && self.reconnect_max_ms >= self.reconnect_base_ms
+            && self.ping_interval_ms > 0
+            && self.max_missed_pings > 0
+            && self.capture_secs > 0
+    }
+}
+
+/// Config embedded by `shell()`; defaults when the tag is missing.
+#[cfg(feature = "hydrate")]
+fn read_client_config() -> ClientConfig {
+    document()
+        .get_element_by_id("leptos-client-config")
+        .and_then(|el| el.text_content())
+        .map(|json| ClientConfig::parse(&json))
+        .unwrap_or_default()
+}
+
+/// Console log level for `hydrate()`: the page's `?log=warn` (any
+/// `log::Level` name, case-insensitive), else the config's `log_level`. An
+/// unparsable param falls back to `Info`.
+#[cfg(feature = "hydrate")]
+pub fn console_log_level() -> log::Level {
+    let search = web_sys::window().and_then(|w| w.location().search().ok()).unwrap_or_default();
+    let param = search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("log="));
+    match param {
+        Some(name) => name.parse().unwrap_or(LOG_LEVEL),
+        None => read_client_config().log_level,
+    }
+}
+
+/// Data WebSocket URL from the `data-ws-url` attribute of the config tag
+/// (`WS_URL` on the server). `None` when unset or not a `ws://`/`wss://`
+/// URL, so the client falls back to `/ws` on the page's host.
+#[cfg(feature = "hydrate")]
+fn configured_ws_url() -> Option<String> {
+    let url = document().get_element_by_id("leptos-client-config")?.get_attribute("data-ws-url")?;
+    if url.starts_with("ws://") || url.starts_with("wss://") {
+        Some(url)
+    } else {
+        leptos::logging::warn!("ignoring WebSocket URL {:?}, expected ws:// or wss://", url);
+        None
+    }
+}
+
+/// Signals the WebSocket callbacks write into. All fields are `Copy`, so the
+/// context can be moved into every closure and into the reconnect timer.
+#[cfg(feature = "hydrate")]
+#[derive(Clone, Copy)]
+struct WsCtx {
+    prices: RwSignal<std::collections::HashMap<String, Vec<f64>>>,
+    price_times: RwSignal<std::collections::HashMap<String, Vec<i64>>>,
+    anomalies: RwSignal<std::collections::HashMap<String, f64>>,
+    trades: RwSignal<Vec<(String, f64, f64, String)>>,
+    trade_summaries: RwSignal<TradeSummaryMap>,
+    vwap_trades: RwSignal<TradeWindowMap>,
+    sma_fast: RwSignal<usize>,
+    sma_slow: RwSignal<usize>,
+    sma_trend: RwSignal<std::collections::HashMap<String, bool>>,
+    crossovers: RwSignal<Vec<Crossover>>,
+    alerts: RwSignal<std::collections::HashMap<String, Alert>>,
+    alert_flash: RwSignal<std::collections::HashMap<String, f64>>,
+    control_acks: RwSignal<std::collections::HashMap<String, f64>>,
+    burst_result: RwSignal<Option<(u64, u64)>>,
+    book_depth: RwSignal<BookMap>,
+    candles: RwSignal<CandleMap>,
+    vehicle_signals: RwSignal<VehicleSignalMap>,
+    can_buses: RwSignal<CanBusMap>,
+    msg_rate: RwSignal<Vec<f64>>,
+    fps_values: RwSignal<Vec<f64>>,
+    latency_values: RwSignal<Vec<f64>>,
+    msg_count: RwSignal<u64>,
+    msg_rate_timer: RwSignal<f64>,
+    bandwidth_values: RwSignal<Vec<f64>>,
+    byte_count: RwSignal<u64>,
+    dropped: RwSignal<u64>,
+    parse_errors: RwSignal<u64>,
+    unknown_msgs: RwSignal<u64>,
+    msg_types: RwSignal<std::collections::HashMap<String, u64>>,
+    parse_times: RwSignal<Vec<f64>>,
+    msgpack: RwSignal<bool>,
+    sample_max: RwSignal<usize>,
+    trade_cap: RwSignal<usize>,
+    agg_window: RwSignal<AggWindow>,
+    agg_fn: RwSignal<AggFn>,
+    conn_state: RwSignal<ConnState>,
+    subscribed: RwSignal<Vec<String>>,
+    batch_ms: RwSignal<u64>,
+    server_shutdown: RwSignal<bool>,
+    degraded_feeds: RwSignal<std::collections::HashSet<String>>,
+    available_symbols: RwSignal<Vec<String>>,
+    enabled_streams: RwSignal<Vec<String>>,
+    schema_mismatch: RwSignal<Option<u32>>,
+    rtt_values: RwSignal<Vec<f64>>,
+    clock_skew: RwSignal<Option<ClockSkew>>,
+    e2e_values: RwSignal<Vec<f64>>,
+    server_cpu: RwSignal<f64>,
+    server_mem_mb: RwSignal<u64>,
+    server_msg_rate: RwSignal<u64>,
+    missed_pings: RwSignal<u32>,
+    reconnect_attempt: RwSignal<u32>,
+    reconnect_pending: RwSignal<bool>,
+    resume_on_visible: RwSignal<bool>,
+    ws_generation: RwSignal<u64>,
+    send_backlog: RwSignal<(u32, usize)>, // bytes in `bufferedAmount`, control messages held back
+    config: ClientConfig, // read once at startup
+}
+
+/// Bytes the data socket may have queued for sending (`bufferedAmount`)
+/// before sends back off, e.g. on a slow uplink.
+const SEND_BUFFER_MAX: u32 = 64 * 1024;
+/// Control messages held back while the socket is backed up; further ones
+/// are dropped with a warning.
+#[cfg(feature = "hydrate")]
+const SEND_DEFERRED_MAX: usize = 64;
+/// How often held back messages are retried and the indicator refreshed.
+#[cfg(feature = "hydrate")]
+const SEND_RETRY_MS: u64 = 100;
+
+/// What a send does while more than [`SEND_BUFFER_MAX`] bytes are queued.
+#[cfg(feature = "hydrate")]
+#[derive(Clone, Copy)]
+enum Backpressure {
+    /// Hold it back and send it, in order, once the buffer has drained; for
+    /// control messages, which change state the server must see
+    Defer,
+    /// Skip it; for periodic messages the next one replaces, like pings
+    Drop,
+}
+
+#[cfg(feature = "hydrate")]
+thread_local! {
+    /// Control messages waiting for the send buffer to drain, oldest first
+    static SEND_DEFERRED: std::cell::RefCell<std::collections::VecDeque<String>> =
+        const { std::cell::RefCell::new(std::collections::VecDeque::new()) };
+}
+
+/// The data WebSocket, if one was opened.
+#[cfg(feature = "hydrate")]
+fn data_socket() -> Option<web_sys::WebSocket> {
+    let js_ws = js_sys::Reflect::get(web_sys::window()?.as_ref(), &js_sys::JsString::from("__leptos_ws")).ok()?;
+    js_ws.dyn_into::<web_sys::WebSocket>().ok()
+}
+
+/// Sends a raw control message over the currently open WebSocket, if any,
+/// deferring it while the socket is backed up.
+#[cfg(feature = "hydrate")]
+fn send_control(payload: &str) {
+    send_ws(payload, Backpressure::Defer);
+}
+
+/// Sends `payload` unless more than [`SEND_BUFFER_MAX`] bytes are still
+/// queued; deferred messages also wait while earlier ones are held back, so
+/// control messages keep their order.
+#[cfg(feature = "hydrate")]
+fn send_ws(payload: &str, policy: Backpressure) {
+    let Some(ws) = data_socket() else { return };
+    let backed_up = ws.buffered_amount() > SEND_BUFFER_MAX;
+    match policy {
+        Backpressure::Drop if backed_up => {}
+        Backpressure::Drop => { let _ = ws.send_with_str(payload); }
+        Backpressure::Defer => SEND_DEFERRED.with_borrow_mut(|deferred| {
+            if !backed_up && deferred.is_empty() {
+                let _ = ws.send_with_str(payload);
+            } else if deferred.len() < SEND_DEFERRED_MAX {
+                deferred.push_back(payload.to_string());
+            } else {
+                leptos::logging::warn!("send buffer backed up, dropping control message {}", payload);
+            }
+        }),
+    }
+}
+
+/// Sends held back control messages while the buffer allows and refreshes
+/// the backpressure indicator. Waits while the socket isn't open, so
+/// messages held back across a reconnect go out on the new connection.
+#[cfg(feature = "hydrate")]
+fn flush_deferred(ctx: WsCtx) {
+    let ws = data_socket().filter(|ws| ws.ready_state() == web_sys::WebSocket::OPEN);
+    let deferred = SEND_DEFERRED.with_borrow_mut(|deferred| {
+        if let Some(ws) = &ws {
+            while ws.buffered_amount() <= SEND_BUFFER_MAX {
+                let Some(payload) = deferred.pop_front() else { break };
+                let _ = ws.send_with_str(&payload);
+            }
+        }
+        deferred.len()
+    });
+    let backlog = (ws.map_or(0, |ws| ws.buffered_amount()), deferred);
+    if ctx.send_backlog.get_untracked() != backlog {
+        ctx.send_backlog.set(backlog);
+    }
+}
+
+/// Duration of the "applied" note after a control message's `ack`.
+const ACK_FADE_MS: u64 = 2000;
+
+/// How long a control message may go without an `ack` before the client
+/// warns about it.
+#[cfg(feature = "hydrate")]
+const ACK_TIMEOUT_MS: u64 = 2000;
+
+#[cfg(feature = "hydrate")]
+thread_local! {
+    /// Last `req_id` handed out
+    static LAST_REQ_ID: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+    /// Fields sent per control message still waiting for its `ack`
+    static PENDING_ACKS: std::cell::RefCell<std::collections::HashMap<u64, Vec<String>>> =
+        std::cell::RefCell::new(std::collections::HashMap::new());
+}
+
+/// Sends a control message (a JSON object) with a fresh `req_id` and warns
+/// if the server doesn't acknowledge it within [`ACK_TIMEOUT_MS`].
+#[cfg(feature = "hydrate")]
+fn send_tracked(mut payload: serde_json::Value) {
+    let Some(fields) = payload.as_object_mut() else { return };
+    let req_id = LAST_REQ_ID.with(|last| {
+        last.set(last.get() + 1);
+        last.get()
+    });
+    let names: Vec<String> = fields.keys().cloned().collect();
+    fields.insert("req_id".into(), req_id.into());
+    PENDING_ACKS.with_borrow_mut(|pending| pending.insert(req_id, names));
+    send_control(&payload.to_string());
+    set_timeout(
+        move || {
+            if let Some(names) = PENDING_ACKS.with_borrow_mut(|pending| pending.remove(&req_id)) {
+                leptos::logging::warn!(
+                    "control message {} ({}) not acknowledged within {} ms",
+                    req_id, names.join(", "), ACK_TIMEOUT_MS
+                );
+            }
+        },
+        std::time::Duration::from_millis(ACK_TIMEOUT_MS),
+    );
+}
+
+/// Price ticks per symbol requested from `/history` on startup.
+#[cfg(feature = "hydrate")]
+const HISTORY_LIMIT: usize = 200;
+
+/// Recent prices of `symbol` from the server's `/history`; `None` on any
+/// network or decoding error, or for symbols the server doesn't know.
+#[cfg(feature = "hydrate")]
+async fn fetch_history(symbol: &str) -> Option<PriceHistory> {
+    use wasm_bindgen_futures::JsFuture;
+
+    let url = format!("/history?symbol={}&limit={}", js_sys::encode_uri_component(symbol), HISTORY_LIMIT);
+    let response: web_sys::Response = JsFuture::from(web_sys::window()?.fetch_with_str(&url))
+        .await
+        .ok()?
+        .dyn_into()
+        .ok()?;
+    if !response.ok() {
+        return None;
+    }
+    let body = JsFuture::from(response.text().ok()?).await.ok()?.as_string()?;
+    serde_json::from_str(&body).ok()
+}
+
+/// Symbols the server currently streams, from its `/symbols`; `None` on any
+/// network or decoding error.
+#[cfg(feature = "hydrate")]
+async fn fetch_symbols() -> Option<Vec<SymbolInfo>> {
+    use wasm_bindgen_futures::JsFuture;
+
+    let response: web_sys::Response = JsFuture::from(web_sys::window()?.fetch_with_str("/symbols"))
+        .await
+        .ok()?
+        .dyn_into()
+        .ok()?;
+    if !response.ok() {
+        return None;
+    }
+    let body = JsFuture::from(response.text().ok()?).await.ok()?.as_string()?;
+    serde_json::from_str(&body).ok()
+}
+
+/// Takes the symbol list and decimals hints from `/symbols`. An empty list
+/// (e.g. replay mode, or no live stream connected yet) or a failed request
+/// falls back to `fallback`, and keeps the current list if that is empty too.
+#[cfg(feature = "hydrate")]
+async fn refresh_symbols(ctx: WsCtx, fallback: Vec<String>) {
+    let infos = fetch_symbols().await.unwrap_or_default();
+    price_format::set_decimals_hints(infos.iter().map(|i| (i.symbol.clone(), i.decimals)));
+    let symbols = if infos.is_empty() { fallback } else { infos.into_iter().map(|i| i.symbol).collect() };
+    if !symbols.is_empty() {
+        ctx.available_symbols.set(symbols);
+    }
+}
+
+/// Replaces the price buffers with the server's history so the charts start
+/// filled; symbols without history keep what they have.
+#[cfg(feature = "hydrate")]
+async fn backfill_prices(ctx: WsCtx) {
+    for symbol in ctx.available_symbols.get_untracked() {
+        let Some(history) = fetch_history(&symbol).await else { continue };
+        if history.prices.is_empty() {
+            continue;
+        }
+        let (mut times, mut prices): (Vec<i64>, Vec<f64>) = history.prices.into_iter().unzip();
+        trim_to_cap(&mut times, ctx.sample_max.get_untracked());
+        trim_to_cap(&mut prices, ctx.sample_max.get_untracked());
+        ctx.price_times.update(|map| {
+            map.insert(history.symbol.clone(), times);
+        });
+        ctx.prices.update(|map| {
+            map.insert(history.symbol, prices);
+        });
+    }
+}
+
+/// `localStorage` key of the saved session.
+#[cfg(feature = "hydrate")]
+const SNAPSHOT_KEY: &str = "leptos-rust.snapshot";
+/// Bump when `AppSnapshot` changes; older snapshots are discarded on load.
+#[cfg(feature = "hydrate")]
+const SNAPSHOT_VERSION: u32 = 1;
+/// Samples kept per buffer in a snapshot.
+#[cfg(feature = "hydrate")]
+const SNAPSHOT_MAX_SAMPLES: usize = 500;
+/// Snapshots larger than this (JSON bytes) aren't saved.
+#[cfg(feature = "hydrate")]
+const SNAPSHOT_MAX_BYTES: usize = 1_000_000;
+/// How often aggregation windows without further ticks are checked.
+#[cfg(feature = "hydrate")]
+const AGG_FLUSH_MS: u64 = 100;
+
+/// How often the session is saved, if new messages arrived.
+#[cfg(feature = "hydrate")]
+const SNAPSHOT_INTERVAL_MS: u64 = 5_000;
+
+/// Chart data and metric buffers saved to `localStorage`, so a refresh
+/// resumes the session instead of starting from empty charts.
+#[cfg(feature = "hydrate")]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct AppSnapshot {
+    version: u32,
+    prices: std::collections::HashMap<String, Vec<f64>>,
+    #[serde(default)]
+    price_times: std::collections::HashMap<String, Vec<i64>>,
+    trades: Vec<(String, f64, f64, String)>,
+    book_depth: BookMap,
+    msg_rate: Vec<f64>,
+    latency_values: Vec<f64>,
+    fps_values: Vec<f64>,
+    rtt_values: Vec<f64>,
+}
+
+#[cfg(feature = "hydrate")]
+impl AppSnapshot {
+    fn capture(ctx: WsCtx) -> Self {
+        fn tail<T: Clone>(v: &[T]) -> Vec<T> {
+            v[v.len().saturating_sub(SNAPSHOT_MAX_SAMPLES)..].to_vec()
+        }
+        Self {
+            version: SNAPSHOT_VERSION,
+            prices: ctx.prices.read_untracked().iter().map(|(s, v)| (s.clone(), tail(v))).collect(),
+            price_times: ctx.price_times.read_untracked().iter().map(|(s, v)| (s.clone(), tail(v))).collect(),
+            trades: tail(&ctx.trades.read_untracked()),
+            book_depth: ctx.book_depth.get_untracked(),
+            msg_rate: tail(&ctx.msg_rate.read_untracked()),
+            latency_values: tail(&ctx.latency_values.read_untracked()),
+            fps_values: tail(&ctx.fps_values.read_untracked()),
+            rtt_values: tail(&ctx.rtt_values.read_untracked()),
+        }
+    }
+
+    fn restore(self, ctx: WsCtx) {
+        ctx.prices.set(self.prices);
+        ctx.price_times.set(self.price_times);
+        ctx.trades.set(self.trades);
+        ctx.book_depth.set(self.book_depth);
+        ctx.msg_rate.set(self.msg_rate);
+        ctx.latency_values.set(self.latency_values);
+        ctx.fps_values.set(self.fps_values);
+        ctx.rtt_values.set(self.rtt_values);
+    }
+}
+
+#[cfg(feature = "hydrate")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+/// localStorage key of the selected theme, `"light"` or `"dark"`
+#[cfg(feature = "hydrate")]
+const THEME_KEY: &str = "leptos-rust.theme";
+
+#[cfg(feature = "hydrate")]
+fn load_theme() -> Option<Theme> {
+    let name = local_storage()?.get_item(THEME_KEY).ok().flatten()?;
+    Some(Theme::from_name(&name))
+}
+
+#[cfg(feature = "hydrate")]
+fn save_theme(theme: Theme) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(THEME_KEY, theme.name());
+    }
+}
+
+/// localStorage key of the vehicle signal units, `"metric"` or `"imperial"`
+#[cfg(feature = "hydrate")]
+const UNITS_KEY: &str = "leptos-rust.units";
+
+#[cfg(feature = "hydrate")]
+fn load_units() -> Option<UnitSystem> {
+    let name = local_storage()?.get_item(UNITS_KEY).ok().flatten()?;
+    Some(UnitSystem::from_name(&name))
+}
+
+#[cfg(feature = "hydrate")]
+fn save_units(units: UnitSystem) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(UNITS_KEY, units.name());
+    }
+}
+
+/// Page background outside `<main>`, which the view doesn't own
+#[cfg(feature = "hydrate")]
+fn apply_body_theme(theme: Theme) {
+    if let Some(body) = web_sys::window().and_then(|w| w.document()).and_then(|d| d.body()) {
+        // `--focus` colors the keyboard focus ring of style/main.scss
+        let _ = body.set_attribute("style", &format!("background:{};color:{};--focus:{};", theme.background, theme.text, theme.accent));
+    }
+}
+
+/// Saves the current session. Oversized snapshots and storage errors (e.g.
+/// the quota is exceeded) drop the saved session instead, so a refresh
+/// never restores an outdated one.
+#[cfg(feature = "hydrate")]
+fn save_state(ctx: WsCtx) {
+    let Some(storage) = local_storage() else { return };
+    let json = match serde_json::to_string(&AppSnapshot::capture(ctx)) {
+        Ok(json) if json.len() <= SNAPSHOT_MAX_BYTES => json,
+        Ok(json) => {
+            leptos::logging::warn!("session snapshot too large ({} bytes), not saved", json.len());
+            let _ = storage.remove_item(SNAPSHOT_KEY);
+            return;
+        }
+        Err(_) => return,
+    };
+    if let Err(e) = storage.set_item(SNAPSHOT_KEY, &json) {
+        leptos::logging::warn!("could not save session snapshot: {:?}", e);
+        let _ = storage.remove_item(SNAPSHOT_KEY);
+    }
+}
+
+/// The saved session, if there is one in the current format. Unreadable or
+/// outdated snapshots are removed.
+#[cfg(feature = "hydrate")]
+fn load_state() -> Option<AppSnapshot> {
+    let storage = local_storage()?;
+    let json = storage.get_item(SNAPSHOT_KEY).ok().flatten()?;
+    match serde_json::from_str::<AppSnapshot>(&json) {
+        Ok(snapshot) if snapshot.version == SNAPSHOT_VERSION => Some(snapshot),
+        _ => {
+            let _ = storage.remove_item(SNAPSHOT_KEY);
+            None
+        }
+    }
+}
+
+#[cfg(feature = "hydrate")]
+fn subscribe_payload(symbols: &[String]) -> String {
+    serde_json::json!({ "subscribe": symbols }).to_string()
+}
+
+/// Serializes the captured buffers into one combined CSV.
+///
+/// Every row carries a `kind` column (`price`, `trade` or `latency`) so the
+/// sections can be split again with a simple filter; columns that don't apply
+/// to a kind are left empty:
+///
+/// ```text
+/// kind,symbol,index,value,side
+/// price,BTC/USD,0,45012.30,
+/// trade,ETH/USD,,2501.23,buy
+/// latency,,0,3.41,
+/// ```
+#[cfg(feature = "hydrate")]
+fn export_csv(
+    prices: &std::collections::HashMap<String, Vec<f64>>,
+    trades: &[(String, f64, f64, String)],
+    latency: &[f64],
+) -> String {
+    use std::fmt::Write;
+    let mut out = String::from("kind,symbol,index,value,side\n");
+    let mut symbols: Vec<_> = prices.keys().collect();
+    symbols.sort();
+    for symbol in symbols {
+        for (i, v) in prices[symbol].iter().enumerate() {
+            let _ = writeln!(out, "price,{},{},{},", symbol, i, v);
+        }
+    }
+    for (symbol, price, _, side) in trades {
+        let _ = writeln!(out, "trade,{},,{},{}", symbol, price, side);
+    }
+    for (i, v) in latency.iter().enumerate() {
+        let _ = writeln!(out, "latency,,{},{},", i, v);
+    }
+    out
+}
+
+/// Offers `contents` as a file download via a Blob object URL.
+#[cfg(feature = "hydrate")]
+fn download_file(filename: &str, mime: &str, contents: &str) {
+    let parts = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(contents));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let Ok(blob) = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options) else { return };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else { return };
+    if let Ok(anchor) = document().create_element("a") {
+        if let Ok(anchor) = anchor.dyn_into::<web_sys::HtmlAnchorElement>() {
+            anchor.set_href(&url);
+            anchor.set_download(filename);
+            anchor.click();
+        }
+    }
+    // Revoking synchronously can cancel the download in some browsers
+    set_timeout(move || { let _ = web_sys::Url::revoke_object_url(&url); }, std::time::Duration::from_secs(1));
+}
+
+/// Upper bound of the capture buffer, whatever `capture_secs` allows.
+#[cfg(feature = "hydrate")]
+const CAPTURE_MAX_MESSAGES: usize = 100_000;
+
+#[cfg(feature = "hydrate")]
+thread_local! {
+    /// Received messages as JSON lines with their receive time (ms, as
+    /// `performance.now()`), oldest first
+    static CAPTURE: std::cell::RefCell<std::collections::VecDeque<(f64, String)>> =
+        const { std::cell::RefCell::new(std::collections::VecDeque::new()) };
+}
+
+/// Messages that only make sense on the connection they were sent on. They
+/// are left out of the capture since a replay broadcasts to every client.
+#[cfg(feature = "hydrate")]
+fn per_connection(msg: &WireMessage) -> bool {
+    matches!(
+        msg,
+        WireMessage::Hello { .. }
+            | WireMessage::Pong { .. }
+            | WireMessage::Ack { .. }
+            | WireMessage::Error { .. }
+            | WireMessage::Lag { .. }
+            | WireMessage::Shutdown
+    )
+}
+
+/// Adds `lines` received at `t_recv` and drops what is older than
+/// `capture_secs` or beyond [`CAPTURE_MAX_MESSAGES`].
+#[cfg(feature = "hydrate")]
+fn capture_lines(lines: impl IntoIterator<Item = String>, t_recv: f64, capture_secs: u64) {
+    CAPTURE.with_borrow_mut(|buf| {
+        buf.extend(lines.into_iter().map(|line| (t_recv, line)));
+        let oldest = t_recv - capture_secs as f64 * 1000.0;
+        while buf.front().is_some_and(|(t, _)| *t < oldest) || buf.len() > CAPTURE_MAX_MESSAGES {
+            buf.pop_front();
+        }
+    });
+}
+
+/// Captured messages of the last `capture_secs` as newline-delimited JSON,
+/// the format `REPLAY_FILE` reads.
+#[cfg(feature = "hydrate")]
+fn capture_ndjson(capture_secs: u64) -> String {
+    let now = web_sys::window().unwrap().performance().unwrap().now();
+    let oldest = now - capture_secs as f64 * 1000.0;
+    CAPTURE.with_borrow(|buf| {
+        buf.iter()
+            .filter(|(t, _)| *t >= oldest)
+            .flat_map(|(_, line)| [line.as_str(), "\n"])
+            .collect()
+    })
+}
+
+#[cfg(feature = "hydrate")]
+fn page_hidden() -> bool {
+    document().visibility_state() == web_sys::VisibilityState::Hidden
+}
+
+/// Exponential backoff (500ms, 1s, 2s, ... capped at 30s by default) plus up
+/// to 20% jitter so that many tabs don't hammer a restarted server in lockstep.
+#[cfg(feature = "hydrate")]
+fn backoff_delay_ms(config: &ClientConfig, attempt: u32) -> u64 {
+    let base = (config.reconnect_base_ms as f64 * 2f64.powi(attempt.min(16) as i32))
+        .min(config.reconnect_max_ms as f64);
+    let jitter = base * 0.2 * js_sys::Math::random();
+    (base + jitter) as u64
+}
+
+/// Schedules a reconnect after the current backoff delay. While the page is
+/// hidden no attempts are made; the `visibilitychange` listener resumes them.
+/// After `max_reconnect_attempts` failed attempts the client stays
+/// [`ConnState::Disconnected`] until [`reconnect_now`].
+#[cfg(feature = "hydrate")]
+fn schedule_reconnect(ctx: WsCtx) {
+    if ctx.reconnect_pending.get_untracked() || ctx.resume_on_visible.get_untracked() {
+        ctx.conn_state.set(ConnState::Reconnecting);
+        return;
+    }
+    let attempt = ctx.reconnect_attempt.get_untracked();
+    let max = ctx.config.max_reconnect_attempts;
+    if max > 0 && attempt >= max {
+        leptos::logging::warn!("giving up after {} reconnect attempts", attempt);
+        ctx.conn_state.set(ConnState::Disconnected);
+        return;
+    }
+    ctx.conn_state.set(ConnState::Reconnecting);
+    if page_hidden() {
+        ctx.resume_on_visible.set(true);
+        return;
+    }
+    ctx.reconnect_attempt.set(attempt.saturating_add(1));
+    ctx.reconnect_pending.set(true);
+    // A socket opened meanwhile (e.g. by `reconnect_now`) cancels this attempt
+    let generation = ctx.ws_generation.get_untracked();
+    set_timeout(
+        move || {
+            if ctx.ws_generation.get_untracked() != generation {
+                return;
+            }
+            ctx.reconnect_pending.set(false);
+            if page_hidden() {
+                ctx.resume_on_visible.set(true);
+            } else {
+                connect_ws(ctx);
+            }
+        },
+        std::time::Duration::from_millis(backoff_delay_ms(&ctx.config, attempt)),
+    );
+}
+
+#[cfg(feature = "hydrate")]
+type RafClosure = wasm_bindgen::closure::Closure<dyn FnMut(f64)>;
+
+#[cfg(feature = "hydrate")]
+thread_local! {
+    /// Receive times of price messages waiting for the next paint
+    static LATENCY_PENDING: std::cell::RefCell<Vec<f64>> = const { std::cell::RefCell::new(Vec::new()) };
+    /// Single rAF callback shared by all messages and connections
+    static LATENCY_RAF: std::cell::RefCell<Option<RafClosure>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Records receive-to-paint latency for a price message. Receive times are
+/// queued and one persistent rAF closure turns them into samples on the next
+/// frame, instead of allocating (and leaking) a closure per message.
+#[cfg(feature = "hydrate")]
+fn track_paint_latency(ctx: WsCtx, t_recv: f64) {
+    use wasm_bindgen::closure::Closure;
+
+    let cap = ctx.sample_max.get_untracked();
+    // A non-empty queue means a frame is already requested
+    let first = LATENCY_PENDING.with_borrow_mut(|pending| {
+        // Hidden tabs don't paint; don't let the queue grow without bound
+        push_capped(pending, t_recv, cap);
+        pending.len() == 1
+    });
+    if !first { return; }
+
+    LATENCY_RAF.with_borrow_mut(|cb| {
+        let cb = cb.get_or_insert_with(|| Closure::wrap(Box::new(move |_: f64| {
+            let t_paint = web_sys::window().unwrap().performance().unwrap().now();
+            let pending = LATENCY_PENDING.with_borrow_mut(std::mem::take);
+            let cap = *ctx.sample_max.read();
+            let mut lv = ctx.latency_values.write();
+            for t in pending {
+                push_capped(&mut lv, t_paint - t, cap);
+            }
+        }) as Box<dyn FnMut(f64)>));
+        if web_sys::window().unwrap().request_animation_frame(cb.as_ref().unchecked_ref()).is_err() {
+            LATENCY_PENDING.with_borrow_mut(Vec::clear);
+        }
+    });
+}
+
+/// Closes the current socket without its handlers scheduling a reconnect,
+/// so the caller decides when to connect again. A pending reconnect is
+/// cancelled as well.
+#[cfg(feature = "hydrate")]
+fn close_socket(ctx: WsCtx) {
+    ctx.ws_generation.update(|g| *g += 1);
+    ctx.reconnect_pending.set(false);
+    if let Some(ws) = data_socket() {
+        let _ = ws.close();
+    }
+}
+
+/// Replaces the socket right away, cancelling a pending reconnect and
+/// starting the backoff over. Also works once the attempts ran out.
+#[cfg(feature = "hydrate")]
+fn reconnect_now(ctx: WsCtx) {
+    close_socket(ctx);
+    ctx.resume_on_visible.set(false);
+    ctx.reconnect_attempt.set(0);
+    ctx.missed_pings.set(0);
+    connect_ws(ctx);
+}
+
+/// Sends a ping every `ping_interval_ms`; after `max_missed_pings`
+/// unanswered pings the socket is treated as half-open and replaced. While
+/// the send buffer is backed up the ping is skipped but still counts as
+/// missed, so an uplink that stays stuck leads to a reconnect too.
+#[cfg(feature = "hydrate")]
+fn heartbeat_tick(ctx: WsCtx) {
+    if ctx.conn_state.get_untracked() != ConnState::Connected {
+        return;
+    }
+    if ctx.missed_pings.get_untracked() >= ctx.config.max_missed_pings {
+        leptos::logging::warn!("no pong for {} pings, reconnecting", ctx.config.max_missed_pings);
+        ctx.missed_pings.set(0);
+        close_socket(ctx);
+        schedule_reconnect(ctx);
+        return;
+    }
+    ctx.missed_pings.update(|m| *m += 1);
+    let ts = web_sys::window().unwrap().performance().unwrap().now();
+    send_ws(&serde_json::json!({ "type": "ping", "ts": ts }).to_string(), Backpressure::Drop);
+}
+
+/// Opens the data WebSocket and wires its handlers. Called once on startup and
+/// again from [`schedule_reconnect`] whenever the connection drops.
+#[cfg(feature = "hydrate")]
+fn connect_ws(ctx: WsCtx) {
+    use web_sys::{MessageEvent, WebSocket};
+    use wasm_bindgen::closure::Closure;
+
+    let window = web_sys::window().expect("window");
+    let base_url = configured_ws_url().unwrap_or_else(|| {
+        let location = window.location();
+        let host = location.host().unwrap_or_else(|_| "127.0.0.1:3000".into());
+        let protocol = location
+            .protocol()
+            .ok()
+            .filter(|p| p.starts_with("https"))
+            .map(|_| "wss")
+            .unwrap_or("ws");
+        format!("{}://{}/ws", protocol, host)
+    });
+    let ws_url = match (ctx.msgpack.get_untracked(), base_url.contains('?')) {
+        (false, _) => base_url,
+        (true, false) => format!("{}?fmt=msgpack", base_url),
+        (true, true) => format!("{}&fmt=msgpack", base_url),
+    };
+
+    // Handlers of a superseded socket must not trigger another reconnect
+    ctx.ws_generation.update(|g| *g += 1);
+    let generation = ctx.ws_generation.get_untracked();
+    if ctx.reconnect_attempt.get_untracked() == 0 {
+        ctx.conn_state.set(ConnState::Connecting);
+    }
+
+    let Ok(ws) = WebSocket::new(&ws_url) else {
+        schedule_reconnect(ctx);
+        return;
+    };
+    // MessagePack frames arrive as binary; decode them from an ArrayBuffer
+    ws.set_binary_type(web_sys::BinaryType::Arraybuffer);
+
+    let _ = js_sys::Reflect::set(
+        window.as_ref(), 
+        &js_sys::JsString::from("__leptos_ws"), 
+        ws.as_ref()
+    );
+
+    let onopen = Closure::wrap(Box::new(move |_: web_sys::Event| {
+        if ctx.ws_generation.get_untracked() == generation {
+            ctx.conn_state.set(ConnState::Connected);
+            ctx.missed_pings.set(0);
+            ctx.server_shutdown.set(false);
+            // Recoveries while away were missed; a still failing feed is
+            // reported again on its next retry
+            ctx.degraded_feeds.update(|feeds| feeds.clear());
+            // The server forgets the subscription together with the old connection
+            let subscribed = ctx.subscribed.get_untracked();
+            if !subscribed.is_empty() {
+                send_control(&subscribe_payload(&subscribed));
+            }
+            let batch_ms = ctx.batch_ms.get_untracked();
+            if batch_ms > 0 {
+                send_control(&format!("{{\"batch_ms\":{}}}", batch_ms));
+            }
+            // Ping right away so the clock skew is known before the first interval
+            heartbeat_tick(ctx);
+        }
+    }) as Box<dyn FnMut(_)>);
+    let _ = ws.add_event_listener_with_callback("open", onopen.as_ref().unchecked_ref());
+    onopen.forget();
+
+    let ondrop = Closure::wrap(Box::new(move |_: web_sys::Event| {
+        if ctx.ws_generation.get_untracked() == generation {
+            schedule_reconnect(ctx);
+        }
+    }) as Box<dyn FnMut(_)>);
+    let _ = ws.add_event_listener_with_callback("close", ondrop.as_ref().unchecked_ref());
+    let _ = ws.add_event_listener_with_callback("error", ondrop.as_ref().unchecked_ref());
+    ondrop.forget();
+
+    let WsCtx { msg_rate: msg_rate_sig, msg_count, msg_rate_timer, bandwidth_values, byte_count, sample_max, .. } = ctx;
+
+    let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
+        if ctx.reconnect_attempt.get_untracked() != 0 {
+            ctx.reconnect_attempt.set(0);
+            ctx.conn_state.set(ConnState::Connected);
+        }
+        let performance = web_sys::window().unwrap().performance().unwrap();
+        let t_recv = performance.now();
+        let data = e.data();
+        let text = data.as_string();
+        // Wire size before any decoding, so JSON and MessagePack compare fairly
+        let frame_bytes = match &text {
+            Some(txt) => txt.len(),
+            None => data.dyn_ref::<js_sys::ArrayBuffer>().map_or(0, |buf| buf.byte_length() as usize),
+        };
+        byte_count.update(|b| *b += frame_bytes as u64);
+        let parsed = if let Some(txt) = &text {
+            serde_json::from_str::<WireMessage>(txt).map_err(|err| {
+                let payload: String = txt.chars().take(200).collect();
+                format!("{} in {}", err, payload)
+            })
+        } else if let Ok(buf) = data.dyn_into::<js_sys::ArrayBuffer>() {
+            let bytes = js_sys::Uint8Array::new(&buf).to_vec();
+            rmp_serde::from_slice::<WireMessage>(&bytes)
+                .map_err(|err| format!("{} in {} byte MessagePack frame", err, bytes.len()))
+        } else {
+            Err("unsupported frame type".to_string())
+        };
+        let parse_ms = performance.now() - t_recv;
+        ctx.parse_times.update(|v| push_capped(v, parse_ms, sample_max.get_untracked()));
+        // Single JSON messages and unparseable text are kept verbatim, batch
+        // and MessagePack frames re-encoded as one JSON line per message
+        let reencode = |items: &[WireMessage]| -> Vec<String> {
+            items.iter().filter(|m| !per_connection(m)).filter_map(|m| serde_json::to_string(m).ok()).collect()
+        };
+        let captured = match (&parsed, text) {
+            (Ok(WireMessage::Batch { items }), _) => reencode(items),
+            (Ok(msg), None) => reencode(std::slice::from_ref(msg)),
+            (Ok(msg), Some(_)) if per_connection(msg) => Vec::new(),
+            (_, Some(txt)) => vec![txt],
+            (Err(_), None) => Vec::new(),
+        };
+        capture_lines(captured, t_recv, ctx.config.capture_secs);
+        // A batch frame counts as all of its items
+        let msgs = match parsed {
+            Ok(WireMessage::Batch { items }) => items,
+            Ok(msg) => vec![msg],
+            Err(err) => {
+                ctx.parse_errors.update(|n| *n += 1);
+                leptos::logging::warn!("unparseable message: {}", err);
+                Vec::new()
+            }
+        };
+        msg_count.update(|c| *c += msgs.len().max(1) as u64);
+        let last_time = *msg_rate_timer.read();
+        if last_time == 0.0 {
+            *msg_rate_timer.write() = t_recv;
+        } else if t_recv - last_time >= 1000.0 {
+            let current_rate = *msg_count.read() as f64 * 1000.0 / (t_recv - last_time);
+            let current_bandwidth = *byte_count.read() as f64 * 1000.0 / (t_recv - last_time);
+            let cap = *sample_max.read();
+            msg_rate_sig.update(|v| push_capped(v, current_rate, cap));
+            bandwidth_values.update(|v| push_capped(v, current_bandwidth, cap));
+            *msg_count.write() = 0;
+            *byte_count.write() = 0;
+            *msg_rate_timer.write() = t_recv;
+        }
+        apply_msgs(ctx, msgs, t_recv);
+    }) as Box<dyn FnMut(_)>);
+    let _ = ws.add_event_listener_with_callback("message", onmessage.as_ref().unchecked_ref());
+    onmessage.forget();
+}
+
+/// Appends `item` and drops the oldest entries so at most `cap` remain
+/// (also after `cap` was lowered).
+#[cfg(feature = "hydrate")]
+fn push_capped<T>(v: &mut Vec<T>, item: T, cap: usize) {
+    v.push(item);
+    trim_to_cap(v, cap);
+}
+
+/// Drops the oldest entries so at most `cap` remain; never pads.
+fn trim_to_cap<T>(v: &mut Vec<T>, cap: usize) {
+    let extra = v.len().saturating_sub(cap);
+    if extra > 0 {
+        v.drain(0..extra);
+    }
+}
+
+#[cfg(feature = "hydrate")]
+thread_local! {
+    /// Open price window per symbol while ticks are aggregated
+    static PRICE_AGG: std::cell::RefCell<aggregate::PriceAggregator> =
+        std::cell::RefCell::new(aggregate::PriceAggregator::default());
+}
+
+/// Applies received messages, touching each signal at most once so a batch
+/// frame costs one update per signal instead of one per item.
+#[cfg(feature = "hydrate")]
+fn apply_msgs(ctx: WsCtx, msgs: Vec<WireMessage>, t_recv: f64) {
+    let cap = ctx.sample_max.get_untracked();
+    let recv_wall_ms = web_sys::window().unwrap().performance().unwrap().time_origin() + t_recv;
+    // Windows no later tick closed, e.g. of a paused or unsubscribed symbol,
+    // against the server clock the tick timestamps come from
+    let server_now_us = (recv_wall_ms + ctx.clock_skew.get_untracked().map_or(0.0, |s| s.offset_ms)) * 1000.0;
+    let mut new_prices = PRICE_AGG.with_borrow_mut(|agg| agg.flush_stale(server_now_us as i64));
+    let mut new_trades = Vec::new();
+    let mut new_books = Vec::new();
+    let mut new_candles = Vec::new();
+    let mut new_summaries = Vec::new();
+    let mut new_signals = Vec::new();
+    let mut new_can = Vec::new();
+    let mut new_e2e = Vec::new(); // server `ts` of messages to measure
+    let mut skipped = 0;
+    let mut type_counts = std::collections::HashMap::<&'static str, u64>::new();
+    let (agg_window, agg_fn) = (ctx.agg_window.get_untracked(), ctx.agg_fn.get_untracked());
+    for msg in msgs {
+        *type_counts.entry(msg.type_name()).or_default() += 1;
+        match msg {
+            WireMessage::Price { symbol, price, ts, .. } => {
+                new_e2e.push(ts);
+                // Aggregated, only a finished window reaches the charts
+                if let Some((price, ts)) = PRICE_AGG.with_borrow_mut(|agg| agg.push(agg_window, agg_fn, &symbol, price, ts)) {
+                    new_prices.push((symbol, price, ts));
+                }
+            }
+            WireMessage::Trade { symbol, price, size, side, ts, .. } => {
+                new_e2e.push(ts);
+                new_trades.push((symbol, price, size, side));
+            }
+            WireMessage::TradeSummary { symbol, count, volume, vwap, buy_ratio, ts } => {
+                new_summaries.push((symbol, TradeSummary { count, volume, vwap, buy_ratio, ts }));
+            }
+            WireMessage::Book { symbol, bids, asks, ts } => {
+                new_e2e.push(ts);
+                new_books.push((symbol, bids, asks));
+            }
+            WireMessage::Candle { symbol, open, high, low, close, volume, ts } => {
+                new_candles.push((symbol, Candle { open, high, low, close, volume, ts }));
+            }
+            WireMessage::VehicleSignal { signal_name, value, unit, source_id, extended, .. } => {
+                new_signals.push((signal_name, value, unit, format_can_id(source_id, extended)));
+            }
+            WireMessage::Lag { skipped: n, .. } => skipped += n,
+            WireMessage::Shutdown => ctx.server_shutdown.set(true),
+            WireMessage::Pong { ts, server_ts } => {
+                ctx.missed_pings.set(0);
+                let rtt = t_recv - ts;
+                ctx.rtt_values.update(|v| push_capped(v, rtt, cap));
+                // Keep the estimate with the tightest error bound
+                if server_ts != 0 && ctx.clock_skew.get_untracked().is_none_or(|s| rtt < s.rtt_ms) {
+                    ctx.clock_skew.set(Some(ClockSkew::estimate(server_ts, recv_wall_ms, rtt)));
+                }
+            }
+            WireMessage::Hello { schema_version, symbols } => {
+                if schema_version != SCHEMA_VERSION {
+                    leptos::logging::warn!(
+                        "server speaks message schema v{}, this client expects v{}; some data may not display",
+                        schema_version, SCHEMA_VERSION
+                    );
+                    ctx.schema_mismatch.set(Some(schema_version));
+                } else {
+                    ctx.schema_mismatch.set(None);
+                }
+                // Live mode lists only the connected markets on /symbols, which
+                // may have changed while we were away
+                leptos::task::spawn_local(refresh_symbols(ctx, symbols));
+            }
+            WireMessage::Streams { enabled } => ctx.enabled_streams.set(enabled),
+            WireMessage::Upstream { status, symbol } => ctx.degraded_feeds.update(|feeds| {
+                if status == "degraded" {
+                    feeds.insert(symbol);
+                } else {
+                    feeds.remove(&symbol);
+                }
+            }),
+            WireMessage::Error { msg, code } => {
+                leptos::logging::warn!("server rejected a control message: {} ({})", msg, code);
+                // Our list may be stale, e.g. a live market was unsubscribed
+                if ErrorCode::from_code(code) == Some(ErrorCode::UnknownSymbol) {
+                    leptos::task::spawn_local(refresh_symbols(ctx, Vec::new()));
+                }
+            }
+            WireMessage::Ack { req_id, applied } => {
+                let sent = PENDING_ACKS.with_borrow_mut(|pending| pending.remove(&req_id)).unwrap_or_default();
+                let ignored: Vec<_> = sent.iter().filter(|field| !applied.contains_key(*field)).collect();
+                if !ignored.is_empty() {
+                    leptos::logging::warn!("server ignored {:?} of control message {}", ignored, req_id);
+                }
+                // A burst is acked again when it's over, with the ticks sent
+                if let Some(burst) = applied.get("burst").filter(|b| b.get("sent").is_some()) {
+                    let count = |field| burst.get(field).and_then(serde_json::Value::as_u64).unwrap_or_default();
+                    ctx.burst_result.set(Some((count("sent"), count("messages"))));
+                }
+                let now = now_ms();
+                ctx.control_acks.update(|acks| {
+                    for field in applied.keys() {
+                        acks.insert(field.clone(), now);
+                    }
+                });
+            }
+            WireMessage::System { cpu_pct, mem_mb, msg_rate, .. } => {
+                ctx.server_cpu.set(cpu_pct);
+                ctx.server_mem_mb.set(mem_mb);
+                ctx.server_msg_rate.set(msg_rate);
+            }
+            // The server never nests batches
+            msg @ (WireMessage::CanFrame { .. } | WireMessage::BusStatus { .. } | WireMessage::CanStats { .. }) => {
+                new_can.push(msg);
+            }
+            WireMessage::Batch { .. } => {}
+            // Valid JSON, but a type this client doesn't know
+            WireMessage::Other => ctx.unknown_msgs.update(|n| *n += 1),
+        }
+    }
+
+    if !type_counts.is_empty() {
+        ctx.msg_types.update(|map| {
+            for (name, n) in type_counts {
+                *map.entry(name.to_string()).or_default() += n;
+            }
+        });
+    }
+    if !new_prices.is_empty() {
+        for _ in &new_prices {
+            track_paint_latency(ctx, t_recv);
+        }
+        let mut fired = Vec::new();
+        ctx.alerts.update_untracked(|alerts| {
+            for (symbol, price, _) in &new_prices {
+                if let Some(alert) = alerts.get_mut(symbol) {
+                    if alert.check(*price) {
+                        fired.push((symbol.clone(), *alert, *price));
+                    }
+                }
+            }
+        });
+        if !fired.is_empty() {
+            let now = now_ms();
+            ctx.alert_flash.update(|map| {
+                for (symbol, alert, price) in fired {
+                    notify_alert(&symbol, &alert, price);
+                    map.insert(symbol, now);
+                }
+            });
+        }
+        ctx.price_times.update(|map| {
+            for (symbol, _, ts) in &new_prices {
+                push_capped(map.entry(symbol.clone()).or_default(), *ts, cap);
+            }
+        });
+        let mut flagged = Vec::new();
+        let mut crossed = Vec::new();
+        let (fast, slow) = (ctx.sma_fast.get_untracked(), ctx.sma_slow.get_untracked());
+        ctx.prices.update(|map| {
+            ctx.sma_trend.update_untracked(|trend| {
+                for (symbol, price, ts) in new_prices {
+                    let history = map.entry(symbol.clone()).or_default();
+                    let recent = &history[history.len().saturating_sub(ANOMALY_WINDOW)..];
+                    if detect_anomaly(recent, price, ANOMALY_Z) {
+                        flagged.push(symbol.clone());
+                    }
+                    push_capped(history, price, cap);
+                    if let (Some(f), Some(s)) = (sma(history, fast), sma(history, slow)) {
+                        let above = f > s;
+                        if trend.insert(symbol.clone(), above) == Some(!above) {
+                            crossed.push(Crossover { symbol, buy: above, price, ts });
+                        }
+                    }
+                }
+            });
+        });
+        if !crossed.is_empty() {
+            ctx.crossovers.update(|log| {
+                for crossover in crossed {
+                    push_capped(log, crossover, CROSSOVER_LOG_MAX);
+                }
+            });
+        }
+        if !flagged.is_empty() {
+            let now = now_ms();
+            ctx.anomalies.update(|map| {
+                for symbol in flagged {
+                    map.insert(symbol, now);
+                }
+            });
+        }
+    }
+    if !new_trades.is_empty() {
+        // The VWAP window follows the chart history length
+        ctx.vwap_trades.update(|map| {
+            for (symbol, price, size, _) in &new_trades {
+                push_capped(map.entry(symbol.clone()).or_default(), (*price, *size), cap);
+            }
+        });
+        let trade_cap = ctx.trade_cap.get_untracked();
+        ctx.trades.update(|t| {
+            for trade in new_trades {
+                push_capped(t, trade, trade_cap);
+            }
+        });
+    }
+    if !new_books.is_empty() {
+        ctx.book_depth.update(|map| {
+            for (symbol, bids, asks) in new_books {
+                map.insert(symbol, (bids, asks));
+            }
+        });
+    }
+    if !new_summaries.is_empty() {
+        ctx.trade_summaries.update(|map| map.extend(new_summaries));
+    }
+    if !new_candles.is_empty() {
+        ctx.candles.update(|map| {
+            for (symbol, candle) in new_candles {
+                push_capped(map.entry(symbol).or_default(), candle, cap);
+            }
+        });
+    }
+    if let Some(skew) = ctx.clock_skew.get_untracked().filter(|_| !new_e2e.is_empty()) {
+        ctx.e2e_values.update(|v| {
+            for ts in new_e2e {
+                push_capped(v, skew.latency_ms(ts, recv_wall_ms), cap);
+            }
+        });
+    }
+    if !new_can.is_empty() {
+        ctx.can_buses.update(|map| {
+            for msg in new_can {
+                match msg {
+                    WireMessage::CanFrame { bus_id, .. } => map.entry(bus_id).or_default().frames += 1,
+                    WireMessage::BusStatus { bus_id, load_percent, error_count, messages_per_sec, .. } => {
+                        let bus = map.entry(bus_id).or_default();
+                        bus.load_percent = load_percent;
+                        bus.error_count = error_count;
+                        bus.messages_per_sec = messages_per_sec as f32;
+                    }
+                    WireMessage::CanStats { bus_id, messages_per_second, bytes_per_second, avg_latency_us, max_latency_us, .. } => {
+                        let bus = map.entry(bus_id).or_default();
+                        bus.messages_per_sec = messages_per_second;
+                        bus.bytes_per_sec = bytes_per_second;
+                        bus.avg_latency_us = avg_latency_us;
+                        bus.max_latency_us = max_latency_us;
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+    if !new_signals.is_empty() {
+        ctx.vehicle_signals.update(|map| {
+            for (name, value, unit, source) in new_signals {
+                map.insert(name, (value, unit, source));
+            }
+        });
+    }
+    if skipped > 0 {
+        ctx.dropped.update(|d| *d += skipped);
+    }
+}
+
+/// Summary statistics over a sample buffer. All fields are zero for an empty buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Stats {
+    mean: f64,
+    p50: f64,
+    p95: f64,
+    p99: f64,
+    min: f64,
+    max: f64,
+    stddev: f64,
+}
+
+impl Stats {
+    fn summary(&self) -> String {
+        format!(
+            "μ:{:.2} σ:{:.2} p50:{:.2} p95:{:.2} p99:{:.2} min:{:.2} max:{:.2}",
+            self.mean, self.stddev, self.p50, self.p95, self.p99, self.min, self.max
+        )
+    }
+}
+
+/// Percentile of an already sorted, non-empty slice.
+///
+/// Uses linear interpolation between the two closest ranks (`q * (n - 1)`),
+/// so for small buffers (n < 20) p99 falls between the two largest samples
+/// instead of always snapping to the maximum. Both indices are clamped to the
+/// slice bounds.
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let last = sorted.len() - 1;
+    let pos = (last as f64 * q).clamp(0.0, last as f64);
+    let lo = (pos.floor() as usize).min(last);
+    let hi = (pos.ceil() as usize).min(last);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+/// Offset of the server clock against the client clock, estimated from one
+/// ping/pong exchange.
+///
+/// The server stamps `server_ts` somewhere between receiving the ping and
+/// sending the pong. Assuming both directions take equally long, that is
+/// half the round trip before the pong arrives, so
+/// `offset = server_ts - (client_recv - rtt / 2)`. Asymmetric paths and
+/// server processing move the true stamp anywhere within the round trip,
+/// so the true offset lies within `±rtt / 2` of the estimate; the same bound
+/// carries over to every latency corrected with it. The client keeps the
+/// estimate from the fastest exchange, which has the tightest bound.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ClockSkew {
+    offset_ms: f64, // server clock minus client clock
+    rtt_ms: f64,    // round trip of the exchange; error bound is half of it
+}
+
+impl ClockSkew {
+    /// `server_ts_us` from the pong, `client_recv_ms` wall clock at receipt
+    #[cfg(feature = "hydrate")]
+    fn estimate(server_ts_us: i64, client_recv_ms: f64, rtt_ms: f64) -> Self {
+        let offset_ms = server_ts_us as f64 / 1000.0 - (client_recv_ms - rtt_ms / 2.0);
+        Self { offset_ms, rtt_ms }
+    }
+
+    /// Server-to-client latency of a message stamped `server_ts_us` and
+    /// received at `client_recv_ms` (wall clock), with the server time moved
+    /// onto the client clock. Accurate to `±rtt_ms / 2`.
+    #[cfg(feature = "hydrate")]
+    fn latency_ms(&self, server_ts_us: i64, client_recv_ms: f64) -> f64 {
+        client_recv_ms - (server_ts_us as f64 / 1000.0 - self.offset_ms)
+    }
+}
+
+/// Equal-width histogram over the observed range, as `(bin center, count)`
+/// pairs. If all values are equal there is a single bin at that value;
+/// non-finite values are skipped.
+fn histogram(data: &[f64], bins: usize) -> Vec<(f64, usize)> {
+    let values: Vec<f64> = data.iter().copied().filter(|v| v.is_finite()).collect();
+    if values.is_empty() || bins == 0 { return Vec::new(); }
+    let (min, max) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), &v| (mn.min(v), mx.max(v)));
+    if max - min < 1e-9 { return vec![(min, values.len())]; }
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+    for v in values {
+        // The maximum belongs to the last bin
+        counts[(((v - min) / width) as usize).min(bins - 1)] += 1;
+    }
+    counts.into_iter().enumerate().map(|(i, n)| (min + width * (i as f64 + 0.5), n)).collect()
+}
+
+/// Trailing price samples a new tick is compared against.
+#[cfg(feature = "hydrate")]
+const ANOMALY_WINDOW: usize = 50;
+/// Z-score above which a tick is flagged.
+#[cfg(feature = "hydrate")]
+const ANOMALY_Z: f64 = 4.0;
+/// How long a flagged price card stays highlighted.
+const ANOMALY_FADE_MS: f64 = 2000.0;
+
+/// Flags `new` if it lies more than `z_threshold` standard deviations from
+/// the mean of `history`. Too little history (< 10 samples) or a flat history
+/// never flags, since neither gives a meaningful spread.
+#[cfg(any(feature = "hydrate", test))]
+fn detect_anomaly(history: &[f64], new: f64, z_threshold: f64) -> bool {
+    if history.len() < 10 { return false; }
+    let n = history.len() as f64;
+    let mean = history.iter().sum::<f64>() / n;
+    let stddev = (history.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    stddev > 0.0 && ((new - mean) / stddev).abs() > z_threshold
+}
+
+/// Wall clock in ms; anomaly highlights are only recorded in the browser.
+fn now_ms() -> f64 {
+    #[cfg(feature = "hydrate")]
+    { js_sys::Date::now() }
+    #[cfg(not(feature = "hydrate"))]
+    { 0.0 }
+}
+
+/// Mean of the last `window` samples; `None` while there are fewer samples,
+/// if `window` is 0 or if one of them isn't finite.
+fn sma(data: &[f64], window: usize) -> Option<f64> {
+    if window == 0 || data.len() < window { return None; }
+    let recent = &data[data.len() - window..];
+    if !recent.iter().all(|v| v.is_finite()) { return None; }
+    Some(recent.iter().sum::<f64>() / window as f64)
+}
+
+/// Crossovers kept in the signal log.
+#[cfg(feature = "hydrate")]
+const CROSSOVER_LOG_MAX: usize = 20;
+
+/// The fast SMA of a symbol crossed its slow SMA: upward is a buy signal,
+/// downward a sell signal.
+#[derive(Clone, Debug)]
+struct Crossover {
+    symbol: String,
+    buy: bool,
+    price: f64, // tick that caused the cross
+    ts: i64,
+}
+
+/// Relative distance the price has to move back past an alert's threshold
+/// before the alert can fire again.
+#[cfg(feature = "hydrate")]
+const ALERT_REARM: f64 = 0.001;
+/// How long a price card flashes after an alert fired.
+const ALERT_FLASH_MS: f64 = 3000.0;
+
+/// Price alert of one symbol: fires when the price crosses `threshold`
+/// upwards (`above`) or downwards.
+///
+/// Only the crossing edge fires. Afterwards the alert stays quiet until the
+/// price is back on the other side by `ALERT_REARM`, so a price hovering at
+/// the threshold notifies once. An alert set while the price is already past
+/// the threshold waits for it to come back first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Alert {
+    threshold: f64,
+    above: bool,
+    armed: bool,
+    fired: bool, // fired at least once; re-arming then needs the margin
+}
+
+impl Alert {
+    fn new(threshold: f64, above: bool) -> Self {
+        Self { threshold, above, armed: false, fired: false }
+    }
+
+    fn describe(&self, spec: &FormatSpec) -> String {
+        format!("{} {}", if self.above { "above" } else { "below" }, spec.format(self.threshold))
+    }
+
+    /// Feeds one price; `true` if the alert fires on it
+    #[cfg(feature = "hydrate")]
+    fn check(&mut self, price: f64) -> bool {
+        let beyond = if self.above { price >= self.threshold } else { price <= self.threshold };
+        if self.armed {
+            if beyond {
+                self.armed = false;
+                self.fired = true;
+                return true;
+            }
+        } else {
+            let margin = if self.fired { self.threshold * ALERT_REARM } else { 0.0 };
+            self.armed = if self.above {
+                price < self.threshold - margin
+            } else {
+                price > self.threshold + margin
+            };
+        }
+        false
+    }
+}
+
+/// Asks for notification permission unless the user already decided. Must
+/// run in a user gesture, so it's called when an alert is set.
+#[cfg(feature = "hydrate")]
+fn request_notification_permission() {
+    if web_sys::Notification::permission() == web_sys::NotificationPermission::Default {
+        let _ = web_sys::Notification::request_permission();
+    }
+}
+
+/// Shows a browser notification for a fired alert, if permitted.
+#[cfg(feature = "hydrate")]
+fn notify_alert(symbol: &str, alert: &Alert, price: f64) {
+    if web_sys::Notification::permission() != web_sys::NotificationPermission::Granted {
+        return;
+    }
+    let options = web_sys::NotificationOptions::new();
+    let spec = FormatSpec::for_symbol(symbol);
+    options.set_body(&format!("{} is {} (now {})", symbol, alert.describe(&spec), spec.format(price)));
+    options.set_tag(&format!("price-alert-{}", symbol));
+    let _ = web_sys::Notification::new_with_options(&format!("🔔 {}", symbol), &options);
+}
+
+/// Indices of `data` at which the fast SMA crosses the slow one, with
+/// `true` for an upward cross. Matches what the live tracking flags, except
+/// for crossings before the start of the kept history. Windows holding a
+/// non-finite sample have no SMA and are skipped, so a NaN can't fake a
+/// crossing.
+fn crossover_points(data: &[f64], fast: usize, slow: usize) -> Vec<(usize, bool)> {
+    let mut out = Vec::new();
+    let mut prev = None;
+    for end in 1..=data.len() {
+        let (Some(f), Some(s)) = (sma(&data[..end], fast), sma(&data[..end], slow)) else { continue };
+        let above = f > s;
+        if prev == Some(!above) {
+            out.push((end - 1, above));
+        }
+        prev = Some(above);
+    }
+    out
+}
+
+/// Pearson correlation of the returns (not the raw prices) of two price
+/// series. Buffers of different length are aligned on their most recent
+/// samples; a step where either series has a non-finite or zero price has
+/// no return and is skipped in both. `None` with fewer than two returns,
+/// when a series is flat or the result isn't finite.
+fn rolling_correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    let (a, b) = (&a[a.len() - n..], &b[b.len() - n..]);
+    let usable = |w: &[f64]| w.iter().all(|p| p.is_finite() && *p != 0.0);
+    let (ra, rb): (Vec<f64>, Vec<f64>) = a
+        .windows(2)
+        .zip(b.windows(2))
+        .filter(|(wa, wb)| usable(wa) && usable(wb))
+        .map(|(wa, wb)| (wa[1] / wa[0] - 1.0, wb[1] / wb[0] - 1.0))
+        .unzip();
+    if ra.len() < 2 {
+        return None;
+    }
+    let mean = |r: &[f64]| r.iter().sum::<f64>() / r.len() as f64;
+    let (mean_a, mean_b) = (mean(&ra), mean(&rb));
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in ra.iter().zip(&rb) {
+        let (dx, dy) = (x - mean_a, y - mean_b);
+        cov += dx * dy;
+        var_a += dx * dx;
+        var_b += dy * dy;
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        return None;
+    }
+    let r = cov / (var_a * var_b).sqrt();
+    r.is_finite().then(|| r.clamp(-1.0, 1.0))
+}
+
+/// Trades per symbol the volume imbalance is computed over.
+const IMBALANCE_WINDOW: usize = 50;
+
+/// Aggressor-side volume imbalance `(buy - sell) / (buy + sell)` of
+/// `(symbol, price, size, side)` trades, from -1 (only sells) to 1 (only
+/// buys). 0 when the trades carry no volume.
+fn volume_imbalance(trades: &[(String, f64, f64, String)]) -> f64 {
+    let (buy, sell) = trades.iter().fold((0.0, 0.0), |(buy, sell), (_, _, size, side)| match side.as_str() {
+        "buy" => (buy + size, sell),
+        "sell" => (buy, sell + size),
+        _ => (buy, sell),
+    });
+    let total = buy + sell;
+    if total > 0.0 { (buy - sell) / total } else { 0.0 }
+}
+
+/// Heatmap cell color: blue for negative, red for positive correlation,
+/// fading out towards 0 so it works on light and dark backgrounds.
+fn correlation_color(r: f64) -> String {
+    let alpha = r.abs().min(1.0);
+    if r < 0.0 {
+        format!("rgba(0,102,204,{:.2})", alpha)
+    } else {
+        format!("rgba(220,38,38,{:.2})", alpha)
+    }
+}
+
+/// `HH:MM:SS` (UTC) of a timestamp in micros since the epoch.
+fn clock_utc(ts_us: i64) -> String {
+    let secs = ts_us.div_euclid(1_000_000).rem_euclid(86_400);
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// [`Stats`] over the finite values of `data`; a single NaN or infinity
+/// would otherwise turn the mean and the percentiles into NaN.
+fn stats(data: &[f64]) -> Stats {
+    let mut v: Vec<f64> = data.iter().copied().filter(|x| x.is_finite()).collect();
+    if v.is_empty() { return Stats::default(); }
+    let n = v.len() as f64;
+    let mean = v.iter().sum::<f64>() / n;
+    let variance = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    v.sort_by(f64::total_cmp);
+    Stats {
+        mean,
+        p50: percentile(&v, 0.50),
+        p95: percentile(&v, 0.95),
+        p99: percentile(&v, 0.99),
+        min: v[0],
+        max: v[v.len() - 1],
+        stddev: variance.sqrt(),
+    }
+}
+
+/// One symbol over its price buffer for the summary strip; the "session" is
+/// what the sample window still holds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SessionSummary {
+    last: f64,
+    /// `last` against the oldest price in percent; `None` if the oldest is 0
+    change_pct: Option<f64>,
+    high: f64,
+    low: f64,
+}
+
+/// [`SessionSummary`] over the finite values of `data`, oldest first. A
+/// single price is its own high and low with a change of 0%; `None` without
+/// a finite price.
+fn session_summary(data: &[f64]) -> Option<SessionSummary> {
+    let mut finite = data.iter().copied().filter(|p| p.is_finite());
+    let first = finite.next()?;
+    let (mut last, mut high, mut low) = (first, first, first);
+    for p in finite {
+        last = p;
+        high = high.max(p);
+        low = low.min(p);
+    }
+    let change_pct = (first != 0.0).then(|| (last - first) / first * 100.0);
+    Some(SessionSummary { last, change_pct, high, low })
+}
+
 #[component]
 pub fn App() -> impl IntoView {
     provide_meta_context();
 
     let prices = RwSignal::new(std::collections::HashMap::<String, Vec<f64>>::new());
-    let trades = RwSignal::new(Vec::<(String, f64, String)>::new());
-    let book_depth = RwSignal::new(std::collections::HashMap::<String, (Vec<f64>, Vec<f64>)>::new());
+    let price_times = RwSignal::new(std::collections::HashMap::<String, Vec<i64>>::new()); // server ts (µs) per price
+    let time_axis = RwSignal::new(false); // price charts by timestamp instead of by index
+    let anomalies = RwSignal::new(std::collections::HashMap::<String, f64>::new()); // symbol → last flagged tick (ms)
+    let trades = RwSignal::new(Vec::<(String, f64, f64, String)>::new()); // (symbol, price, size, side)
+    let trade_summaries = RwSignal::new(TradeSummaryMap::new());
+    // Trade tape view: symbol and side filters (None = all), price instead of time order
+    let tape_symbol = RwSignal::new(None::<String>);
+    let tape_side = RwSignal::new(None::<String>);
+    let tape_by_price = RwSignal::new(false);
+    let vwap_trades = RwSignal::new(TradeWindowMap::new());
+    // Moving-average crossover signals; the trend is fast > slow per symbol
+    let sma_fast = RwSignal::new(10usize);
+    let sma_slow = RwSignal::new(30usize);
+    let sma_trend = RwSignal::new(std::collections::HashMap::<String, bool>::new());
+    let crossovers = RwSignal::new(Vec::<Crossover>::new());
+    let alerts = RwSignal::new(std::collections::HashMap::<String, Alert>::new());
+    let alert_flash = RwSignal::new(std::collections::HashMap::<String, f64>::new()); // symbol → last fired (ms)
+    let alert_symbol = RwSignal::new(SYMBOLS[0].to_string()); // target of the alert controls
+    let alert_above = RwSignal::new(true);
+    let control_acks = RwSignal::new(std::collections::HashMap::<String, f64>::new()); // control field → last ack (ms)
+    // Burst mode: ticks and window of the next burst, (sent, requested) of the last one
+    let burst_messages = RwSignal::new(1000u32);
+    let burst_over_ms = RwSignal::new(1000u32);
+    let burst_result = RwSignal::new(None::<(u64, u64)>);
+    let book_depth = RwSignal::new(BookMap::new());
+    let candles = RwSignal::new(CandleMap::new());
+    let vehicle_signals = RwSignal::new(VehicleSignalMap::new());
+    let can_buses = RwSignal::new(CanBusMap::new());
 
     let msg_rate = RwSignal::new(Vec::<f64>::new());
     let latency_values = RwSignal::new(Vec::<f64>::new());
+    let rtt_values = RwSignal::new(Vec::<f64>::new()); // ping round-trip times (ms)
+    let clock_skew = RwSignal::new(None::<ClockSkew>); // from the fastest ping/pong so far
+    let e2e_values = RwSignal::new(Vec::<f64>::new()); // skew-corrected server-to-client latency (ms)
+    // Latest `system` message, for comparing server and client throughput
+    let server_cpu = RwSignal::new(0.0f64);
+    let server_mem_mb = RwSignal::new(0u64);
+    let server_msg_rate = RwSignal::new(0u64);
     let fps_values = RwSignal::new(Vec::<f64>::new());
     let sample_max = RwSignal::new(200usize);
+    let trade_cap = RwSignal::new(100usize); // recent trades kept
+    // Price ticks per wall-clock window drawn instead of every tick
+    let agg_window = RwSignal::new(AggWindow::Raw);
+    let agg_fn = RwSignal::new(AggFn::Last);
+    // Shown on the dump button; the client config may change it after hydration
+    let capture_secs = RwSignal::new(CAPTURE_SECS);
+    // Server-side intervals of the simulated streams, mirrored for the inputs
+    let price_ms = RwSignal::new(50u64);
+    let book_ms = RwSignal::new(100u64);
+    let trade_ms = RwSignal::new(150u64);
     let msg_count = RwSignal::new(0u64);
     let msg_rate_timer = RwSignal::new(0.0);
+    let bandwidth_values = RwSignal::new(Vec::<f64>::new()); // received bytes/s, bucketed with the message rate
+    let byte_count = RwSignal::new(0u64);
+    let dropped = RwSignal::new(0u64); // messages the server skipped for this client
+    let parse_errors = RwSignal::new(0u64); // frames that weren't a valid message
+    let unknown_msgs = RwSignal::new(0u64); // valid messages of an unknown type
+    let msg_types = RwSignal::new(std::collections::HashMap::<String, u64>::new()); // messages per `type` this session
+    let parse_times = RwSignal::new(Vec::<f64>::new()); // decode time per frame (ms)
+    let msgpack = RwSignal::new(false); // binary MessagePack frames instead of JSON
+    // Lowering the sample window cuts every buffer it caps right away
+    // instead of letting them drain over time
+    let unit_system = RwSignal::new(UnitSystem::Metric); // of the vehicle signals
+    let trim_buffers = move |cap: usize| {
+        prices.update(|map| map.values_mut().for_each(|v| trim_to_cap(v, cap)));
+        price_times.update(|map| map.values_mut().for_each(|v| trim_to_cap(v, cap)));
+        vwap_trades.update(|map| map.values_mut().for_each(|v| trim_to_cap(v, cap)));
+        candles.update(|map| map.values_mut().for_each(|v| trim_to_cap(v, cap)));
+        for buffer in [msg_rate, bandwidth_values, latency_values, fps_values, rtt_values, e2e_values, parse_times] {
+            buffer.update(|v| trim_to_cap(v, cap));
+        }
+    };
+
+    let conn_state = RwSignal::new(ConnState::Connecting);
+    let send_backlog = RwSignal::new((0u32, 0usize)); // queued bytes and held back control messages
+    // Bumped by "Reconnect now"; the socket effect reconnects on every change
+    let reconnect_requests = RwSignal::new(0u32);
+    let subscribed = RwSignal::new(Vec::<String>::new());
+    let batch_ms = RwSignal::new(0u64); // server-side batching window, 0 = off
+    let server_shutdown = RwSignal::new(false); // set by the server's shutdown notice
+    let degraded_feeds = RwSignal::new(std::collections::HashSet::<String>::new()); // symbols whose upstream is failing
+    let param_symbol = RwSignal::new(SYMBOLS[0].to_string()); // target of the simulation controls
+    let available_symbols = RwSignal::new(SYMBOLS.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    let enabled_streams = RwSignal::new(STREAMS.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    let stream_paused = move |stream: &str| !enabled_streams.read().iter().any(|s| s == stream);
+    // Starts light on both server and client; the stored choice is applied after hydration
+    let theme = RwSignal::new(Theme::LIGHT);
+    provide_context(theme);
+    // "✓ applied" next to a control, fading out once the server acknowledged
+    // it; one memo per field so other acks don't restart the fade
+    let applied_badge = move |field: &'static str| {
+        let acked_at = Memo::new(move |_| control_acks.read().get(field).copied());
+        move || acked_at.get().map(|_| view! {
+            <span style=format!("margin-left:0.25rem;font-size:0.8rem;color:{};animation:ack-fade {}ms ease-in forwards;", theme.read().up, ACK_FADE_MS)>
+                "✓ applied"
+            </span>
+        })
+    };
+    // Sparkline style per chart, `Line` until picked; one entry covers all price charts
+    let spark_styles = RwSignal::new(std::collections::HashMap::<&'static str, SparkStyle>::new());
+    let spark_style = move |chart: &'static str| spark_styles.read().get(chart).copied().unwrap_or_default();
+    let spark_style_picker = move |chart: &'static str| view! {
+        <select title="Chart style"
+            style=move || format!("margin-left:0.5rem;padding:0 0.25rem;font-size:0.75rem;font-weight:normal;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+            on:change=move |ev| { spark_styles.write().insert(chart, SparkStyle::from_name(&event_target_value(&ev))); }>
+            {SparkStyle::ALL.into_iter().map(|style| view! {
+                <option value=style.name() selected=style == SparkStyle::default()>{style.name()}</option>
+            }).collect::<Vec<_>>()}
+        </select>
+    };
+    // Per-card resets; "Reset All Metrics" runs each of them
+    let reset_prices = move || {
+        prices.write().clear();
+        price_times.write().clear();
+        anomalies.write().clear();
+        sma_trend.write().clear();
+        crossovers.write().clear();
+        candles.write().clear();
+        // A pending window would otherwise bring back a pre-reset price
+        #[cfg(feature = "hydrate")]
+        PRICE_AGG.with_borrow_mut(|agg| agg.clear());
+    };
+    let reset_trades = move || {
+        trades.write().clear();
+        vwap_trades.write().clear();
+        trade_summaries.write().clear();
+    };
+    let reset_book = move || book_depth.write().clear();
+    // The count and its timer only go together: a count without its timer
+    // start would be divided by the time since the last reset
+    let reset_msg_rate = move || {
+        msg_rate.write().clear();
+        *msg_count.write() = 0;
+        *msg_rate_timer.write() = 0.0;
+        bandwidth_values.write().clear();
+        *byte_count.write() = 0;
+    };
+    let reset_fps = move || fps_values.write().clear();
+    let reset_latency = move || {
+        latency_values.write().clear();
+        parse_times.write().clear();
+    };
+    let reset_all = move || {
+        reset_prices();
+        reset_trades();
+        reset_book();
+        reset_msg_rate();
+        reset_fps();
+        reset_latency();
+        vehicle_signals.write().clear();
+        can_buses.write().clear();
+        rtt_values.write().clear();
+        e2e_values.write().clear();
+        *dropped.write() = 0;
+        *parse_errors.write() = 0;
+        *unknown_msgs.write() = 0;
+        msg_types.write().clear();
+    };
+    // Shown instead of the last data of a paused stream
+    let paused_note = move || view! { <p style=move || format!("margin:0;color:{};font-style:italic;", theme.read().muted)>"⏸ Paused"</p> };
+    let schema_mismatch = RwSignal::new(None::<u32>); // server schema version if it differs
+
+    // While paused the charts render `frozen`; data collection keeps running
+    let paused = RwSignal::new(false);
+    let frozen = RwSignal::new(None::<Snapshot>);
+    let toggle_pause = move || {
+        if paused.get_untracked() {
+            frozen.set(None);
+        } else {
+            frozen.set(Some(Snapshot {
+                prices: prices.get_untracked(),
+                price_times: price_times.get_untracked(),
+                trades: trades.get_untracked(),
+                vwap_trades: vwap_trades.get_untracked(),
+                trade_summaries: trade_summaries.get_untracked(),
+                book_depth: book_depth.get_untracked(),
+                candles: candles.get_untracked(),
+                vehicle_signals: vehicle_signals.get_untracked(),
+            }));
+        }
+        paused.update(|p| *p = !*p);
+    };
+
+    Effect::new(move |_| {
+        #[cfg(feature = "hydrate")]
+        if let Some(stored) = load_theme() {
+            theme.set(stored);
+        }
+    });
+
+    Effect::new(move |_| {
+        #[cfg(feature = "hydrate")]
+        if let Some(stored) = load_units() {
+            unit_system.set(stored);
+        }
+    });
+
+    Effect::new(move |_| {
+        #[cfg(feature = "hydrate")]
+        apply_body_theme(theme.get());
+    });
 
     Effect::new(move |_| {
         #[cfg(feature = "hydrate")]
         {
-            use web_sys::{MessageEvent, WebSocket};
-            use wasm_bindgen::closure::Closure;
-            use wasm_bindgen::JsCast;
-            if prices.read().is_empty() {
-                let window = web_sys::window().expect("window");
-                let location = window.location();
-                let host = location.host().unwrap_or_else(|_| "127.0.0.1:3000".into());
-                let protocol = location
-                    .protocol()
-                    .ok()
-                    .filter(|p| p.starts_with("https"))
-                    .map(|_| "wss")
-                    .unwrap_or("ws");
-
-                let ws_url = format!("{}://{}/ws", protocol, host);
-
-                if let Ok(ws) = WebSocket::new(&ws_url) {
-                    if let Some(win) = web_sys::window() {
-                        let _ = js_sys::Reflect::set(
-                            win.as_ref(), 
-                            &js_sys::JsString::from("__leptos_ws"), 
-                            ws.as_ref()
-                        );
+            // Space or p toggles pause, r resets all metrics and 1-5 focus the
+            // price cards; all except while typing into a form field
+            let _ = window_event_listener(leptos::ev::keydown, move |ev| {
+                let typing = ev
+                    .target()
+                    .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+                    .is_some_and(|el| matches!(el.tag_name().as_str(), "INPUT" | "SELECT" | "TEXTAREA"));
+                if typing || ev.ctrl_key() || ev.meta_key() || ev.alt_key() {
+                    return;
+                }
+                match ev.key().as_str() {
+                    " " | "p" => {
+                        ev.prevent_default();
+                        toggle_pause();
                     }
+                    "r" => reset_all(),
+                    key @ ("1" | "2" | "3" | "4" | "5") => {
+                        let index = key.parse::<u32>().unwrap_or(1) - 1;
+                        let card = document()
+                            .get_elements_by_class_name("price-card")
+                            .item(index)
+                            .and_then(|el| el.dyn_into::<web_sys::HtmlElement>().ok());
+                        if let Some(card) = card {
+                            let _ = card.focus();
+                        }
+                    }
+                    _ => {}
+                }
+            });
+        }
+    });
+
+    Effect::new(move |_| {
+        #[cfg(feature = "hydrate")]
+        {
+            use wasm_bindgen::closure::Closure;
 
-                    #[derive(Deserialize)]
-                    #[serde(tag = "type")]
-                    enum Msg {
-                        #[serde(rename = "price")] 
-                        Price { 
-                            symbol: String,
-                            price: f64,
-                            volume: u64,
-                            ts: i64
-                        },
-                        #[serde(rename = "trade")] 
-                        Trade { 
-                            symbol: String,
-                            price: f64,
-                            size: f64,
-                            side: String,
-                            ts: i64
-                        },
-                        #[serde(rename = "book")] 
-                        Book { 
-                            symbol: String,
-                            bids: Vec<(f64, f64)>,
-                            asks: Vec<(f64, f64)>,
-                            ts: i64
-                        },
-                        #[serde(rename = "system")] 
-                        System { 
-                            cpu_pct: f64,
-                            mem_mb: u64,
-                            msg_rate: u64,
-                            ts: i64
-                        },
-                        #[serde(other)] Other,
-                    }
+            let ctx = WsCtx {
+                prices,
+                price_times,
+                anomalies,
+                trades,
+                trade_summaries,
+                vwap_trades,
+                sma_fast,
+                sma_slow,
+                sma_trend,
+                crossovers,
+                alerts,
+                alert_flash,
+                control_acks,
+                burst_result,
+                book_depth,
+                candles,
+                vehicle_signals,
+                can_buses,
+                msg_rate,
+                fps_values,
+                latency_values,
+                msg_count,
+                msg_rate_timer,
+                bandwidth_values,
+                byte_count,
+                dropped,
+                parse_errors,
+                unknown_msgs,
+                msg_types,
+                parse_times,
+                msgpack,
+                sample_max,
+                trade_cap,
+                agg_window,
+                agg_fn,
+                conn_state,
+                subscribed,
+                batch_ms,
+                server_shutdown,
+                degraded_feeds,
+                available_symbols,
+                enabled_streams,
+                schema_mismatch,
+                rtt_values,
+                clock_skew,
+                e2e_values,
+                server_cpu,
+                server_mem_mb,
+                server_msg_rate,
+                missed_pings: RwSignal::new(0),
+                reconnect_attempt: RwSignal::new(0),
+                reconnect_pending: RwSignal::new(false),
+                resume_on_visible: RwSignal::new(false),
+                ws_generation: RwSignal::new(0),
+                send_backlog,
+                config: read_client_config(),
+            };
+            capture_secs.set(ctx.config.capture_secs);
 
-                    let onmessage = Closure::wrap(Box::new({
-                        let prices = prices.clone();
-                        let trades = trades.clone();
-                        let book_depth = book_depth.clone();
-                        let msg_rate_sig = msg_rate.clone();
-                        let latency_values = latency_values.clone();
-                        let msg_count = msg_count.clone();
-                        let msg_rate_timer = msg_rate_timer.clone();
-                        let sample_max = sample_max.clone();
-
-                        move |e: MessageEvent| {
-                            let t_recv = web_sys::window().unwrap().performance().unwrap().now();
-                            msg_count.update(|c| *c += 1);
-                            let last_time = *msg_rate_timer.read();
-                            if last_time == 0.0 {
-                                *msg_rate_timer.write() = t_recv;
-                            } else if t_recv - last_time >= 1000.0 {
-                                let current_rate = *msg_count.read() as f64 * 1000.0 / (t_recv - last_time);
-                                msg_rate_sig.update(|v| {
-                                    v.push(current_rate);
-                                    let cap = *sample_max.read();
-                                    let extra = v.len().saturating_sub(cap);
-                                    if extra > 0 { v.drain(0..extra); }
-                                });
-                                *msg_count.write() = 0;
-                                *msg_rate_timer.write() = t_recv;
-                            }
-                            if let Some(txt) = e.data().as_string() {
-                                if let Ok(msg) = serde_json::from_str::<Msg>(&txt) {
-                                    match msg {
-                                        Msg::Price { symbol, price, .. } => {
-                                            prices.update(|map| {
-                                                let entry = map.entry(symbol).or_insert_with(Vec::new);
-                                                entry.push(price);
-                                                let cap = *sample_max.read();
-                                                if entry.len() > cap { 
-                                                    entry.drain(0..entry.len() - cap); 
-                                                }
-                                            });
-
-                                            let latency_values = latency_values.clone();
-
-                                            let cb = Closure::wrap(Box::new(move |_: f64| {
-                                                let t_paint = web_sys::window().unwrap().performance().unwrap().now();
-                                                let dt = t_paint - t_recv;
-                                                let mut lv = latency_values.write();
-                                                lv.push(dt);
-                                                let cap = *sample_max.read();
-                                                let extra = lv.len().saturating_sub(cap);
-                                                if extra > 0 { lv.drain(0..extra); }
-                                            }) as Box<dyn FnMut(f64)>);
-
-                                            let _ = web_sys::window().unwrap().request_animation_frame(cb.as_ref().unchecked_ref());
-                                            cb.forget();
-                                        }
-                                        Msg::Trade { symbol, price, side, .. } => {
-                                            trades.update(|t| {
-                                                t.push((symbol, price, side));
-                                                if t.len() > 100 { t.drain(0..t.len() - 100); }
-                                            });
-                                        }
-                                        Msg::Book { symbol, bids, asks, .. } => {
-                                            book_depth.update(|map| {
-                                                let bid_prices: Vec<f64> = bids.iter().map(|(p, _)| *p).collect();
-                                                let ask_prices: Vec<f64> = asks.iter().map(|(p, _)| *p).collect();
-                                                map.insert(symbol, (bid_prices, ask_prices));
-                                            });
-                                        }
-                                        Msg::System { .. } => {
-                                        }
-                                        Msg::Other => {}
-                                    }
-                                }
-                            }
-                        }
-                    }) as Box<dyn FnMut(_)>);
-                    let _ = ws.add_event_listener_with_callback("message", onmessage.as_ref().unchecked_ref());
-                    onmessage.forget();
+            // Resume a reconnect that was put on hold while the tab was hidden
+            let on_visibility = Closure::wrap(Box::new(move |_: web_sys::Event| {
+                if !page_hidden() && ctx.resume_on_visible.get_untracked() {
+                    ctx.resume_on_visible.set(false);
+                    connect_ws(ctx);
+                }
+            }) as Box<dyn FnMut(_)>);
+            let _ = document().add_event_listener_with_callback("visibilitychange", on_visibility.as_ref().unchecked_ref());
+            on_visibility.forget();
+
+            Effect::new(move |prev: Option<u32>| {
+                let requests = reconnect_requests.get();
+                if prev.is_some() {
+                    reconnect_now(ctx);
+                }
+                requests
+            });
+
+            // Switching the wire format takes a new connection
+            Effect::new(move |prev: Option<bool>| {
+                let msgpack = ctx.msgpack.get();
+                if prev.is_some_and(|p| p != msgpack) {
+                    close_socket(ctx);
+                    connect_ws(ctx);
                 }
+                msgpack
+            });
+
+            // Restore the previous session before the socket fills in
+            if let Some(snapshot) = load_state() {
+                snapshot.restore(ctx);
             }
+            // The server's symbols and recent prices are fresher than the saved
+            // ones; fetched before connecting so live ticks only ever append to them
+            leptos::task::spawn_local(async move {
+                refresh_symbols(ctx, Vec::new()).await;
+                backfill_prices(ctx).await;
+                connect_ws(ctx);
+            });
+            set_interval(move || heartbeat_tick(ctx), std::time::Duration::from_millis(ctx.config.ping_interval_ms));
+            set_interval(move || flush_deferred(ctx), std::time::Duration::from_millis(SEND_RETRY_MS));
+            // Without ticks nothing else closes the aggregation windows
+            set_interval(
+                move || apply_msgs(ctx, Vec::new(), web_sys::window().unwrap().performance().unwrap().now()),
+                std::time::Duration::from_millis(AGG_FLUSH_MS),
+            );
+            // Save at most every few seconds, and only when data arrived
+            let saved_count = RwSignal::new(0u64);
+            set_interval(
+                move || {
+                    let count = ctx.msg_count.get_untracked();
+                    if count != saved_count.get_untracked() {
+                        saved_count.set(count);
+                        save_state(ctx);
+                    }
+                },
+                std::time::Duration::from_millis(SNAPSHOT_INTERVAL_MS),
+            );
         }
     });
 
@@ -201,10 +2318,11 @@ pub fn App() -> impl IntoView {
             use std::cell::RefCell;
             let start_sec = RwSignal::new(web_sys::window().unwrap().performance().unwrap().now());
             let frames = RwSignal::new(0u32);
-            let fps_values_signal = fps_values.clone();
-            let sample_max_signal = sample_max.clone();
+            let fps_values_signal = fps_values;
+            let sample_max_signal = sample_max;
 
-            let cb_cell: Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>> = Rc::new(RefCell::new(None));
+            type RafCell = Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>>;
+            let cb_cell: RafCell = Rc::new(RefCell::new(None));
             let cb_cell_clone = cb_cell.clone();
             let raf_cb = Closure::wrap(Box::new(move |_: f64| {
                 frames.update(|f| *f += 1);
@@ -213,11 +2331,8 @@ pub fn App() -> impl IntoView {
                 if elapsed >= 1000.0 {
                     let fps = *frames.read() as f64 * 1000.0 / elapsed.max(1.0);
                     {
-                        let mut v = fps_values_signal.write();
-                        v.push(fps);
                         let cap = *sample_max_signal.read();
-                        let extra = v.len().saturating_sub(cap);
-                        if extra > 0 { v.drain(0..extra); }
+                        push_capped(&mut fps_values_signal.write(), fps, cap);
                     }
                     *start_sec.write() = now;
                     *frames.write() = 0;
@@ -234,30 +2349,40 @@ pub fn App() -> impl IntoView {
         }
     });
 
-    fn sparkline_points(data: &[f64], width: f64, height: f64) -> String {
-        if data.is_empty() { return String::new(); }
-        let (min, max) = data.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), &v| (mn.min(v), mx.max(v)));
-        let range = if (max - min).abs() < 1e-9 { 1.0 } else { max - min };
-        let n = data.len() as f64;
-        let step = if n > 1.0 { width / (n - 1.0) } else { width };
-        let mut out = String::new();
-        for (i, &v) in data.iter().enumerate() {
-            let x = step * (i as f64);
-            let y = height - ((v - min) / range) * height;
-            if i > 0 { out.push(' '); }
-            out.push_str(&format!("{:.1},{:.1}", x, y));
+    /// Small "↺" button in a card or section heading that runs `reset`.
+    fn reset_button(theme: RwSignal<Theme>, title: &'static str, reset: impl Fn() + 'static) -> impl IntoView {
+        view! {
+            <button style=move || format!("margin-left:0.5rem;border:none;background:none;cursor:pointer;font-size:0.9rem;color:{};", theme.read().down)
+                title=title
+                on:click=move |_| reset()>
+                "↺"
+            </button>
         }
-        out
     }
 
-    fn stats(data: &[f64]) -> (f64, f64, f64) {
-        if data.is_empty() { return (0.0, 0.0, 0.0); }
-        let mean = data.iter().sum::<f64>() / (data.len() as f64);
-        let mut v = data.to_vec();
-        v.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-        let idx50 = ((v.len() - 1) as f64 * 0.50).round() as usize;
-        let idx95 = ((v.len() - 1) as f64 * 0.95).round() as usize;
-        (mean, v[idx50], v[idx95])
+    /// Y of `value` in the coordinate space of `sparkline_points(data, ..)`,
+    /// clamped to the chart area.
+    fn sparkline_y(data: &[f64], value: f64, height: f64) -> f64 {
+        let (min, max) = data.iter().filter(|v| v.is_finite()).fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), &v| (mn.min(v), mx.max(v)));
+        let range = if (max - min).abs() < 1e-9 { 1.0 } else { max - min };
+        (height - ((value - min) / range) * height).clamp(0.0, height)
+    }
+
+    /// High/low wick per candle as `(x, y_high, y_low, color)` in SVG space.
+    /// Candles with a non-finite high or low get no wick.
+    fn candle_lines(data: &[Candle], width: f64, height: f64, theme: &Theme) -> Vec<(f64, f64, f64, &'static str)> {
+        let finite = |c: &&Candle| c.low.is_finite() && c.high.is_finite();
+        if !data.iter().any(|c| finite(&c)) { return Vec::new(); }
+        let (min, max) = data.iter().filter(finite).fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), c| (mn.min(c.low), mx.max(c.high)));
+        let range = if (max - min).abs() < 1e-9 { 1.0 } else { max - min };
+        let step = width / data.len() as f64;
+        data.iter().enumerate().filter(|(_, c)| finite(c)).map(|(i, c)| {
+            let x = step * (i as f64 + 0.5);
+            let y_high = height - ((c.high - min) / range) * height;
+            let y_low = height - ((c.low - min) / range) * height;
+            let color = if c.close >= c.open { theme.up } else { theme.down };
+            (x, y_high, y_low, color)
+        }).collect()
     }
 
     view! {
@@ -265,116 +2390,590 @@ pub fn App() -> impl IntoView {
         <Title text="Leptos Live Data Performance Test"/>
 
         <Router>
-            <main style="padding: 1rem; font-family: system-ui;">
+            <main style=move || format!("padding: 1rem; font-family: system-ui;color:{};", theme.read().text)>
                 <Routes fallback=|| "Page not found.".into_view()>
                     <Route path=StaticSegment("") view=HomePage/>
                 </Routes>
                 
+                <Show when=move || server_shutdown.get()>
+                    <div style=move || format!("padding:0.75rem 1rem;margin-bottom:1rem;background:{};border:1px solid {};border-radius:4px;font-weight:bold;", theme.read().banner_warn_background, theme.read().banner_warn_border)>
+                        "⚠️ Server shutting down, reconnecting when it is back"
+                    </div>
+                </Show>
+                {move || schema_mismatch.get().map(|v| view! {
+                    <div style=move || format!("padding:0.75rem 1rem;margin-bottom:1rem;background:{};border:1px solid {};border-radius:4px;font-weight:bold;", theme.read().banner_error_background, theme.read().down)>
+                        {format!("⚠️ Server message schema v{} does not match client v{}; reload or update the client", v, SCHEMA_VERSION)}
+                    </div>
+                })}
                 <h1>"🚀 Real-time Market Data Stream"</h1>
-                <p style="color: #666;">
-                    {move || format!("Total messages: {} | FPS: {:.1}", 
+                <p style=move || format!("color: {};", theme.read().subtle)>
+                    <span role="status"
+                        style=move || format!("color:{};font-weight:bold;cursor:{};", conn_state.read().color(&theme.read()),
+                            if conn_state.get() == ConnState::Disconnected { "pointer" } else { "default" })
+                        on:click=move |_| if conn_state.get_untracked() == ConnState::Disconnected { reconnect_requests.update(|n| *n += 1) }>
+                        {move || format!("● {}", conn_state.read().label())}
+                    </span>
+                    {move || {
+                        let (bytes, deferred) = send_backlog.get();
+                        (bytes > SEND_BUFFER_MAX || deferred > 0).then(|| view! {
+                            <span role="status" style=move || format!("margin-left:0.5rem;color:{};font-weight:bold;", theme.read().warn)
+                                title="The uplink is slower than we send: control messages wait until the socket's send buffer drains, pings are skipped">
+                                {format!("⏫ send backpressure: {:.0} KB buffered, {} held back", bytes as f64 / 1024.0, deferred)}
+                            </span>
+                        })
+                    }}
+                    <button
+                        style=move || format!("margin-left:0.5rem;border:1px solid {};background:{};color:{};border-radius:4px;cursor:pointer;", theme.read().input_border, theme.read().input_background, theme.read().text)
+                        title="Connect again now and restart the backoff"
+                        on:click=move |_| reconnect_requests.update(|n| *n += 1)>
+                        "Reconnect now"
+                    </button>
+                    {move || format!(" | Total messages: {} | Parse errors: {} | Unknown: {} | Dropped: {} | FPS: {:.1}", 
                         *msg_count.read(), 
+                        *parse_errors.read(),
+                        *unknown_msgs.read(),
+                        *dropped.read(),
                         fps_values.read().last().cloned().unwrap_or(0.0)
                     )}
+                    " | "
+                    <button
+                        style=move || format!("border:1px solid {};background:{};color:{};border-radius:4px;cursor:pointer;", theme.read().input_border, theme.read().input_background, theme.read().text)
+                        on:click=move |_| {
+                            theme.update(|t| *t = if t.dark { Theme::LIGHT } else { Theme::DARK });
+                            #[cfg(feature = "hydrate")]
+                            save_theme(theme.get_untracked());
+                        }
+                    >
+                        {move || if theme.read().dark { "☀️ Light" } else { "🌙 Dark" }}
+                    </button>
                 </p>
 
                 // Price charts for each symbol
                 <section style="margin: 2rem 0;">
-                    <h2>"📈 Live Price Feeds"</h2>
-                    <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(280px,1fr));gap:1rem;">
+                    <h2>
+                        "📈 Live Price Feeds"{spark_style_picker("price")}
+                        <button
+                            style=move || format!("margin-left:0.5rem;border:1px solid {};background:{};color:{};border-radius:4px;cursor:pointer;font-size:0.8rem;", theme.read().input_border, theme.read().input_background, theme.read().text)
+                            title="Place samples by their timestamp instead of evenly, so stalls in the feed show"
+                            on:click=move |_| time_axis.update(|t| *t = !*t)
+                        >
+                            {move || if time_axis.get() { "⏱ time" } else { "# index" }}
+                        </button>
+                        {reset_button(theme, "Clear the price history", reset_prices)}
+                    </h2>
+                    <Show when=move || !stream_paused("price") fallback=paused_note>
+                        <div role="list" aria-label="Market summary"
+                            style=move || format!("display:flex;flex-wrap:wrap;gap:0.25rem 1.25rem;padding:0.5rem;margin-bottom:1rem;border:1px solid {};border-radius:4px;font-size:0.85rem;", theme.read().border)>
+                            {move || {
+                                let snapshot = frozen.read();
+                                let live_prices;
+                                let p = match snapshot.as_ref() {
+                                    Some(s) => &s.prices,
+                                    None => {
+                                        live_prices = prices.read();
+                                        &*live_prices
+                                    }
+                                };
+                                let sub = subscribed.read();
+                                // Symbols without a finite price yet are left out
+                                let mut symbols: Vec<_> = p.iter()
+                                    .filter(|(s, _)| is_subscribed(&sub, s))
+                                    .filter_map(|(s, data)| Some((s, session_summary(data)?)))
+                                    .collect();
+                                symbols.sort_by(|a, b| a.0.cmp(b.0));
+                                symbols.into_iter().map(|(symbol, summary)| {
+                                    let spec = FormatSpec::for_symbol(symbol);
+                                    let change = summary.change_pct.unwrap_or(0.0);
+                                    view! {
+                                        <span role="listitem" style="white-space:nowrap;">
+                                            <strong>{symbol.clone()}</strong>" "
+                                            {spec.format(summary.last)}" "
+                                            <span style=move || format!("color:{};", if change > 0.0 { theme.read().up } else if change < 0.0 { theme.read().down } else { theme.read().muted })>
+                                                {summary.change_pct.map(|c| format!("{:+.2}%", c)).unwrap_or_else(|| "n/a".into())}
+                                            </span>
+                                            <span style=move || format!("margin-left:0.4rem;color:{};", theme.read().muted)>
+                                                {format!("H {} L {}", spec.format(summary.high), spec.format(summary.low))}
+                                            </span>
+                                        </span>
+                                    }
+                                }).collect::<Vec<_>>()
+                            }}
+                        </div>
+                        <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(280px,1fr));gap:1rem;">
+                            {move || {
+                                // Only track the live signals while not paused
+                                let snapshot = frozen.read();
+                                let (live_prices, live_times, live_candles, live_vwap);
+                                let (p, pt, c, vw) = match snapshot.as_ref() {
+                                    Some(s) => (&s.prices, &s.price_times, &s.candles, &s.vwap_trades),
+                                    None => {
+                                        live_prices = prices.read();
+                                        live_times = price_times.read();
+                                        live_candles = candles.read();
+                                        live_vwap = vwap_trades.read();
+                                        (&*live_prices, &*live_times, &*live_candles, &*live_vwap)
+                                    }
+                                };
+                                let by_time = time_axis.get();
+                                let sub = subscribed.read();
+                                let mut symbols: Vec<_> = p.keys().filter(|s| is_subscribed(&sub, s)).cloned().collect();
+                                symbols.sort();
+                                symbols.into_iter().map(|symbol| {
+                                    let data = p.get(&symbol).cloned().unwrap_or_default();
+                                    // Index mode passes no timestamps
+                                    let times = if by_time { pt.get(&symbol).cloned() } else { None };
+                                    let latest = data.last().cloned().unwrap_or(0.0);
+                                    let st = stats(&data);
+                                    let symbol_candles = c.get(&symbol).cloned().unwrap_or_default();
+                                    let symbol_vwap = vw.get(&symbol).map(|t| vwap(t)).filter(|v| *v > 0.0);
+                                    let spec = FormatSpec::for_symbol(&symbol);
+                                    // Cards are rebuilt on every tick; a negative delay
+                                    // resumes the fade where the previous render was
+                                    let fade = |events: &std::collections::HashMap<String, f64>, name: &str, duration: f64| {
+                                        events.get(&symbol)
+                                            .map(|ts| now_ms() - ts)
+                                            .filter(|age| *age < duration)
+                                            .map(|age| format!("animation:{} {}ms ease-out -{}ms forwards;", name, duration, age))
+                                    };
+                                    // An alert outranks an anomaly highlight
+                                    let highlight = fade(&alert_flash.read(), "alert-flash", ALERT_FLASH_MS)
+                                        .or_else(|| fade(&anomalies.read(), "anomaly-fade", ANOMALY_FADE_MS))
+                                        .unwrap_or_default();
+                                    view! {
+                                        <div class="price-card" tabindex="0" style=format!("border:1px solid {};padding:0.5rem;border-radius:4px;{}", theme.read().border, highlight)>
+                                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">
+                                                {symbol.clone()}
+                                                {degraded_feeds.read().contains(&symbol).then(|| view! {
+                                                    <span style=move || format!("margin-left:0.5rem;font-size:0.75rem;font-weight:normal;color:{};", theme.read().down)
+                                                        title="The server can't reach this market's upstream feed and is backing off; prices may be stale">
+                                                        "⚠ feed degraded"
+                                                    </span>
+                                                })}
+                                            </h3>
+                                            <p style=move || format!("margin:0;font-size:1.5rem;font-weight:bold;color:{};", theme.read().accent)>
+                                                {spec.format(latest)}
+                                            </p>
+                                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;"
+                                                role="img" aria-label=sparkline_label(&symbol, &spec.format(latest), &data)>
+                                                <path fill=move || theme.read().accent fill-opacity="0.2" stroke="none"
+                                                    d={match &times {
+                                                        Some(t) => sparkline_area_timed(&data, t, 300.0, 60.0, spark_style("price")),
+                                                        None => sparkline_area(&data, 300.0, 60.0, spark_style("price")),
+                                                    }} />
+                                                <path stroke=move || theme.read().accent fill="none" stroke-width="2"
+                                                    d={match &times {
+                                                        Some(t) => sparkline_points_timed(&data, t, 300.0, 60.0, spark_style("price")),
+                                                        None => sparkline_points(&data, 300.0, 60.0, spark_style("price")),
+                                                    }} />
+                                                {symbol_vwap.filter(|_| !data.is_empty()).map(|v| {
+                                                    let y = sparkline_y(&data, v, 60.0);
+                                                    view! {
+                                                        <line x1="0" y1=y x2="300" y2=y stroke=move || theme.read().warn stroke-width="1.5" stroke-dasharray="4 3" />
+                                                    }
+                                                })}
+                                                {crossover_points(&data, sma_fast.get(), sma_slow.get()).into_iter().filter(|&(i, _)| data[i].is_finite()).map(|(i, buy)| {
+                                                    let x = match &times {
+                                                        Some(t) => sparkline_x_timed(&data, t, i, 300.0),
+                                                        None => sparkline_x(data.len(), i, 300.0),
+                                                    };
+                                                    let y = sparkline_y(&data, data[i], 60.0);
+                                                    // Buy points up, sell points down
+                                                    let (points, color) = if buy {
+                                                        (format!("{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}", x, y - 5.0, x - 4.0, y + 3.0, x + 4.0, y + 3.0), theme.read().up)
+                                                    } else {
+                                                        (format!("{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}", x, y + 5.0, x - 4.0, y - 3.0, x + 4.0, y - 3.0), theme.read().down)
+                                                    };
+                                                    view! { <polygon points=points fill=color /> }
+                                                }).collect::<Vec<_>>()}
+                                            </svg>
+                                            {times.as_ref().map(|t| time_axis_labels(&data, t, 3)).filter(|labels| !labels.is_empty()).map(|labels| view! {
+                                                <div style=move || format!("display:flex;justify-content:space-between;font-size:0.65rem;color:{};", theme.read().muted)>
+                                                    {labels.into_iter().map(|label| view! { <span>{label}</span> }).collect::<Vec<_>>()}
+                                                </div>
+                                            })}
+                                            <svg width="100%" height="40" viewBox="0 0 300 40"
+                                                role="img" aria-label=format!("{} candles, {} completed", symbol, symbol_candles.len())>
+                                                {candle_lines(&symbol_candles, 300.0, 40.0, &theme.read()).into_iter().map(|(x, y1, y2, color)| view! {
+                                                    <line x1=x y1=y1 x2=x y2=y2 stroke=color stroke-width="2" />
+                                                }).collect::<Vec<_>>()}
+                                            </svg>
+                                            <p style=move || format!("margin:0.5rem 0 0 0;font-size:0.75rem;color:{};", theme.read().muted)>
+                                                {st.summary()}
+                                            </p>
+                                            {symbol_vwap.map(|v| view! {
+                                                <p style=move || format!("margin:0.25rem 0 0 0;font-size:0.75rem;color:{};", theme.read().warn)>
+                                                    {format!("VWAP {} (dashed)", spec.format(v))}
+                                                </p>
+                                            })}
+                                        </div>
+                                    }
+                                }).collect::<Vec<_>>()
+                            }}
+                        </div>
+                    </Show>
+                </section>
+
+                // Pairwise correlation of the price returns
+                <section style="margin: 2rem 0;">
+                    <h2>"🧮 Return Correlation"</h2>
+                    <Show when=move || !stream_paused("price") fallback=paused_note>
                         {move || {
-                            let p = prices.read();
-                            let mut symbols: Vec<_> = p.keys().cloned().collect();
+                            let snapshot = frozen.read();
+                            let live_prices;
+                            let p = match snapshot.as_ref() {
+                                Some(s) => &s.prices,
+                                None => {
+                                    live_prices = prices.read();
+                                    &*live_prices
+                                }
+                            };
+                            let sub = subscribed.read();
+                            let mut symbols: Vec<_> = p.keys().filter(|s| is_subscribed(&sub, s)).cloned().collect();
                             symbols.sort();
-                            symbols.into_iter().map(|symbol| {
-                                let data = p.get(&symbol).cloned().unwrap_or_default();
-                                let latest = data.last().cloned().unwrap_or(0.0);
-                                let (mean, p50, p95) = stats(&data);
+                            let cell = "padding:0.35rem 0.5rem;text-align:center;font-family:monospace;font-size:0.8rem;";
+                            let header = symbols.iter().map(|s| view! {
+                                <div style=format!("{}font-weight:bold;", cell)>{s.clone()}</div>
+                            }).collect::<Vec<_>>();
+                            let rows = symbols.iter().map(|a| {
+                                let cells = symbols.iter().map(|b| {
+                                    let r = rolling_correlation(&p[a], &p[b]);
+                                    let background = r.map(correlation_color).unwrap_or_default();
+                                    let label = r.map(|r| format!("{:+.2}", r)).unwrap_or_else(|| "–".into());
+                                    view! {
+                                        <div style=format!("{}background:{};", cell, background)>{label}</div>
+                                    }
+                                }).collect::<Vec<_>>();
                                 view! {
-                                    <div style="border:1px solid #ddd;padding:0.5rem;border-radius:4px;">
-                                        <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">{symbol.clone()}</h3>
-                                        <p style="margin:0;font-size:1.5rem;font-weight:bold;color:#0066cc;">
-                                            {format!("${:.2}", latest)}
-                                        </p>
-                                        <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;">
-                                            <polyline stroke="#0066cc" fill="none" stroke-width="2"
-                                                points={sparkline_points(&data, 300.0, 60.0)} />
-                                        </svg>
-                                        <p style="margin:0.5rem 0 0 0;font-size:0.75rem;color:#888;">
-                                            {format!("μ:{:.2} p50:{:.2} p95:{:.2}", mean, p50, p95)}
-                                        </p>
-                                    </div>
+                                    <div style=format!("{}font-weight:bold;text-align:right;", cell)>{a.clone()}</div>
+                                    {cells}
                                 }
-                            }).collect::<Vec<_>>()
+                            }).collect::<Vec<_>>();
+                            let columns = symbols.len() + 1;
+                            view! {
+                                <div style=move || format!(
+                                    "display:inline-grid;grid-template-columns:repeat({}, auto);gap:1px;border:1px solid {};border-radius:4px;",
+                                    columns, theme.read().border
+                                )>
+                                    <div></div>
+                                    {header}
+                                    {rows}
+                                </div>
+                            }
                         }}
-                    </div>
+                    </Show>
                 </section>
 
-                // Recent trades feed
+                // Latest decoded CAN signals
+                <Show when=move || !vehicle_signals.read().is_empty()>
+                    <section style="margin: 2rem 0;">
+                        <h2>
+                            "🚗 Vehicle Signals"
+                            <button
+                                style=move || format!("margin-left:0.5rem;border:1px solid {};background:{};color:{};border-radius:4px;cursor:pointer;font-size:0.8rem;", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:click=move |_| {
+                                    unit_system.update(|u| *u = if *u == UnitSystem::Metric { UnitSystem::Imperial } else { UnitSystem::Metric });
+                                    #[cfg(feature = "hydrate")]
+                                    save_units(unit_system.get_untracked());
+                                }
+                            >
+                                {move || if unit_system.get() == UnitSystem::Metric { "Metric" } else { "Imperial" }}
+                            </button>
+                        </h2>
+                        <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(180px,1fr));gap:1rem;">
+                            {move || {
+                                let snapshot = frozen.read();
+                                let live;
+                                let signals = match snapshot.as_ref() {
+                                    Some(s) => &s.vehicle_signals,
+                                    None => {
+                                        live = vehicle_signals.read();
+                                        &*live
+                                    }
+                                };
+                                let mut names: Vec<_> = signals.keys().cloned().collect();
+                                names.sort();
+                                names.into_iter().map(|name| {
+                                    let (value, unit, source) = signals[&name].clone();
+                                    let (value, unit) = units::convert(value, &unit, unit_system.get());
+                                    view! {
+                                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">
+                                                {name.clone()}
+                                                <span style=move || format!("margin-left:0.5rem;font-family:monospace;font-size:0.75rem;color:{};", theme.read().muted)>{source}</span>
+                                            </h3>
+                                            <p style=move || format!("margin:0;font-size:1.5rem;font-weight:bold;color:{};", theme.read().accent)>
+                                                {format_signal_value(value, &unit)}
+                                            </p>
+                                        </div>
+                                    }
+                                }).collect::<Vec<_>>()
+                            }}
+                        </div>
+                    </section>
+                </Show>
+
+                // Moving-average crossover log
                 <section style="margin: 2rem 0;">
-                    <h2>"💱 Recent Trades"</h2>
-                    <div style="max-height:200px;overflow-y:auto;border:1px solid #ddd;padding:0.5rem;border-radius:4px;font-family:monospace;font-size:0.85rem;">
+                    <h2>{move || format!("🔀 SMA Crossovers ({}/{})", sma_fast.get(), sma_slow.get())}</h2>
+                    <div style=move || format!("max-height:200px;overflow-y:auto;border:1px solid {};padding:0.5rem;border-radius:4px;font-family:monospace;font-size:0.85rem;", theme.read().border)>
                         {move || {
-                            let t = trades.read();
-                            t.iter().rev().take(20).map(|(symbol, price, side)| {
-                                let color = if side == "buy" { "#00cc66" } else { "#ff6666" };
+                            let log = crossovers.read();
+                            if log.is_empty() {
+                                return vec![view! { <div style=move || format!("color:{};", theme.read().muted)>"No crossovers yet"</div> }.into_any()];
+                            }
+                            log.iter().rev().map(|c| {
+                                let (label, color) = if c.buy { ("▲ BUY", theme.read().up) } else { ("▼ SELL", theme.read().down) };
                                 view! {
-                                    <div style=format!("padding:0.25rem;border-bottom:1px solid #f0f0f0;color:{}", color)>
-                                        {format!("{} ${:.2} {}", symbol, price, side.to_uppercase())}
+                                    <div style=format!("padding:0.25rem;border-bottom:1px solid {};color:{}", theme.read().divider, color)>
+                                        {format!("{} {} {} @ {}", clock_utc(c.ts), label, c.symbol, FormatSpec::for_symbol(&c.symbol).format(c.price))}
                                     </div>
-                                }
+                                }.into_any()
                             }).collect::<Vec<_>>()
                         }}
                     </div>
                 </section>
 
+                // Multi-user measurements (/mu)
+                <MeasurementDashboard/>
+
+                // Simulated CAN buses (USE_CAN=true)
+                <Show when=move || !can_buses.read().is_empty()>
+                    <section style="margin: 2rem 0;">
+                        <h2>"🚌 CAN Buses"</h2>
+                        <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(220px,1fr));gap:1rem;">
+                            {move || can_buses.read().iter().map(|(bus_id, bus)| view! {
+                                <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                                    <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">{format!("Bus {}", bus_id)}</h3>
+                                    <p style=move || format!("margin:0;font-size:1.5rem;font-weight:bold;color:{};", theme.read().accent)>
+                                        {format!("{:.1}% load", bus.load_percent)}
+                                    </p>
+                                    <p style=move || format!("margin:0.5rem 0 0 0;font-size:0.75rem;color:{};", theme.read().muted)>
+                                        {format!(
+                                            "{:.0} msg/s | {:.0} B/s | errors {} | latency avg {}µs max {}µs | frames {}",
+                                            bus.messages_per_sec, bus.bytes_per_sec, bus.error_count,
+                                            bus.avg_latency_us, bus.max_latency_us, bus.frames
+                                        )}
+                                    </p>
+                                </div>
+                            }).collect::<Vec<_>>()}
+                        </div>
+                    </section>
+                </Show>
+
+                // Recent trades feed
+                <section style="margin: 2rem 0;">
+                    <h2>"💱 Recent Trades"{reset_button(theme, "Clear the trades", reset_trades)}</h2>
+                    <Show when=move || !stream_paused("trade") fallback=paused_note>
+                        // Buy vs. sell volume of the last trades per symbol
+                        <div style="display:grid;grid-template-columns:auto 1fr auto;gap:0.25rem 0.5rem;align-items:center;margin-bottom:0.75rem;font-family:monospace;font-size:0.85rem;">
+                            {move || {
+                                let snapshot = frozen.read();
+                                let live;
+                                let t = match snapshot.as_ref() {
+                                    Some(s) => &s.trades,
+                                    None => {
+                                        live = trades.read();
+                                        &*live
+                                    }
+                                };
+                                let sub = subscribed.read();
+                                let mut windows = std::collections::BTreeMap::<String, Vec<(String, f64, f64, String)>>::new();
+                                for trade in t.iter().rev().filter(|(symbol, ..)| is_subscribed(&sub, symbol)) {
+                                    let window = windows.entry(trade.0.clone()).or_default();
+                                    if window.len() < IMBALANCE_WINDOW {
+                                        window.push(trade.clone());
+                                    }
+                                }
+                                windows.into_iter().map(|(symbol, window)| {
+                                    let imbalance = volume_imbalance(&window);
+                                    let color = if imbalance >= 0.0 { theme.read().up } else { theme.read().down };
+                                    // The bar grows from the center: right for buys, left for sells
+                                    let left = 50.0 + imbalance.min(0.0) * 50.0;
+                                    let width = imbalance.abs() * 50.0;
+                                    view! {
+                                        <span>{symbol}</span>
+                                        <div style=format!("position:relative;height:0.75rem;border:1px solid {};border-radius:2px;", theme.read().border)>
+                                            <div style=format!("position:absolute;top:0;bottom:0;left:{:.1}%;width:{:.1}%;background:{};", left, width, color)></div>
+                                            <div style=format!("position:absolute;top:0;bottom:0;left:50%;width:1px;background:{};", theme.read().muted)></div>
+                                        </div>
+                                        <span style=format!("color:{};", color)>{format!("{:+.2}", imbalance)}</span>
+                                    }
+                                }).collect::<Vec<_>>()
+                            }}
+                        </div>
+                        // The server's per-second summary of each symbol's trades
+                        <div style="display:grid;grid-template-columns:repeat(5,auto);gap:0.1rem 0.75rem;justify-content:start;margin-bottom:0.75rem;font-family:monospace;font-size:0.8rem;">
+                            {move || {
+                                let snapshot = frozen.read();
+                                let live;
+                                let summaries = match snapshot.as_ref() {
+                                    Some(s) => &s.trade_summaries,
+                                    None => {
+                                        live = trade_summaries.read();
+                                        &*live
+                                    }
+                                };
+                                let sub = subscribed.read();
+                                let rows: Vec<_> = summaries.iter().filter(|(symbol, _)| is_subscribed(&sub, symbol)).collect();
+                                if rows.is_empty() {
+                                    return Vec::new();
+                                }
+                                let muted = theme.read().muted;
+                                let header = ["symbol", "trades/s", "volume", "vwap", "buy %"].map(|title| view! {
+                                    <span style=format!("color:{};", muted)>{title}</span>
+                                });
+                                let mut cells: Vec<_> = header.into_iter().map(|cell| cell.into_any()).collect();
+                                for (symbol, summary) in rows {
+                                    let spec = FormatSpec::for_symbol(symbol);
+                                    let color = if summary.buy_ratio >= 0.5 { theme.read().up } else { theme.read().down };
+                                    cells.push(view! { <span>{symbol.clone()}</span> }.into_any());
+                                    cells.push(view! { <span>{summary.count}</span> }.into_any());
+                                    cells.push(view! { <span>{format!("{:.3}", summary.volume)}</span> }.into_any());
+                                    cells.push(view! { <span>{spec.format(summary.vwap)}</span> }.into_any());
+                                    cells.push(view! { <span style=format!("color:{};", color)>{format!("{:.0}%", summary.buy_ratio * 100.0)}</span> }.into_any());
+                                }
+                                cells
+                            }}
+                        </div>
+                        <div style="display:flex;gap:0.5rem;flex-wrap:wrap;align-items:center;margin-bottom:0.5rem;font-size:0.9rem;">
+                            <select title="Symbol"
+                                style=move || format!("padding:0.25rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    let symbol = event_target_value(&ev);
+                                    tape_symbol.set((!symbol.is_empty()).then_some(symbol));
+                                }>
+                                <option value="" selected>"All symbols"</option>
+                                {move || available_symbols.get().into_iter().map(|s| view! { <option value=s.clone()>{s.clone()}</option> }).collect::<Vec<_>>()}
+                            </select>
+                            <select title="Side"
+                                style=move || format!("padding:0.25rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    let side = event_target_value(&ev);
+                                    tape_side.set((!side.is_empty()).then_some(side));
+                                }>
+                                <option value="" selected>"Buy & sell"</option>
+                                <option value="buy">"Buy"</option>
+                                <option value="sell">"Sell"</option>
+                            </select>
+                            <label>
+                                <input type="checkbox"
+                                    prop:checked=move || tape_by_price.get()
+                                    on:change=move |ev| tape_by_price.set(event_target_checked(&ev)) />
+                                " Sort by price"
+                            </label>
+                        </div>
+                        <div style=move || format!("max-height:200px;overflow-y:auto;border:1px solid {};padding:0.5rem;border-radius:4px;font-family:monospace;font-size:0.85rem;", theme.read().border)>
+                            {move || {
+                                let snapshot = frozen.read();
+                                let live;
+                                let t = match snapshot.as_ref() {
+                                    Some(s) => &s.trades,
+                                    None => {
+                                        live = trades.read();
+                                        &*live
+                                    }
+                                };
+                                let sub = subscribed.read();
+                                let (only_symbol, only_side) = (tape_symbol.read(), tape_side.read());
+                                // Filtered over the whole buffer so rare symbols still show up
+                                let mut shown: Vec<_> = t
+                                    .iter()
+                                    .rev()
+                                    .filter(|(symbol, ..)| is_subscribed(&sub, symbol))
+                                    .filter(|(symbol, ..)| only_symbol.as_ref().is_none_or(|s| s == symbol))
+                                    .filter(|(.., side)| only_side.as_ref().is_none_or(|s| s == side))
+                                    .collect();
+                                if tape_by_price.get() {
+                                    shown.sort_by(|a, b| b.1.total_cmp(&a.1));
+                                }
+                                shown.into_iter().take(20).map(|(symbol, price, size, side)| {
+                                    let color = if side == "buy" { theme.read().up } else { theme.read().down };
+                                    view! {
+                                        <div style=format!("padding:0.25rem;border-bottom:1px solid {};color:{}", theme.read().divider, color)>
+                                            {format!("{} {} x{:.4} {}", symbol, FormatSpec::for_symbol(symbol).format(*price), size, side.to_uppercase())}
+                                        </div>
+                                    }
+                                }).collect::<Vec<_>>()
+                            }}
+                        </div>
+                    </Show>
+                </section>
+
                 // Order book depth
                 <section style="margin: 2rem 0;">
-                    <h2>"📊 Order Book Depth"</h2>
-                    <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(280px,1fr));gap:1rem;">
-                        {move || {
-                            let books = book_depth.read();
-                            let mut symbols: Vec<_> = books.keys().cloned().collect();
-                            symbols.sort();
-                            symbols.into_iter().map(|symbol| {
-                                let (bids, asks) = books.get(&symbol).cloned().unwrap_or_default();
-                                view! {
-                                    <div style="border:1px solid #ddd;padding:0.5rem;border-radius:4px;font-family:monospace;font-size:0.8rem;">
-                                        <h3 style="margin:0 0 0.5rem 0;font-size:0.9rem;">{symbol.clone()}</h3>
-                                        <div style="display:grid;grid-template-columns:1fr 1fr;gap:0.5rem;">
-                                            <div>
-                                                <strong style="color:#00cc66;">"BIDS"</strong>
-                                                {bids.iter().take(5).map(|p| view! {
-                                                    <div>{format!("${:.2}", p)}</div>
-                                                }).collect::<Vec<_>>()}
+                    <h2>"📊 Order Book Depth"{reset_button(theme, "Clear the order books", reset_book)}</h2>
+                    <Show when=move || !stream_paused("book") fallback=paused_note>
+                        <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(280px,1fr));gap:1rem;">
+                            {move || {
+                                let snapshot = frozen.read();
+                                let live;
+                                let books = match snapshot.as_ref() {
+                                    Some(s) => &s.book_depth,
+                                    None => {
+                                        live = book_depth.read();
+                                        &*live
+                                    }
+                                };
+                                let sub = subscribed.read();
+                                let mut symbols: Vec<_> = books.keys().filter(|s| is_subscribed(&sub, s)).cloned().collect();
+                                symbols.sort();
+                                symbols.into_iter().map(|symbol| {
+                                    let (bids, asks) = books.get(&symbol).cloned().unwrap_or_default();
+                                    let bid_prices: Vec<f64> = bids.iter().map(|(p, _)| *p).collect();
+                                    let ask_prices: Vec<f64> = asks.iter().map(|(p, _)| *p).collect();
+                                    let spec = FormatSpec::for_symbol(&symbol);
+                                    let spread = spread_metrics(&bid_prices, &ask_prices)
+                                        .map(|m| m.summary(&spec))
+                                        .unwrap_or_else(|| "empty book".into());
+                                    let bids = cumulative_depth(&bids, true);
+                                    let asks = cumulative_depth(&asks, false);
+                                    // Both sides share one scale so their depth is comparable
+                                    let max_cum = bids.iter().chain(asks.iter()).map(|(_, c)| *c).fold(0.0, f64::max);
+                                    let bar = move |(price, cum): (f64, f64), color: &'static str, spec: &FormatSpec| {
+                                        let pct = if max_cum > 0.0 { cum / max_cum * 100.0 } else { 0.0 };
+                                        view! {
+                                            <div style="position:relative;padding:0 0.25rem;">
+                                                <div style=format!("position:absolute;inset:0 auto 0 0;width:{:.1}%;background:{};opacity:0.25;", pct, color)></div>
+                                                <span style="position:relative;">{format!("{} ", spec.format(price))}</span>
+                                                <span style=move || format!("position:relative;color:{};", theme.read().subtle)>{format!("{:.2}", cum)}</span>
                                             </div>
-                                            <div>
-                                                <strong style="color:#ff6666;">"ASKS"</strong>
-                                                {asks.iter().take(5).map(|p| view! {
-                                                    <div>{format!("${:.2}", p)}</div>
-                                                }).collect::<Vec<_>>()}
+                                        }
+                                    };
+                                    view! {
+                                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;font-family:monospace;font-size:0.8rem;", theme.read().border)>
+                                            <h3 style="margin:0 0 0.5rem 0;font-size:0.9rem;">{symbol.clone()}</h3>
+                                            <div style=move || format!("margin-bottom:0.5rem;color:{};", theme.read().subtle)>{spread}</div>
+                                            <div style="display:grid;grid-template-columns:1fr 1fr;gap:0.5rem;">
+                                                <div>
+                                                    <strong style=move || format!("color:{};", theme.read().up)>"BIDS"</strong>
+                                                    {bids.into_iter().take(5).map(|level| bar(level, theme.read().up, &spec)).collect::<Vec<_>>()}
+                                                </div>
+                                                <div>
+                                                    <strong style=move || format!("color:{};", theme.read().down)>"ASKS"</strong>
+                                                    {asks.into_iter().take(5).map(|level| bar(level, theme.read().down, &spec)).collect::<Vec<_>>()}
+                                                </div>
                                             </div>
                                         </div>
-                                    </div>
-                                }
-                            }).collect::<Vec<_>>()
-                        }}
-                    </div>
+                                    }
+                                }).collect::<Vec<_>>()
+                            }}
+                        </div>
+                    </Show>
                 </section>
 
                 // Performance metrics
                 <section style="margin: 2rem 0;">
                     <h2>"⚡ Performance Metrics"</h2>
                     <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(280px,1fr));gap:1rem;">
-                        <div style="border:1px solid #ddd;padding:0.5rem;border-radius:4px;">
-                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"💬 Momentane Message Rate (msg/s)"</h3>
-                            <p style="margin:0;font-size:1.5rem;font-weight:bold;color:#9933ff;">
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"💬 Momentane Message Rate (msg/s)"{spark_style_picker("msg_rate")}{reset_button(theme, "Reset the message and byte rates", reset_msg_rate)}</h3>
+                            <p style=move || format!("margin:0;font-size:1.5rem;font-weight:bold;color:{};", theme.read().volume)>
                                 {move || {
                                     let current = msg_rate.read().last().cloned().unwrap_or(0.0);
                                     format!("{:.1}", current)
                                 }}
                             </p>
-                            <div style="font-size:0.75rem;color:#666;margin-top:0.25rem;">
+                            <div style=move || format!("font-size:0.75rem;color:{};margin-top:0.25rem;", theme.read().subtle)>
                                 {move || {
                                     let rates = msg_rate.read();
                                     if rates.len() >= 2 {
@@ -388,97 +2987,540 @@ pub fn App() -> impl IntoView {
                                     }
                                 }}
                             </div>
-                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;">
-                                <polyline stroke="#9933ff" fill="none" stroke-width="2"
-                                    points={move || sparkline_points(&msg_rate.read(), 300.0, 60.0)} />
+                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;" role="img"
+                                aria-label=move || {
+                                    let d = msg_rate.read();
+                                    sparkline_label("Message rate", &format!("{:.0} msg/s", d.last().copied().unwrap_or(0.0)), &d)
+                                }>
+                                <path fill=move || theme.read().volume fill-opacity="0.2" stroke="none"
+                                    d={move || sparkline_area(&msg_rate.read(), 300.0, 60.0, spark_style("msg_rate"))} />
+                                <path stroke=move || theme.read().volume fill="none" stroke-width="2"
+                                    d={move || sparkline_points(&msg_rate.read(), 300.0, 60.0, spark_style("msg_rate"))} />
+                            </svg>
+                        </div>
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"📶 Bandwidth"{spark_style_picker("bandwidth_values")}{reset_button(theme, "Reset the message and byte rates", reset_msg_rate)}</h3>
+                            <p style=move || format!("margin:0;font-size:1.5rem;font-weight:bold;color:{};", theme.read().volume)>
+                                {move || format_byte_rate(bandwidth_values.read().last().cloned().unwrap_or(0.0))}
+                            </p>
+                            <div style=move || format!("font-size:0.75rem;color:{};margin-top:0.25rem;", theme.read().subtle)>
+                                {move || {
+                                    // Bytes per message tell JSON and MessagePack apart at a glance
+                                    let (bytes, msgs) = (bandwidth_values.read().last().cloned(), msg_rate.read().last().cloned());
+                                    match (bytes, msgs) {
+                                        (Some(b), Some(m)) if m > 0.0 => format!("{:.0} bytes/msg as {}", b / m, if msgpack.get() { "MessagePack" } else { "JSON" }),
+                                        _ => "Warte auf Daten...".to_string(),
+                                    }
+                                }}
+                            </div>
+                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;" role="img"
+                                aria-label=move || {
+                                    let d = bandwidth_values.read();
+                                    sparkline_label("Bandwidth", &format_byte_rate(d.last().copied().unwrap_or(0.0)), &d)
+                                }>
+                                <path fill=move || theme.read().volume fill-opacity="0.2" stroke="none"
+                                    d={move || sparkline_area(&bandwidth_values.read(), 300.0, 60.0, spark_style("bandwidth_values"))} />
+                                <path stroke=move || theme.read().volume fill="none" stroke-width="2"
+                                    d={move || sparkline_points(&bandwidth_values.read(), 300.0, 60.0, spark_style("bandwidth_values"))} />
                             </svg>
                         </div>
-                        <div style="border:1px solid #ddd;padding:0.5rem;border-radius:4px;">
-                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"Render FPS"</h3>
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"🧩 Message Mix"</h3>
+                            {move || {
+                                // Most frequent first; colors cycle through the theme's chart colors
+                                let mut counts: Vec<(String, u64)> = msg_types.get().into_iter().collect();
+                                counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                                let total = counts.iter().map(|(_, n)| n).sum::<u64>().max(1) as f64;
+                                let t = theme.get();
+                                let palette = [t.accent, t.volume, t.up, t.down, t.warn, t.rate, t.neutral];
+                                let shares: Vec<(String, u64, f64, &'static str)> = counts
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(i, (name, n))| (name, n, n as f64 / total * 100.0, palette[i % palette.len()]))
+                                    .collect();
+                                if shares.is_empty() {
+                                    return view! { <p style=move || format!("margin:0;color:{};font-style:italic;", theme.read().muted)>"Warte auf Daten..."</p> }.into_any();
+                                }
+                                let label = shares.iter().map(|(name, _, pct, _)| format!("{} {:.0}%", name, pct)).collect::<Vec<_>>().join(", ");
+                                view! {
+                                    <div role="img" aria-label=format!("Message mix: {}", label)
+                                        style="display:flex;height:1.25rem;border-radius:4px;overflow:hidden;">
+                                        {shares.iter().map(|(name, n, pct, color)| view! {
+                                            <div title=format!("{}: {} ({:.1}%)", name, n, pct) style=format!("width:{:.2}%;background:{};", pct, color)></div>
+                                        }).collect::<Vec<_>>()}
+                                    </div>
+                                    <div style="display:grid;grid-template-columns:auto 1fr auto auto;gap:0.1rem 0.5rem;margin-top:0.5rem;font-size:0.8rem;align-items:center;">
+                                        {shares.into_iter().map(|(name, n, pct, color)| view! {
+                                            <span style=format!("width:0.6rem;height:0.6rem;border-radius:2px;background:{};", color)></span>
+                                            <span>{name}</span>
+                                            <span style="text-align:right;">{n}</span>
+                                            <span style=move || format!("text-align:right;color:{};", theme.read().subtle)>{format!("{:.1}%", pct)}</span>
+                                        }).collect::<Vec<_>>()}
+                                    </div>
+                                }.into_any()
+                            }}
+                        </div>
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"Render FPS"{spark_style_picker("fps_values")}{reset_button(theme, "Reset the frame rate", reset_fps)}</h3>
                             <p style="margin:0;font-size:1.5rem;font-weight:bold;">
                                 {move || format!("{:.1}", fps_values.read().last().cloned().unwrap_or(0.0))}
                             </p>
-                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;">
-                                <polyline stroke="#00cc66" fill="none" stroke-width="2"
-                                    points={move || sparkline_points(&fps_values.read(), 300.0, 60.0)} />
+                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;" role="img"
+                                aria-label=move || {
+                                    let d = fps_values.read();
+                                    sparkline_label("Render FPS", &format!("{:.1}", d.last().copied().unwrap_or(0.0)), &d)
+                                }>
+                                <path fill=move || theme.read().up fill-opacity="0.2" stroke="none"
+                                    d={move || sparkline_area(&fps_values.read(), 300.0, 60.0, spark_style("fps_values"))} />
+                                <path stroke=move || theme.read().up fill="none" stroke-width="2"
+                                    d={move || sparkline_points(&fps_values.read(), 300.0, 60.0, spark_style("fps_values"))} />
                             </svg>
                         </div>
-                        <div style="border:1px solid #ddd;padding:0.5rem;border-radius:4px;">
-                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"Latency (ms)"</h3>
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"Latency (ms)"{spark_style_picker("latency_values")}{reset_button(theme, "Reset the latency and parse times", reset_latency)}</h3>
                             <p style="margin:0;font-size:1.5rem;font-weight:bold;">
                                 {move || {
                                     let d = latency_values.read();
                                     format!("{:.2}", d.last().cloned().unwrap_or(0.0))
                                 }}
                             </p>
-                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;">
-                                <polyline stroke="#ff6666" fill="none" stroke-width="2"
-                                    points={move || sparkline_points(&latency_values.read(), 300.0, 60.0)} />
+                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;" role="img"
+                                aria-label=move || {
+                                    let d = latency_values.read();
+                                    sparkline_label("Latency", &format!("{:.2} ms", d.last().copied().unwrap_or(0.0)), &d)
+                                }>
+                                <path fill=move || theme.read().down fill-opacity="0.2" stroke="none"
+                                    d={move || sparkline_area(&latency_values.read(), 300.0, 60.0, spark_style("latency_values"))} />
+                                <path stroke=move || theme.read().down fill="none" stroke-width="2"
+                                    d={move || sparkline_points(&latency_values.read(), 300.0, 60.0, spark_style("latency_values"))} />
                             </svg>
-                            <p style="margin:0.5rem 0 0 0;font-size:0.75rem;color:#888;">
+                            // Distribution of the same samples; bimodal latency shows up as two humps
+                            <svg width="100%" height="62" viewBox="0 0 300 62" style="margin-top:0.25rem;" role="img"
+                                aria-label=move || format!("Latency distribution: {}", stats(&latency_values.read()).summary())>
                                 {move || {
-                                    let d = latency_values.read();
-                                    let (mean, p50, p95) = stats(&d);
-                                    format!("μ:{:.2} p50:{:.2} p95:{:.2}", mean, p50, p95)
+                                    let hist = histogram(&latency_values.read(), 20);
+                                    let Some(peak) = hist.iter().map(|(_, n)| *n).max() else { return Vec::new() };
+                                    let bar_w = 300.0 / hist.len() as f64;
+                                    let mut shapes = hist.iter().enumerate().map(|(i, (_, n))| {
+                                        let h = *n as f64 / peak as f64 * 50.0;
+                                        view! {
+                                            <rect x=i as f64 * bar_w y=50.0 - h width=(bar_w - 1.0).max(1.0) height=h fill=move || theme.read().down />
+                                        }.into_any()
+                                    }).collect::<Vec<_>>();
+                                    // Axis labels at both edges and the middle of the binned range
+                                    let half = if hist.len() > 1 { (hist[1].0 - hist[0].0) / 2.0 } else { 0.0 };
+                                    let (lo, hi) = (hist[0].0 - half, hist[hist.len() - 1].0 + half);
+                                    let ticks = if hist.len() == 1 {
+                                        vec![(150.0, "middle", lo)]
+                                    } else {
+                                        vec![(0.0, "start", lo), (150.0, "middle", (lo + hi) / 2.0), (300.0, "end", hi)]
+                                    };
+                                    shapes.extend(ticks.into_iter().map(|(x, anchor, v)| view! {
+                                        <text x=x y="61" font-size="9" fill=move || theme.read().muted text-anchor=anchor>{format!("{:.2}", v)}</text>
+                                    }.into_any()));
+                                    shapes
+                                }}
+                            </svg>
+                            <p style=move || format!("margin:0.5rem 0 0 0;font-size:0.75rem;color:{};", theme.read().muted)>
+                                {move || {
+                                    stats(&latency_values.read()).summary()
+                                }}
+                            </p>
+                            <p style=move || format!("margin:0.25rem 0 0 0;font-size:0.75rem;color:{};", theme.read().muted)>
+                                {move || {
+                                    let st = stats(&parse_times.read());
+                                    let fmt = if msgpack.get() { "MessagePack" } else { "JSON" };
+                                    format!("parse ({}) {:.1}µs avg | p95 {:.1}µs", fmt, st.mean * 1000.0, st.p95 * 1000.0)
+                                }}
+                            </p>
+                        </div>
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"Round-trip Time (ms)"{spark_style_picker("rtt_values")}</h3>
+                            <p style="margin:0;font-size:1.5rem;font-weight:bold;">
+                                {move || format!("{:.2}", rtt_values.read().last().cloned().unwrap_or(0.0))}
+                            </p>
+                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;" role="img"
+                                aria-label=move || {
+                                    let d = rtt_values.read();
+                                    sparkline_label("Round-trip time", &format!("{:.2} ms", d.last().copied().unwrap_or(0.0)), &d)
+                                }>
+                                <path fill=move || theme.read().warn fill-opacity="0.2" stroke="none"
+                                    d={move || sparkline_area(&rtt_values.read(), 300.0, 60.0, spark_style("rtt_values"))} />
+                                <path stroke=move || theme.read().warn fill="none" stroke-width="2"
+                                    d={move || sparkline_points(&rtt_values.read(), 300.0, 60.0, spark_style("rtt_values"))} />
+                            </svg>
+                            <p style=move || format!("margin:0.5rem 0 0 0;font-size:0.75rem;color:{};", theme.read().muted)>
+                                {move || stats(&rtt_values.read()).summary()}
+                            </p>
+                        </div>
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"E2E Latency (ms)"{spark_style_picker("e2e_values")}</h3>
+                            <p style="margin:0;font-size:1.5rem;font-weight:bold;">
+                                {move || format!("{:.2}", e2e_values.read().last().cloned().unwrap_or(0.0))}
+                            </p>
+                            <svg width="100%" height="60" viewBox="0 0 300 60" style="margin-top:0.5rem;" role="img"
+                                aria-label=move || {
+                                    let d = e2e_values.read();
+                                    sparkline_label("End-to-end latency", &format!("{:.2} ms", d.last().copied().unwrap_or(0.0)), &d)
+                                }>
+                                <path fill=move || theme.read().rate fill-opacity="0.2" stroke="none"
+                                    d={move || sparkline_area(&e2e_values.read(), 300.0, 60.0, spark_style("e2e_values"))} />
+                                <path stroke=move || theme.read().rate fill="none" stroke-width="2"
+                                    d={move || sparkline_points(&e2e_values.read(), 300.0, 60.0, spark_style("e2e_values"))} />
+                            </svg>
+                            <p style=move || format!("margin:0.5rem 0 0 0;font-size:0.75rem;color:{};", theme.read().muted)>
+                                {move || stats(&e2e_values.read()).summary()}
+                            </p>
+                            <p style=move || format!("margin:0.25rem 0 0 0;font-size:0.75rem;color:{};", theme.read().muted)>
+                                {move || match clock_skew.get() {
+                                    Some(skew) => format!("server clock {:+.1} ms, ±{:.1} ms", skew.offset_ms, skew.rtt_ms / 2.0),
+                                    None => "waiting for the first pong to estimate clock skew".to_string(),
                                 }}
                             </p>
                         </div>
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">"Server Metrics"</h3>
+                            <Show when=move || !stream_paused("system") fallback=paused_note>
+                                <p style="margin:0;font-size:1.5rem;font-weight:bold;">
+                                    {move || format!("{} msg/s", server_msg_rate.get())}
+                                </p>
+                                <p style="margin:0.5rem 0 0 0;font-size:0.9rem;">
+                                    {move || format!("CPU {:.1}% · Memory {} MB", server_cpu.get(), server_mem_mb.get())}
+                                </p>
+                            </Show>
+                        </div>
                     </div>
                 </section>
 
                 // Control panel
-                <section style="margin: 2rem 0;padding:1rem;background:#f5f5f5;border-radius:4px;">
+                <section style=move || format!("margin: 2rem 0;padding:1rem;background:{};border-radius:4px;", theme.read().panel)>
                     <h2 style="margin:0 0 1rem 0;">"🎛️ Control Panel"</h2>
                     <div style="display:flex;gap:1rem;flex-wrap:wrap;align-items:center;">
                         <div>
-                            <label for="freq" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Update Frequency (ms)"</label>
-                            <input id="freq" type="number" value=50 min=10 max=1000 step=10
-                                style="padding:0.5rem;border:1px solid #ccc;border-radius:4px;"
+                            <label for="freq" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">
+                                {move || format!("All Streams ({} ms)", price_ms.get())}
+                                {applied_badge("frequency_ms")}
+                            </label>
+                            <input id="freq" type="range" min=10 max=1000 step=10
+                                prop:value=move || price_ms.get().to_string()
                                 on:change=move |ev| {
-                                    #[cfg(feature = "hydrate")]
-                                    {
-                                        if let Some(win) = web_sys::window() {
+                                    if let Ok(val) = event_target_value(&ev).parse::<u64>() {
+                                        let val = val.clamp(10, 5000);
+                                        for interval in [price_ms, book_ms, trade_ms] {
+                                            interval.set(val);
+                                        }
+                                        #[cfg(feature = "hydrate")]
+                                        send_tracked(serde_json::json!({ "frequency_ms": val }));
+                                    }
+                                } />
+                        </div>
+                        {[("price_ms", "Price Interval (ms)", price_ms), ("book_ms", "Book Interval (ms)", book_ms), ("trade_ms", "Trade Interval (ms)", trade_ms)]
+                            .into_iter()
+                            .map(|(field, label, interval)| view! {
+                                <div>
+                                    <label for=field style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">{label}{applied_badge(field)}</label>
+                                    <input id=field type="number" min=10 max=5000 step=10
+                                        prop:value=move || interval.get().to_string()
+                                        style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;width:6rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                        on:change=move |ev| {
                                             if let Ok(val) = event_target_value(&ev).parse::<u64>() {
-                                                if let Ok(js_ws) = js_sys::Reflect::get(win.as_ref(), &js_sys::JsString::from("__leptos_ws")) {
-                                                    if !js_ws.is_undefined() {
-                                                        if let Ok(ws) = js_ws.dyn_into::<web_sys::WebSocket>() {
-                                                            let _ = ws.send_with_str(&format!("{{\"frequency_ms\":{}}}", val));
-                                                        }
-                                                    }
-                                                }
+                                                let val = val.clamp(10, 5000);
+                                                interval.set(val);
+                                                #[cfg(feature = "hydrate")]
+                                                send_tracked(serde_json::json!({ field: val }));
                                             }
+                                        } />
+                                </div>
+                            })
+                            .collect::<Vec<_>>()}
+                        <div>
+                            <span style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Streams"{applied_badge("enabled_streams")}</span>
+                            {STREAMS.iter().map(|&stream| view! {
+                                <label style="margin-right:0.5rem;font-size:0.9rem;">
+                                    <input type="checkbox"
+                                        prop:checked=move || !stream_paused(stream)
+                                        on:change=move |ev| {
+                                            let on = event_target_checked(&ev);
+                                            // Keep the STREAMS order; the server echoes the set to all clients
+                                            let enabled: Vec<String> = STREAMS
+                                                .iter()
+                                                .filter(|s| if **s == stream { on } else { !stream_paused(s) })
+                                                .map(|s| s.to_string())
+                                                .collect();
+                                            #[cfg(feature = "hydrate")]
+                                            send_tracked(serde_json::json!({ "enabled_streams": enabled }));
+                                            enabled_streams.set(enabled);
+                                        } />
+                                    {format!(" {}", stream)}
+                                </label>
+                            }).collect::<Vec<_>>()}
+                        </div>
+                        <div>
+                            <label for="candle" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Candle Interval (ms)"{applied_badge("candle_ms")}</label>
+                            <input id="candle" type="number" value=1000 min=100 max=60000 step=100
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    #[cfg(feature = "hydrate")]
+                                    {
+                                        if let Ok(val) = event_target_value(&ev).parse::<u64>() {
+                                            send_tracked(serde_json::json!({ "candle_ms": val }));
                                         }
                                     }
                                 } />
                         </div>
+                        <div>
+                            <label for="wire_format" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Wire Format"</label>
+                            <select id="wire_format"
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    // Reconnects; parse times restart for the new format
+                                    parse_times.write().clear();
+                                    msgpack.set(event_target_value(&ev) == "msgpack");
+                                }>
+                                <option value="json" selected>"JSON"</option>
+                                <option value="msgpack">"MessagePack"</option>
+                            </select>
+                        </div>
+                        <div>
+                            <label for="batch" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Batch Window (ms, 0 = off)"{applied_badge("batch_ms")}</label>
+                            <input id="batch" type="number" value=0 min=0 max=1000 step=10
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    if let Ok(val) = event_target_value(&ev).parse::<u64>() {
+                                        batch_ms.set(val);
+                                        #[cfg(feature = "hydrate")]
+                                        send_tracked(serde_json::json!({ "batch_ms": val }));
+                                    }
+                                } />
+                        </div>
                         <div>
                             <label for="sample" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Sample Window"</label>
                             <select id="sample" 
-                                style="padding:0.5rem;border:1px solid #ccc;border-radius:4px;"
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
                                 on:change=move |ev| {
-                                    if let Ok(val) = event_target_value(&ev).parse::<usize>() { *sample_max.write() = val; }
+                                    if let Ok(val) = event_target_value(&ev).parse::<usize>() {
+                                        *sample_max.write() = val;
+                                        trim_buffers(val);
+                                    }
                                 }>
                                 <option value="200" selected>"200"</option>
                                 <option value="500">"500"</option>
                                 <option value="1000">"1000"</option>
                             </select>
                         </div>
-                        <button 
-                            style="padding:0.5rem 1rem;background:#ff6666;color:white;border:none;border-radius:4px;cursor:pointer;font-weight:bold;"
+                        <div>
+                            <label for="agg_window" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Price Aggregation"</label>
+                            <select id="agg_window"
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| agg_window.set(AggWindow::from_name(&event_target_value(&ev)))>
+                                {[(AggWindow::Raw, "Raw ticks"), (AggWindow::Ms100, "100 ms"), (AggWindow::S1, "1 s")]
+                                    .into_iter()
+                                    .map(|(window, label)| view! {
+                                        <option value=window.name() selected=move || agg_window.get() == window>{label}</option>
+                                    })
+                                    .collect::<Vec<_>>()}
+                            </select>
+                            <select id="agg_fn" aria-label="Aggregation function"
+                                disabled=move || agg_window.get() == AggWindow::Raw
+                                style=move || format!("padding:0.5rem;margin-left:0.25rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| agg_fn.set(AggFn::from_name(&event_target_value(&ev)))>
+                                <option value=AggFn::Last.name() selected=move || agg_fn.get() == AggFn::Last>"Last"</option>
+                                <option value=AggFn::Mean.name() selected=move || agg_fn.get() == AggFn::Mean>"Mean"</option>
+                            </select>
+                        </div>
+                        <div>
+                            <label for="trade_cap" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Trades Kept"</label>
+                            <input type="number" id="trade_cap" min="20" max="5000"
+                                prop:value=move || trade_cap.get().to_string()
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;width:6rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    if let Ok(val) = event_target_value(&ev).parse::<usize>() {
+                                        trade_cap.set(val.clamp(20, 5000));
+                                    }
+                                } />
+                        </div>
+                        {[("sma_fast", "Fast SMA", sma_fast), ("sma_slow", "Slow SMA", sma_slow)]
+                            .into_iter()
+                            .map(|(id, label, window)| view! {
+                                <div>
+                                    <label for=id style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">{label}</label>
+                                    <input type="number" id=id min="2" max="1000"
+                                        prop:value=move || window.get().to_string()
+                                        style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;width:6rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                        on:change=move |ev| {
+                                            if let Ok(val) = event_target_value(&ev).parse::<usize>() {
+                                                window.set(val.clamp(2, 1000));
+                                                // The old trend says nothing about the new windows
+                                                sma_trend.write().clear();
+                                            }
+                                        } />
+                                </div>
+                            })
+                            .collect::<Vec<_>>()}
+                        <div>
+                            <label for="symbols" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Symbols (none = all)"{applied_badge("subscribe")}</label>
+                            <select id="symbols" multiple size=3
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    #[cfg(feature = "hydrate")]
+                                    {
+                                        let select = event_target::<web_sys::HtmlSelectElement>(&ev);
+                                        let options = select.selected_options();
+                                        let chosen: Vec<String> = (0..options.length())
+                                            .filter_map(|i| options.item(i))
+                                            .filter_map(|el| el.dyn_into::<web_sys::HtmlOptionElement>().ok())
+                                            .map(|opt| opt.value())
+                                            .collect();
+                                        send_tracked(serde_json::json!({ "subscribe": chosen }));
+                                        subscribed.set(chosen);
+                                    }
+                                }>
+                                {move || available_symbols.get().into_iter().map(|s| view! { <option value=s.clone()>{s.clone()}</option> }).collect::<Vec<_>>()}
+                            </select>
+                        </div>
+                        <div>
+                            <label for="alert-symbol" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Price Alert: symbol / direction / price"</label>
+                            <select id="alert-symbol"
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| alert_symbol.set(event_target_value(&ev))>
+                                {move || available_symbols.get().into_iter().map(|s| view! { <option value=s.clone()>{s.clone()}</option> }).collect::<Vec<_>>()}
+                            </select>
+                            <select
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| alert_above.set(event_target_value(&ev) == "above")>
+                                <option value="above" selected>"above"</option>
+                                <option value="below">"below"</option>
+                            </select>
+                            <input type="number" min=0 step=0.01 placeholder="price" title="Notify when the price crosses this level"
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;width:7rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    if let Ok(val) = event_target_value(&ev).parse::<f64>() {
+                                        if val.is_finite() && val > 0.0 {
+                                            #[cfg(feature = "hydrate")]
+                                            request_notification_permission();
+                                            alerts.write().insert(alert_symbol.get_untracked(), Alert::new(val, alert_above.get_untracked()));
+                                        }
+                                    }
+                                } />
+                            <div style="margin-top:0.25rem;font-size:0.8rem;">
+                                {move || {
+                                    let mut active: Vec<_> = alerts.read().iter().map(|(s, a)| (s.clone(), a.describe(&FormatSpec::for_symbol(s)))).collect();
+                                    active.sort();
+                                    active.into_iter().map(|(symbol, text)| {
+                                        let remove = symbol.clone();
+                                        view! {
+                                            <span style="margin-right:0.5rem;">
+                                                {format!("🔔 {} {} ", symbol, text)}
+                                                <button style=move || format!("border:none;background:none;cursor:pointer;color:{};", theme.read().down)
+                                                    title="Remove alert"
+                                                    on:click=move |_| { alerts.write().remove(&remove); }>
+                                                    "✕"
+                                                </button>
+                                            </span>
+                                        }
+                                    }).collect::<Vec<_>>()
+                                }}
+                            </div>
+                        </div>
+                        <div>
+                            <label for="param-symbol" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Simulation: symbol / volatility / price"{applied_badge("set_volatility")}{applied_badge("set_base_price")}</label>
+                            <select id="param-symbol"
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| param_symbol.set(event_target_value(&ev))>
+                                {move || available_symbols.get().into_iter().map(|s| view! { <option value=s.clone()>{s.clone()}</option> }).collect::<Vec<_>>()}
+                            </select>
+                            <input type="number" value=0.002 min=0 max=0.5 step=0.001 title="Max relative change per tick"
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;width:6rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    #[cfg(feature = "hydrate")]
+                                    {
+                                        if let Ok(val) = event_target_value(&ev).parse::<f64>() {
+                                            send_tracked(serde_json::json!({ "set_volatility": (param_symbol.get_untracked(), val) }));
+                                        }
+                                    }
+                                } />
+                            <input type="number" min=0 step=0.01 placeholder="price" title="Continue the walk from this price"
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;width:7rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    #[cfg(feature = "hydrate")]
+                                    {
+                                        if let Ok(val) = event_target_value(&ev).parse::<f64>() {
+                                            send_tracked(serde_json::json!({ "set_base_price": (param_symbol.get_untracked(), val) }));
+                                        }
+                                    }
+                                } />
+                        </div>
+                        <div>
+                            <label for="burst" style="display:block;margin-bottom:0.25rem;font-size:0.9rem;">"Burst: ticks / over (ms)"{applied_badge("burst")}</label>
+                            <input id="burst" type="number" min=1 max=100000 step=100
+                                prop:value=move || burst_messages.get().to_string()
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;width:6rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    if let Ok(val) = event_target_value(&ev).parse::<u32>() {
+                                        burst_messages.set(val.clamp(1, 100_000));
+                                    }
+                                } />
+                            <input type="number" min=0 max=60000 step=100 title="0 = all at once"
+                                prop:value=move || burst_over_ms.get().to_string()
+                                style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;width:6rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                on:change=move |ev| {
+                                    if let Ok(val) = event_target_value(&ev).parse::<u32>() {
+                                        burst_over_ms.set(val.min(60_000));
+                                    }
+                                } />
+                            <button
+                                style=move || format!("margin-left:0.25rem;padding:0.5rem 1rem;background:{};color:white;border:none;border-radius:4px;cursor:pointer;font-weight:bold;", theme.read().accent)
+                                title="Extra price ticks for all clients on top of the regular streams"
+                                on:click=move |_| {
+                                    burst_result.set(None);
+                                    #[cfg(feature = "hydrate")]
+                                    send_tracked(serde_json::json!({
+                                        "burst": { "messages": burst_messages.get_untracked(), "over_ms": burst_over_ms.get_untracked() }
+                                    }));
+                                }>
+                                "Burst"
+                            </button>
+                            <div style=move || format!("margin-top:0.25rem;font-size:0.8rem;color:{};", theme.read().muted)>
+                                {move || burst_result.get().map(|(sent, requested)| format!("last burst: {} of {} ticks sent", sent, requested))}
+                            </div>
+                        </div>
+                        <button
+                            style=move || format!("padding:0.5rem 1rem;background:{};color:white;border:none;border-radius:4px;cursor:pointer;font-weight:bold;", theme.read().button_secondary)
+                            title="Shortcut: Space or p"
+                            on:click=move |_| toggle_pause()>
+                            {move || if paused.get() { "▶ Resume Charts" } else { "⏸ Pause Charts" }}
+                        </button>
+                        <button
+                            style=move || format!("padding:0.5rem 1rem;background:{};color:white;border:none;border-radius:4px;cursor:pointer;font-weight:bold;", theme.read().accent)
+                            on:click=move |_| {
+                                #[cfg(feature = "hydrate")]
+                                {
+                                    let csv = export_csv(&prices.read(), &trades.read(), &latency_values.read());
+                                    download_file("leptos-capture.csv", "text/csv", &csv);
+                                }
+                            }>
+                            "Export CSV"
+                        </button>
+                        <button
+                            style=move || format!("padding:0.5rem 1rem;background:{};color:white;border:none;border-radius:4px;cursor:pointer;font-weight:bold;", theme.read().accent)
+                            title="Received messages as NDJSON, playable with REPLAY_FILE"
                             on:click=move |_| {
-                                prices.write().clear();
-                                trades.write().clear();
-                                book_depth.write().clear();
-                                msg_rate.write().clear();
-                                fps_values.write().clear();
-                                latency_values.write().clear();
-                                *msg_count.write() = 0;
-                                *msg_rate_timer.write() = 0.0;  // ← TIMER auch resetten!
+                                #[cfg(feature = "hydrate")]
+                                {
+                                    let ndjson = capture_ndjson(capture_secs.get_untracked());
+                                    download_file("leptos-capture.ndjson", "application/x-ndjson", &ndjson);
+                                }
                             }>
+                            {move || format!("Dump last {}s", capture_secs.get())}
+                        </button>
+                        <button 
+                            style=move || format!("padding:0.5rem 1rem;background:{};color:white;border:none;border-radius:4px;cursor:pointer;font-weight:bold;", theme.read().down)
+                            title="Shortcut: r"
+                            on:click=move |_| reset_all()>
                             "Reset All Metrics"
                         </button>
                     </div>
-                    <p style="margin:1rem 0 0 0;font-size:0.85rem;color:#666;">
+                    <p style=move || format!("margin:1rem 0 0 0;font-size:0.85rem;color:{};", theme.read().subtle)>
                         "Lower frequency = higher message rate. Adjust to stress test frontend rendering performance."
                     </p>
                 </section>
@@ -490,11 +3532,113 @@ pub fn App() -> impl IntoView {
 /// Renders the home page of your application.
 #[component]
 fn HomePage() -> impl IntoView {
+    let theme = expect_context::<RwSignal<Theme>>();
     view! {
         <div style="text-align:center;padding:2rem;">
-            <p style="font-size:1.1rem;color:#666;">
+            <p style=move || format!("font-size:1.1rem;color:{};", theme.read().subtle)>
                 "Monitoring live data streams. Charts will populate as messages arrive."
             </p>
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anomaly_flags_an_outlier() {
+        let history: Vec<f64> = (0..20).map(|i| 100.0 + if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        // Mean 100, stddev 1
+        assert!(detect_anomaly(&history, 105.0, 4.0));
+        assert!(detect_anomaly(&history, 95.0, 4.0));
+        assert!(!detect_anomaly(&history, 103.0, 4.0));
+    }
+
+    #[test]
+    fn anomaly_needs_history() {
+        assert!(!detect_anomaly(&[], 1e9, 4.0));
+        let short = [100.0, 101.0, 99.0, 100.0, 101.0, 99.0, 100.0, 101.0, 99.0];
+        assert!(!detect_anomaly(&short, 1e9, 4.0));
+    }
+
+    #[test]
+    fn anomaly_ignores_constant_history() {
+        let flat = [100.0; 20];
+        assert!(!detect_anomaly(&flat, 100.0, 4.0));
+        assert!(!detect_anomaly(&flat, 1e9, 4.0));
+    }
+
+    fn trade(size: f64, side: &str) -> (String, f64, f64, String) {
+        ("BTC/USD".to_string(), 100.0, size, side.to_string())
+    }
+
+    #[test]
+    fn imbalance_of_buys_and_sells() {
+        assert_eq!(volume_imbalance(&[trade(3.0, "buy"), trade(1.0, "sell")]), 0.5);
+        assert_eq!(volume_imbalance(&[trade(2.0, "buy")]), 1.0);
+        assert_eq!(volume_imbalance(&[trade(2.0, "sell"), trade(2.0, "sell")]), -1.0);
+        // Unknown sides carry no direction
+        assert_eq!(volume_imbalance(&[trade(1.0, "buy"), trade(1.0, "sell"), trade(5.0, "?")]), 0.0);
+    }
+
+    #[test]
+    fn imbalance_without_volume_is_zero() {
+        assert_eq!(volume_imbalance(&[]), 0.0);
+        assert_eq!(volume_imbalance(&[trade(0.0, "buy"), trade(0.0, "sell")]), 0.0);
+        assert_eq!(volume_imbalance(&[trade(4.0, "?")]), 0.0);
+    }
+
+    #[test]
+    fn stats_skip_non_finite_values() {
+        let s = stats(&[4.0, f64::NAN, 1.0, f64::INFINITY, 3.0, f64::NEG_INFINITY, 2.0]);
+        assert_eq!(s, stats(&[1.0, 2.0, 3.0, 4.0]));
+        assert_eq!((s.min, s.max, s.mean, s.p50), (1.0, 4.0, 2.5, 2.5));
+        assert!((s.p99 - 3.97).abs() < 1e-9, "{}", s.p99);
+    }
+
+    #[test]
+    fn stats_without_finite_values_are_zero() {
+        assert_eq!(stats(&[]), Stats::default());
+        assert_eq!(stats(&[f64::NAN, f64::INFINITY]), Stats::default());
+    }
+
+    #[test]
+    fn crossovers_skip_non_finite_windows() {
+        // Without the guard the NaN faked a sell at 4 and a buy at 7
+        let data = [1.0, 2.0, 3.0, 4.0, f64::NAN, 6.0, 7.0, 8.0, 1.0];
+        assert_eq!(crossover_points(&data, 1, 3), [(8, false)]);
+        let data = [1.0, 2.0, 3.0, 4.0, f64::INFINITY, 6.0, 7.0, 8.0, 1.0];
+        assert_eq!(crossover_points(&data, 1, 3), [(8, false)]);
+        assert_eq!(sma(&[1.0, f64::NAN, 3.0], 2), None);
+        assert_eq!(sma(&[f64::NAN, 2.0, 4.0], 2), Some(3.0));
+    }
+
+    #[test]
+    fn correlation_skips_non_finite_and_zero_prices() {
+        // The remaining steps have the same returns in both series
+        let a = [1.0, 2.0, f64::NAN, 4.0, 0.0, 6.0, 3.0, 6.0];
+        let b = [2.0, 4.0, 5.0, 8.0, 9.0, 12.0, 6.0, 12.0];
+        let r = rolling_correlation(&a, &b).unwrap();
+        assert!((r - 1.0).abs() < 1e-9, "{}", r);
+        assert_eq!(rolling_correlation(&[f64::NAN, 0.0, f64::NAN, 1.0], &b), None);
+    }
+
+    #[test]
+    fn session_summary_over_the_buffer() {
+        let summary = session_summary(&[100.0, 120.0, f64::NAN, 90.0, 110.0]).unwrap();
+        assert_eq!(summary, SessionSummary { last: 110.0, change_pct: Some(10.0), high: 120.0, low: 90.0 });
+        // A single price is its own high and low
+        let summary = session_summary(&[f64::INFINITY, 42.0]).unwrap();
+        assert_eq!(summary, SessionSummary { last: 42.0, change_pct: Some(0.0), high: 42.0, low: 42.0 });
+    }
+
+    #[test]
+    fn session_summary_without_a_change() {
+        // No percentage against an oldest price of 0
+        let summary = session_summary(&[0.0, 5.0]).unwrap();
+        assert_eq!((summary.change_pct, summary.last, summary.high), (None, 5.0, 5.0));
+        assert_eq!(session_summary(&[]), None);
+        assert_eq!(session_summary(&[f64::NAN, f64::NAN]), None);
+    }
+}
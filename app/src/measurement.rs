@@ -0,0 +1,749 @@
+use leptos::prelude::*;
+use leptos_rust::multi_user_types::{
+    ChannelConfig, DataQuality, DataRetention, MeasurementConfig, MeasurementId, MeasurementType, MultiUserMessage,
+    ResourceUsage, SystemStats, TriggerConfig, TriggerEvent, TriggerType,
+};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "hydrate")]
+use leptos_rust::error_code::ErrorCode;
+#[cfg(feature = "hydrate")]
+use leptos_rust::multi_user_types::{MeasurementData, MeasurementStatus, SystemEvent};
+#[cfg(feature = "hydrate")]
+use leptos_rust::sample_gaps::detect_gaps;
+#[cfg(feature = "hydrate")]
+use wasm_bindgen::JsCast;
+
+use crate::{sparkline_label, sparkline_points, sparkline_x, SparkStyle, Theme};
+
+/// Samples kept per channel for the sparklines.
+#[cfg(feature = "hydrate")]
+const MEASUREMENT_SAMPLES: usize = 500;
+
+/// Trigger events kept per measurement for the log.
+#[cfg(feature = "hydrate")]
+const TRIGGER_LOG_LEN: usize = 20;
+
+/// Window property holding the `/mu` socket, like `__leptos_ws` for `/ws`.
+#[cfg(feature = "hydrate")]
+const MU_SOCKET_KEY: &str = "__leptos_mu_ws";
+
+/// Recent values of one measurement channel.
+#[derive(Clone, Debug, Default)]
+struct ChannelTrace {
+    name: String,
+    unit: String,
+    values: Vec<f64>,
+    qualities: Vec<DataQuality>, // quality of each value in `values`
+}
+
+/// Client-side state of one measurement started on this connection.
+#[derive(Clone, Debug)]
+struct MeasurementView {
+    name: String,
+    status: String,
+    config: MeasurementConfig, // as started, or as last updated by the server
+    channels: BTreeMap<u8, ChannelTrace>,
+    gaps: Vec<usize>, // positions in the channel traces where a gap was detected
+    overruns: u32,    // synthesized `DataOverrun` events
+    jumps: u32,       // synthesized `TimestampJump` events
+    trigger_marks: Vec<(u8, usize)>, // (channel, position in its trace) of each trigger event
+    trigger_log: Vec<TriggerEvent>, // most recent last, at most `TRIGGER_LOG_LEN`
+    samples: u64,  // received since the start
+    revision: u64, // bumped on every change, so only the shown tab re-renders
+}
+
+/// What gap detection needs to know about a measurement's stream.
+#[cfg(feature = "hydrate")]
+struct GapState {
+    expected_dt_ns: u64,
+    last: Option<MeasurementData>,
+}
+
+#[cfg(feature = "hydrate")]
+thread_local! {
+    /// Per measurement; kept out of the signal since the view never reads it
+    static GAP_STATE: std::cell::RefCell<std::collections::HashMap<MeasurementId, GapState>> =
+        std::cell::RefCell::new(std::collections::HashMap::new());
+}
+
+/// Color of a value by its quality: the up color for good data, the warning
+/// color for questionable data and the down color for anything unusable.
+fn quality_color(q: &DataQuality, theme: &Theme) -> &'static str {
+    match q {
+        DataQuality::Good => theme.up,
+        DataQuality::Questionable => theme.warn,
+        DataQuality::Bad | DataQuality::Overflow | DataQuality::Underflow => theme.down,
+    }
+}
+
+/// Name of a trigger type for the log and the trigger select.
+fn trigger_label(t: &TriggerType) -> &'static str {
+    match t {
+        TriggerType::Rising => "Rising",
+        TriggerType::Falling => "Falling",
+        TriggerType::Both => "Both",
+        TriggerType::Level => "Level",
+        TriggerType::Window { .. } => "Window",
+    }
+}
+
+/// `HH:MM:SS.mmm` (UTC) of a timestamp in nanos since the epoch.
+fn clock_utc_ms(ts_ns: u64) -> String {
+    let ms = ts_ns / 1_000_000;
+    let secs = ms / 1000 % 86_400;
+    format!("{:02}:{:02}:{:02}.{:03}", secs / 3600, secs / 60 % 60, secs % 60, ms % 1000)
+}
+
+/// Marker next to a value outside the channel's range.
+fn quality_icon(q: &DataQuality) -> Option<(&'static str, &'static str)> {
+    match q {
+        DataQuality::Overflow => Some(("▲", "Overflow: above the channel range")),
+        DataQuality::Underflow => Some(("▼", "Underflow: below the channel range")),
+        _ => None,
+    }
+}
+
+impl MeasurementView {
+    /// Samples in the window where at least one channel wasn't good.
+    fn degraded_samples(&self) -> usize {
+        let len = self.channels.values().map(|c| c.qualities.len()).max().unwrap_or(0);
+        (0..len)
+            .filter(|&i| {
+                self.channels
+                    .values()
+                    .any(|c| c.qualities.get(i).is_some_and(|q| !matches!(q, DataQuality::Good)))
+            })
+            .count()
+    }
+}
+
+/// Measurement id → its traces, in a stable order for rendering.
+type MeasurementViews = BTreeMap<MeasurementId, MeasurementView>;
+
+/// Signals the `/mu` socket callbacks write into.
+#[derive(Clone, Copy)]
+struct MuCtx {
+    user: RwSignal<Option<String>>, // logged in user, `None` without a session
+    error: RwSignal<Option<String>>, // last error reported by the server
+    measurements: RwSignal<MeasurementViews>,
+    active: RwSignal<Option<MeasurementId>>, // selected tab; the first one without
+    // Config of the `StartMeasurement` in flight; names the channels of the
+    // measurement the next `Starting` status announces
+    pending: RwSignal<Option<MeasurementConfig>>,
+    system: RwSignal<Option<SystemStats>>, // last server-wide stats
+    usage: RwSignal<Option<ResourceUsage>>, // this session's share of them
+}
+
+/// Measurement types a user may start with the default permissions.
+const MEASUREMENT_TYPES: [(&str, MeasurementType); 5] = [
+    ("Voltage", MeasurementType::Voltage),
+    ("Current", MeasurementType::Current),
+    ("Temperature", MeasurementType::Temperature),
+    ("Pressure", MeasurementType::Pressure),
+    ("Acceleration", MeasurementType::Acceleration),
+];
+
+/// Edge triggers offered when starting a measurement.
+const TRIGGER_TYPES: [TriggerType; 3] = [TriggerType::Rising, TriggerType::Falling, TriggerType::Both];
+
+/// How much of a measurement the server records, if it records at all
+/// (`MEASUREMENT_DIR`).
+const RETENTIONS: [(&str, DataRetention); 4] = [
+    ("Don't record", DataRetention::Discard),
+    ("Record all", DataRetention::KeepAll),
+    ("Record last 60 s", DataRetention::Duration(60)),
+    ("Record last 100k samples", DataRetention::MaxSamples(100_000)),
+];
+
+/// Continuous measurement with `channels` channels in a range typical for
+/// the measured quantity. `trigger` watches one channel for an edge at the
+/// threshold, or at the middle of the range without one; the hysteresis is
+/// 2% of the range.
+fn measurement_config(
+    measurement_type: MeasurementType,
+    sample_rate_hz: u32,
+    channels: u8,
+    trigger: Option<(TriggerType, u8, Option<f64>)>,
+    data_retention: DataRetention,
+) -> MeasurementConfig {
+    let (unit, range_min, range_max) = match &measurement_type {
+        MeasurementType::Voltage => ("V", -10.0, 10.0),
+        MeasurementType::Current => ("A", 0.0, 5.0),
+        MeasurementType::Temperature => ("°C", -20.0, 120.0),
+        MeasurementType::Pressure => ("bar", 0.0, 10.0),
+        MeasurementType::Acceleration => ("g", -2.0, 2.0),
+        MeasurementType::Custom { .. } => ("", 0.0, 1.0),
+    };
+    MeasurementConfig {
+        measurement_type,
+        sample_rate_hz,
+        duration_seconds: None,
+        channels: (0..channels)
+            .map(|channel_id| ChannelConfig {
+                channel_id,
+                name: format!("CH{}", channel_id),
+                unit: unit.to_string(),
+                range_min,
+                range_max,
+                enabled: true,
+            })
+            .collect(),
+        trigger_config: trigger.map(|(trigger_type, channel_id, threshold)| TriggerConfig {
+            trigger_type,
+            channel_id: channel_id.min(channels.saturating_sub(1)),
+            threshold: threshold.unwrap_or((range_min + range_max) / 2.0),
+            hysteresis: (range_max - range_min) * 0.02,
+            pre_trigger_samples: 0,
+            post_trigger_samples: 0,
+        }),
+        auto_scale: false,
+        data_retention,
+    }
+}
+
+/// The open `/mu` socket, if any.
+#[cfg(feature = "hydrate")]
+fn mu_socket() -> Option<web_sys::WebSocket> {
+    let win = web_sys::window()?;
+    let ws = js_sys::Reflect::get(win.as_ref(), &js_sys::JsString::from(MU_SOCKET_KEY)).ok()?;
+    ws.dyn_into::<web_sys::WebSocket>()
+        .ok()
+        .filter(|ws| ws.ready_state() == web_sys::WebSocket::OPEN)
+}
+
+/// Sends `msg` on the open `/mu` socket; dropped without one.
+fn send_mu(msg: &MultiUserMessage) {
+    #[cfg(feature = "hydrate")]
+    if let (Some(ws), Ok(json)) = (mu_socket(), serde_json::to_string(msg)) {
+        let _ = ws.send_with_str(&json);
+    }
+    #[cfg(not(feature = "hydrate"))]
+    let _ = msg;
+}
+
+/// Sends `login` on the open socket, or opens one and logs in once it's up.
+/// Sessions end with their connection, so a dropped socket just logs out.
+#[cfg(feature = "hydrate")]
+fn connect_mu(ctx: MuCtx, login: MultiUserMessage) {
+    use wasm_bindgen::closure::Closure;
+    use web_sys::{MessageEvent, WebSocket};
+
+    if mu_socket().is_some() {
+        send_mu(&login);
+        return;
+    }
+    let window = web_sys::window().expect("window");
+    let location = window.location();
+    let host = location.host().unwrap_or_else(|_| "127.0.0.1:3000".into());
+    let protocol = location
+        .protocol()
+        .ok()
+        .filter(|p| p.starts_with("https"))
+        .map(|_| "wss")
+        .unwrap_or("ws");
+    let Ok(ws) = WebSocket::new(&format!("{}://{}/mu", protocol, host)) else {
+        ctx.error.set(Some("could not open /mu".into()));
+        return;
+    };
+    let _ = js_sys::Reflect::set(window.as_ref(), &js_sys::JsString::from(MU_SOCKET_KEY), ws.as_ref());
+
+    let onopen = Closure::wrap(Box::new(move |_: web_sys::Event| send_mu(&login)) as Box<dyn FnMut(_)>);
+    ws.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+    onopen.forget();
+
+    let onclose = Closure::wrap(Box::new(move |_: web_sys::Event| {
+        ctx.user.set(None);
+        for view in ctx.measurements.write().values_mut() {
+            view.status = "Disconnected".into();
+            view.revision += 1;
+        }
+    }) as Box<dyn FnMut(_)>);
+    ws.set_onclose(Some(onclose.as_ref().unchecked_ref()));
+    onclose.forget();
+
+    let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
+        let Some(txt) = e.data().as_string() else { return };
+        match serde_json::from_str::<MultiUserMessage>(&txt) {
+            Ok(msg) => apply_mu_message(ctx, msg),
+            Err(err) => leptos::logging::warn!("unparseable /mu message: {}", err),
+        }
+    }) as Box<dyn FnMut(_)>);
+    ws.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+}
+
+#[cfg(feature = "hydrate")]
+fn apply_mu_message(ctx: MuCtx, msg: MultiUserMessage) {
+    match msg {
+        MultiUserMessage::SessionStarted { session } => {
+            ctx.user.set(Some(session.username));
+            ctx.error.set(None);
+        }
+        MultiUserMessage::UserLogout => ctx.user.set(None),
+        MultiUserMessage::MeasurementStatus { measurement_id, status } => {
+            let label = match &status {
+                MeasurementStatus::Error { message } => format!("Error: {}", message),
+                other => format!("{:?}", other),
+            };
+            let config = if matches!(status, MeasurementStatus::Starting) {
+                ctx.pending.try_update(Option::take).flatten()
+            } else {
+                None
+            };
+            ctx.measurements.update(|map| {
+                if let Some(config) = config {
+                    let channels = config
+                        .channels
+                        .iter()
+                        .map(|c| (c.channel_id, ChannelTrace { name: c.name.clone(), unit: c.unit.clone(), ..Default::default() }))
+                        .collect();
+                    let name = match &config.measurement_type {
+                        MeasurementType::Custom { name, .. } => name.clone(),
+                        other => format!("{:?}", other),
+                    };
+                    let expected_dt_ns = 1_000_000_000 / config.sample_rate_hz.max(1) as u64;
+                    GAP_STATE.with_borrow_mut(|state| state.insert(measurement_id, GapState { expected_dt_ns, last: None }));
+                    map.insert(
+                        measurement_id,
+                        MeasurementView {
+                            name,
+                            status: label,
+                            config,
+                            channels,
+                            gaps: Vec::new(),
+                            overruns: 0,
+                            jumps: 0,
+                            trigger_marks: Vec::new(),
+                            trigger_log: Vec::new(),
+                            samples: 0,
+                            revision: 0,
+                        },
+                    );
+                    ctx.active.set(Some(measurement_id));
+                } else if let Some(view) = map.get_mut(&measurement_id) {
+                    view.status = label;
+                    view.revision += 1;
+                }
+            });
+        }
+        // The server adopted a new rate or channel set; the traces go on
+        MultiUserMessage::UpdateMeasurementConfig { measurement_id, config } => ctx.measurements.update(|map| {
+            let Some(view) = map.get_mut(&measurement_id) else { return };
+            for c in &config.channels {
+                let trace = view.channels.entry(c.channel_id).or_default();
+                trace.name.clone_from(&c.name);
+                trace.unit.clone_from(&c.unit);
+            }
+            GAP_STATE.with_borrow_mut(|state| {
+                // The step to the first sample at the new rate isn't a gap
+                if let Some(state) = state.get_mut(&measurement_id) {
+                    state.expected_dt_ns = 1_000_000_000 / config.sample_rate_hz.max(1) as u64;
+                    state.last = None;
+                }
+            });
+            view.config = config;
+            view.revision += 1;
+        }),
+        MultiUserMessage::MeasurementData(data) => apply_samples(ctx, std::slice::from_ref(&data)),
+        MultiUserMessage::MeasurementBatch { measurements } => apply_samples(ctx, &measurements),
+        MultiUserMessage::Error { message, error_code } => {
+            // The session is gone, e.g. after a server restart: back to the login form
+            if ErrorCode::from_code(error_code) == Some(ErrorCode::Unauthorized) {
+                ctx.user.set(None);
+                ctx.pending.set(None);
+            }
+            ctx.error.set(Some(format!("{} ({})", message, error_code)));
+        }
+        MultiUserMessage::SystemStats(stats) => ctx.system.set(Some(stats)),
+        MultiUserMessage::UserStats(usage) => ctx.usage.set(Some(usage)),
+        MultiUserMessage::ResourceLimitExceeded { resource, limit } => {
+            ctx.pending.set(None);
+            ctx.error.set(Some(format!("limit exceeded: {} (max {})", resource, limit)));
+        }
+        _ => {}
+    }
+}
+
+/// Appends the channel values of `samples`, touching the signal once per
+/// message however large the batch, so a `MeasurementBatch` re-renders the
+/// dashboard once instead of once per sample. Samples are applied in
+/// `sample_index` order whatever order the batch lists them in. Each sample
+/// is checked against the previous one of its measurement; a gap is counted
+/// and marked at the sample's position in the traces. Trigger events are
+/// logged and marked on their channel at the position of the sample that
+/// carries them, which is the one at the event's `timestamp_ns`.
+#[cfg(feature = "hydrate")]
+fn apply_samples(ctx: MuCtx, samples: &[MeasurementData]) {
+    let mut ordered: Vec<&MeasurementData> = samples.iter().collect();
+    // Stable, and a no-op for the server's batches, which are already in order
+    ordered.sort_by_key(|sample| sample.sample_index);
+    ctx.measurements.update(|map| {
+        GAP_STATE.with_borrow_mut(|gap_state| {
+            for sample in ordered {
+                let Some(view) = map.get_mut(&sample.measurement_id) else { continue };
+                view.samples += 1;
+                view.revision += 1;
+                if let Some(state) = gap_state.get_mut(&sample.measurement_id) {
+                    let events = state
+                        .last
+                        .as_ref()
+                        .map(|prev| detect_gaps(prev, sample, state.expected_dt_ns))
+                        .unwrap_or_default();
+                    if !events.is_empty() {
+                        view.gaps.push(view.channels.values().next().map_or(0, |c| c.values.len()));
+                        for event in &events {
+                            match event {
+                                SystemEvent::DataOverrun => view.overruns += 1,
+                                SystemEvent::TimestampJump => view.jumps += 1,
+                                _ => {}
+                            }
+                        }
+                    }
+                    let mut last = sample.clone();
+                    last.metadata.system_events.extend(events);
+                    state.last = Some(last);
+                }
+                for event in &sample.metadata.trigger_events {
+                    let at = view.channels.get(&event.channel_id).map_or(0, |c| c.values.len());
+                    view.trigger_marks.push((event.channel_id, at));
+                    crate::push_capped(&mut view.trigger_log, event.clone(), TRIGGER_LOG_LEN);
+                }
+                // A full trace drops its oldest value, moving the markers left
+                let full = view.channels.values().next().is_some_and(|c| c.values.len() >= MEASUREMENT_SAMPLES);
+                for channel in &sample.channels {
+                    let trace = view.channels.entry(channel.channel_id).or_default();
+                    crate::push_capped(&mut trace.values, channel.value, MEASUREMENT_SAMPLES);
+                    crate::push_capped(&mut trace.qualities, channel.quality.clone(), MEASUREMENT_SAMPLES);
+                }
+                if full {
+                    view.gaps.retain(|&at| at > 0);
+                    for at in &mut view.gaps {
+                        *at -= 1;
+                    }
+                    view.trigger_marks.retain(|&(_, at)| at > 0);
+                    for (_, at) in &mut view.trigger_marks {
+                        *at -= 1;
+                    }
+                }
+            }
+        });
+    });
+}
+
+/// Logs in on `/mu`, starts simulated measurements and draws one sparkline
+/// per channel from the `MeasurementData` the server forwards. Sample rates
+/// above 50 Hz arrive as `MeasurementBatch`, one per 20 ms. Each measurement
+/// gets a tab; all of them keep buffering, but only the selected one renders.
+#[component]
+pub fn MeasurementDashboard() -> impl IntoView {
+    let theme = expect_context::<RwSignal<Theme>>();
+    let ctx = MuCtx {
+        user: RwSignal::new(None),
+        error: RwSignal::new(None),
+        measurements: RwSignal::new(MeasurementViews::new()),
+        active: RwSignal::new(None),
+        pending: RwSignal::new(None),
+        system: RwSignal::new(None),
+        usage: RwSignal::new(None),
+    };
+    let MuCtx { user, error, measurements, active, system, usage, .. } = ctx;
+    let username = RwSignal::new("demo".to_string());
+    let password = RwSignal::new("demo".to_string());
+    let type_index = RwSignal::new(0usize);
+    let sample_rate = RwSignal::new(100u32);
+    let channel_count = RwSignal::new(2u8);
+    // Index into `TRIGGER_TYPES`, `None` = no trigger
+    let trigger_index = RwSignal::new(None::<usize>);
+    let trigger_channel = RwSignal::new(0u8);
+    let trigger_threshold = RwSignal::new(None::<f64>); // `None` = middle of the range
+    let retention_index = RwSignal::new(0usize); // into `RETENTIONS`
+
+    let login = move |_| {
+        #[cfg(feature = "hydrate")]
+        connect_mu(
+            ctx,
+            MultiUserMessage::UserLogin { username: username.get_untracked(), password: password.get_untracked() },
+        );
+    };
+    let start = move |_| {
+        let config = measurement_config(
+            MEASUREMENT_TYPES[type_index.get_untracked()].1.clone(),
+            sample_rate.get_untracked(),
+            channel_count.get_untracked(),
+            trigger_index
+                .get_untracked()
+                .map(|i| (TRIGGER_TYPES[i].clone(), trigger_channel.get_untracked(), trigger_threshold.get_untracked())),
+            RETENTIONS[retention_index.get_untracked()].1.clone(),
+        );
+        send_mu(&MultiUserMessage::StartMeasurement { config: config.clone() });
+        ctx.pending.set(Some(config));
+    };
+    // The shown tab and its revision: samples for the other tabs leave it
+    // unchanged, so they don't re-render the card
+    let shown = Memo::new(move |_| {
+        let map = measurements.read();
+        let id = active.get().filter(|id| map.contains_key(id)).or_else(|| map.keys().next().copied())?;
+        Some((id, map[&id].revision))
+    });
+    let tabs = Memo::new(move |_| {
+        measurements.read().iter().map(|(&id, m)| (id, m.name.clone(), m.status.clone(), m.samples)).collect::<Vec<_>>()
+    });
+    // Finished measurements can be closed; running ones only by stopping them
+    let close = move |id: MeasurementId| {
+        measurements.update(|map| {
+            map.remove(&id);
+        });
+        #[cfg(feature = "hydrate")]
+        GAP_STATE.with_borrow_mut(|state| state.remove(&id));
+    };
+    let input_style = move || format!(
+        "padding:0.5rem;border:1px solid {};border-radius:4px;width:6rem;background:{};color:{};",
+        theme.read().input_border, theme.read().input_background, theme.read().text
+    );
+    let button_style = move || format!(
+        "padding:0.5rem 1rem;background:{};color:white;border:none;border-radius:4px;cursor:pointer;font-weight:bold;",
+        theme.read().accent
+    );
+
+    view! {
+        <section style="margin: 2rem 0;">
+            <h2>"🔬 Measurements"</h2>
+            <div style="display:flex;gap:0.5rem;flex-wrap:wrap;align-items:center;margin-bottom:1rem;">
+                <Show
+                    when=move || user.read().is_some()
+                    fallback=move || view! {
+                        <input type="text" placeholder="user" style=input_style
+                            prop:value=move || username.get()
+                            on:change=move |ev| username.set(event_target_value(&ev)) />
+                        <input type="password" placeholder="password" style=input_style
+                            prop:value=move || password.get()
+                            on:change=move |ev| password.set(event_target_value(&ev)) />
+                        <button style=button_style on:click=login>"Log in"</button>
+                    }>
+                    <span style="font-size:0.9rem;">{move || format!("👤 {}", user.get().unwrap_or_default())}</span>
+                    <select style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                        on:change=move |ev| {
+                            if let Ok(i) = event_target_value(&ev).parse::<usize>() {
+                                type_index.set(i.min(MEASUREMENT_TYPES.len() - 1));
+                            }
+                        }>
+                        {MEASUREMENT_TYPES.iter().enumerate().map(|(i, (label, _))| view! {
+                            <option value=i.to_string() selected=i == 0>{*label}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    <input type="number" min=1 max=100000 title="Sample rate (Hz)" style=input_style
+                        prop:value=move || sample_rate.get().to_string()
+                        on:change=move |ev| {
+                            if let Ok(hz) = event_target_value(&ev).parse::<u32>() {
+                                sample_rate.set(hz.clamp(1, 100_000));
+                            }
+                        } />
+                    <input type="number" min=1 max=16 title="Channels" style=input_style
+                        prop:value=move || channel_count.get().to_string()
+                        on:change=move |ev| {
+                            if let Ok(n) = event_target_value(&ev).parse::<u8>() {
+                                channel_count.set(n.clamp(1, 16));
+                            }
+                        } />
+                    <select title="Trigger" style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                        on:change=move |ev| trigger_index.set(event_target_value(&ev).parse::<usize>().ok().filter(|&i| i < TRIGGER_TYPES.len()))>
+                        <option value="" selected>"No trigger"</option>
+                        {TRIGGER_TYPES.iter().enumerate().map(|(i, t)| view! {
+                            <option value=i.to_string()>{format!("{} edge", trigger_label(t))}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    <Show when=move || trigger_index.read().is_some()>
+                        <input type="number" min=0 max=15 title="Trigger channel" style=input_style
+                            prop:value=move || trigger_channel.get().to_string()
+                            on:change=move |ev| {
+                                if let Ok(ch) = event_target_value(&ev).parse::<u8>() {
+                                    trigger_channel.set(ch.min(15));
+                                }
+                            } />
+                        <input type="number" step="any" placeholder="mid-range" title="Trigger threshold" style=input_style
+                            prop:value=move || trigger_threshold.get().map(|t| t.to_string()).unwrap_or_default()
+                            on:change=move |ev| trigger_threshold.set(event_target_value(&ev).parse::<f64>().ok().filter(|t| t.is_finite())) />
+                    </Show>
+                    <select title="Recording on the server" style=move || format!("padding:0.5rem;border:1px solid {};border-radius:4px;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                        on:change=move |ev| {
+                            if let Ok(i) = event_target_value(&ev).parse::<usize>() {
+                                retention_index.set(i.min(RETENTIONS.len() - 1));
+                            }
+                        }>
+                        {RETENTIONS.iter().enumerate().map(|(i, (label, _))| view! {
+                            <option value=i.to_string() selected=i == 0>{*label}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    <button style=button_style on:click=start>"Start"</button>
+                </Show>
+                {move || error.get().map(|e| view! { <span style=move || format!("color:{};font-size:0.9rem;", theme.read().down)>{e}</span> })}
+            </div>
+            {move || system.get().filter(|_| user.read().is_some()).map(|s| {
+                let own = usage.get().map(|u| format!(" (yours: {:.1} kbit/s)", u.bandwidth_bps as f64 / 1000.0)).unwrap_or_default();
+                view! {
+                    <p style=move || format!("margin:0 0 1rem 0;font-size:0.8rem;color:{};", theme.read().muted)>
+                        {format!(
+                            "🖥 {}/{} users · {}/{} measurements running · {} samples/s · ~{:.1} kbit/s{}",
+                            s.active_users, s.total_users, s.active_measurements, s.total_measurements,
+                            s.total_samples_per_second, s.network_throughput_bps as f64 / 1000.0, own
+                        )}
+                    </p>
+                }
+            })}
+            <div role="tablist" style=move || format!("display:flex;flex-wrap:wrap;gap:0.25rem;border-bottom:1px solid {};margin-bottom:0.5rem;", theme.read().divider)>
+                {move || tabs.get().into_iter().map(|(id, name, status, samples)| {
+                    let selected = shown.read().is_some_and(|(shown, _)| shown == id);
+                    let closable = !matches!(status.as_str(), "Starting" | "Running" | "Paused");
+                    view! {
+                        <div role="tab" aria-selected=selected.to_string()
+                            style=move || format!(
+                                "padding:0.35rem 0.75rem;cursor:pointer;font-size:0.85rem;border-bottom:2px solid {};{}",
+                                if selected { theme.read().accent } else { "transparent" },
+                                if selected { "font-weight:bold;" } else { "" }
+                            )
+                            on:click=move |_| active.set(Some(id))>
+                            {format!("{} · {} · {} samples", name, status, samples)}
+                            {closable.then(|| view! {
+                                <button style=move || format!("margin-left:0.35rem;border:none;background:none;cursor:pointer;color:{};", theme.read().muted)
+                                    title="Close tab"
+                                    on:click=move |ev| {
+                                        ev.stop_propagation();
+                                        close(id);
+                                    }>
+                                    "×"
+                                </button>
+                            })}
+                        </div>
+                    }
+                }).collect::<Vec<_>>()}
+            </div>
+            <div>
+                {move || shown.get().and_then(|(id, _)| measurements.with_untracked(|map| map.get(&id).cloned()).map(|m| (id, m))).map(|(id, m)| {
+                    let stop = move |_| send_mu(&MultiUserMessage::StopMeasurement { measurement_id: id });
+                    let paused = m.status == "Paused";
+                    let degraded = m.degraded_samples();
+                    let toggle_pause = move |_| send_mu(&if paused {
+                        MultiUserMessage::ResumeMeasurement { measurement_id: id }
+                    } else {
+                        MultiUserMessage::PauseMeasurement { measurement_id: id }
+                    });
+                    view! {
+                        <div style=move || format!("border:1px solid {};padding:0.5rem;border-radius:4px;", theme.read().border)>
+                            <h3 style="margin:0 0 0.5rem 0;font-size:1rem;">
+                                {format!("{} · {}", m.name, m.status)}
+                                {(m.overruns + m.jumps > 0).then(|| view! {
+                                    <span style=move || format!("margin-left:0.5rem;font-size:0.8rem;color:{};", theme.read().down)
+                                        title="Lost samples / timestamp jumps">
+                                        {format!("⚠ {} overrun · {} jump", m.overruns, m.jumps)}
+                                    </span>
+                                })}
+                                {(degraded > 0).then(|| view! {
+                                    <span style=move || format!("margin-left:0.5rem;padding:0 0.35rem;border-radius:8px;font-size:0.75rem;color:white;background:{};", theme.read().warn)
+                                        title="Samples in the window with a questionable, bad or out-of-range channel">
+                                        {format!("{} degraded", degraded)}
+                                    </span>
+                                })}
+                                <button style=move || format!("margin-left:0.5rem;border:none;background:none;cursor:pointer;color:{};", theme.read().accent)
+                                    title=if paused { "Resume measurement" } else { "Pause measurement" }
+                                    on:click=toggle_pause>
+                                    {if paused { "▶" } else { "⏸" }}
+                                </button>
+                                <button style=move || format!("margin-left:0.5rem;border:none;background:none;cursor:pointer;color:{};", theme.read().down)
+                                    title="Stop measurement"
+                                    on:click=stop>
+                                    "■"
+                                </button>
+                            </h3>
+                            <div style="display:flex;gap:0.5rem;align-items:center;margin-bottom:0.5rem;font-size:0.8rem;">
+                                <label>
+                                    "Rate (Hz) "
+                                    <input type="number" min=1 max=100000 title="Adopted on the next tick, without a restart"
+                                        style=move || format!("padding:0.2rem;border:1px solid {};border-radius:4px;width:6rem;background:{};color:{};", theme.read().input_border, theme.read().input_background, theme.read().text)
+                                        prop:value=m.config.sample_rate_hz.to_string()
+                                        on:change={
+                                            let config = m.config.clone();
+                                            move |ev| {
+                                                if let Ok(hz) = event_target_value(&ev).parse::<u32>() {
+                                                    let mut config = config.clone();
+                                                    config.sample_rate_hz = hz.clamp(1, 100_000);
+                                                    send_mu(&MultiUserMessage::UpdateMeasurementConfig { measurement_id: id, config });
+                                                }
+                                            }
+                                        } />
+                                </label>
+                            </div>
+                            {m.channels.iter().map(|(&channel_id, ch)| view! {
+                                <div style="font-size:0.8rem;">
+                                    // Unticking stops the channel; its trace keeps what it has
+                                    <input type="checkbox" title="Emit this channel"
+                                        prop:checked=m.config.channels.iter().any(|c| c.channel_id == channel_id && c.enabled)
+                                        on:change={
+                                            let config = m.config.clone();
+                                            move |ev| {
+                                                let mut config = config.clone();
+                                                if let Some(c) = config.channels.iter_mut().find(|c| c.channel_id == channel_id) {
+                                                    c.enabled = event_target_checked(&ev);
+                                                }
+                                                send_mu(&MultiUserMessage::UpdateMeasurementConfig { measurement_id: id, config });
+                                            }
+                                        } />
+                                    {format!("{} ", ch.name)}
+                                    {
+                                        let quality = ch.qualities.last().cloned().unwrap_or(DataQuality::Good);
+                                        let icon = quality_icon(&quality);
+                                        view! {
+                                            <span style=move || format!("color:{};font-weight:bold;", quality_color(&quality, &theme.read()))>
+                                                {format!("{:.3}", ch.values.last().copied().unwrap_or(0.0))}
+                                                {icon.map(|(icon, title)| view! { <span title=title>{format!(" {}", icon)}</span> })}
+                                            </span>
+                                        }
+                                    }
+                                    {format!(" {}", ch.unit)}
+                                    <svg width="100%" height="40" viewBox="0 0 300 40" role="img"
+                                        aria-label=sparkline_label(&ch.name, &format!("{:.3} {}", ch.values.last().copied().unwrap_or(0.0), ch.unit), &ch.values)>
+                                        <path stroke=move || theme.read().accent fill="none" stroke-width="1.5"
+                                            d=sparkline_points(&ch.values, 300.0, 40.0, SparkStyle::Line) />
+                                        // Gap markers sit at the first sample after the gap
+                                        {m.gaps.iter().filter(|&&at| at < ch.values.len()).map(|&at| {
+                                            let x = sparkline_x(ch.values.len(), at, 300.0);
+                                            view! {
+                                                <line x1=x y1=0 x2=x y2=40 stroke=move || theme.read().down stroke-width="1" stroke-dasharray="2,2" />
+                                            }
+                                        }).collect::<Vec<_>>()}
+                                        // Trigger markers sit at the sample that fired
+                                        {m.trigger_marks.iter().filter(|&&(channel, at)| channel == channel_id && at < ch.values.len()).map(|&(_, at)| {
+                                            let x = sparkline_x(ch.values.len(), at, 300.0);
+                                            view! {
+                                                <line x1=x y1=0 x2=x y2=40 stroke=move || theme.read().warn stroke-width="1.5" />
+                                            }
+                                        }).collect::<Vec<_>>()}
+                                    </svg>
+                                </div>
+                            }).collect::<Vec<_>>()}
+                            {(!m.trigger_log.is_empty()).then(|| view! {
+                                <div style=move || format!("margin-top:0.5rem;max-height:6rem;overflow-y:auto;border-top:1px solid {};font-family:monospace;font-size:0.75rem;", theme.read().divider)>
+                                    {m.trigger_log.iter().rev().map(|event| {
+                                        let channel = m.channels.get(&event.channel_id);
+                                        view! {
+                                            <div>
+                                                {format!(
+                                                    "{} {} {} {:.3} {}",
+                                                    clock_utc_ms(event.timestamp_ns),
+                                                    channel.map_or_else(|| format!("CH{}", event.channel_id), |c| c.name.clone()),
+                                                    trigger_label(&event.trigger_type),
+                                                    event.trigger_value,
+                                                    channel.map_or("", |c| c.unit.as_str()),
+                                                )}
+                                            </div>
+                                        }
+                                    }).collect::<Vec<_>>()}
+                                </div>
+                            })}
+                        </div>
+                    }
+                })}
+            </div>
+        </section>
+    }
+}
@@ -0,0 +1,117 @@
+/// How the prices of one symbol are written: `{prefix}{value}{suffix}` with
+/// `decimals` digits after the point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatSpec {
+    pub decimals: usize,
+    pub prefix: String,
+    pub suffix: String,
+}
+
+/// Symbols whose prices are too small for the decimals of their quote
+/// currency. Only the decimals differ from the derived spec.
+const DECIMAL_OVERRIDES: [(&str, usize); 7] = [
+    ("DOGE/USD", 5),
+    ("DOGE/USDT", 5),
+    ("XRP/USD", 4),
+    ("XRP/USDT", 4),
+    ("ADA/USD", 4),
+    ("ADA/USDT", 4),
+    ("SHIB/USDT", 8),
+];
+
+thread_local! {
+    /// Decimals hinted by the server's `/symbols`, see [`set_decimals_hints`]
+    static DECIMALS_HINTS: std::cell::RefCell<std::collections::HashMap<String, usize>> =
+        std::cell::RefCell::new(std::collections::HashMap::new());
+}
+
+/// Remembers the server's decimals per symbol. A hint only ever adds
+/// decimals, for markets too cheap for the rules of their quote currency.
+pub fn set_decimals_hints(hints: impl IntoIterator<Item = (String, usize)>) {
+    DECIMALS_HINTS.with_borrow_mut(|map| map.extend(hints));
+}
+
+impl Default for FormatSpec {
+    /// US dollars with cents, also used for symbols without a quote
+    /// currency such as stock tickers
+    fn default() -> Self {
+        Self { decimals: 2, prefix: "$".into(), suffix: String::new() }
+    }
+}
+
+impl FormatSpec {
+    /// Derives the spec from the quote currency after the `/` (`ETH/BTC` is
+    /// quoted in BTC), then applies [`DECIMAL_OVERRIDES`] and the server's
+    /// hints. Currencies with a common sign get it as prefix, all others
+    /// their code as suffix.
+    pub fn for_symbol(symbol: &str) -> Self {
+        let mut spec = match symbol.split_once('/').map(|(_, quote)| quote) {
+            None | Some("USD") => Self::default(),
+            Some(quote) => {
+                let (decimals, prefix) = match quote {
+                    "EUR" => (2, "€"),
+                    "GBP" => (2, "£"),
+                    "JPY" => (0, "¥"),
+                    "BTC" => (8, ""),
+                    "ETH" => (6, ""),
+                    _ => (2, ""),
+                };
+                let suffix = if prefix.is_empty() { format!(" {}", quote) } else { String::new() };
+                Self { decimals, prefix: prefix.into(), suffix }
+            }
+        };
+        if let Some((_, decimals)) = DECIMAL_OVERRIDES.iter().find(|(s, _)| *s == symbol) {
+            spec.decimals = *decimals;
+        }
+        if let Some(hint) = DECIMALS_HINTS.with_borrow(|map| map.get(symbol).copied()) {
+            spec.decimals = spec.decimals.max(hint);
+        }
+        spec
+    }
+
+    pub fn format(&self, price: f64) -> String {
+        format!("{}{:.*}{}", self.prefix, self.decimals, price, self.suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(decimals: usize, prefix: &str, suffix: &str) -> FormatSpec {
+        FormatSpec { decimals, prefix: prefix.into(), suffix: suffix.into() }
+    }
+
+    #[test]
+    fn quote_currency_decides_the_spec() {
+        assert_eq!(FormatSpec::for_symbol("BTC/USD"), spec(2, "$", ""));
+        assert_eq!(FormatSpec::for_symbol("AAPL"), spec(2, "$", ""));
+        assert_eq!(FormatSpec::for_symbol("BTC/EUR"), spec(2, "€", ""));
+        assert_eq!(FormatSpec::for_symbol("BTC/GBP"), spec(2, "£", ""));
+        assert_eq!(FormatSpec::for_symbol("BTC/JPY"), spec(0, "¥", ""));
+        assert_eq!(FormatSpec::for_symbol("ETH/BTC"), spec(8, "", " BTC"));
+        assert_eq!(FormatSpec::for_symbol("LINK/ETH"), spec(6, "", " ETH"));
+        assert_eq!(FormatSpec::for_symbol("BTC/USDT"), spec(2, "", " USDT"));
+    }
+
+    #[test]
+    fn overrides_change_only_the_decimals() {
+        assert_eq!(FormatSpec::for_symbol("DOGE/USD"), spec(5, "$", ""));
+        assert_eq!(FormatSpec::for_symbol("XRP/USDT"), spec(4, "", " USDT"));
+        assert_eq!(FormatSpec::for_symbol("SHIB/USDT"), spec(8, "", " USDT"));
+    }
+
+    #[test]
+    fn hints_only_add_decimals() {
+        set_decimals_hints([("PEPE/USDT".to_string(), 10), ("SHIB/USDT".to_string(), 3)]);
+        assert_eq!(FormatSpec::for_symbol("PEPE/USDT").decimals, 10);
+        assert_eq!(FormatSpec::for_symbol("SHIB/USDT").decimals, 8);
+    }
+
+    #[test]
+    fn format_places_prefix_and_suffix() {
+        assert_eq!(FormatSpec::for_symbol("BTC/USD").format(43_210.456), "$43210.46");
+        assert_eq!(FormatSpec::for_symbol("BTC/JPY").format(6_500_000.4), "¥6500000");
+        assert_eq!(FormatSpec::for_symbol("ETH/BTC").format(0.0512), "0.05120000 BTC");
+    }
+}
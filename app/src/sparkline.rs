@@ -0,0 +1,303 @@
+/// How a sparkline connects its samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SparkStyle {
+    /// Straight segments between samples
+    #[default]
+    Line,
+    /// Line with a translucent fill down to the bottom edge
+    Area,
+    /// Horizontal then vertical segments, for discrete values like rates
+    Stepped,
+}
+
+impl SparkStyle {
+    pub const ALL: [SparkStyle; 3] = [SparkStyle::Line, SparkStyle::Area, SparkStyle::Stepped];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SparkStyle::Line => "line",
+            SparkStyle::Area => "area",
+            SparkStyle::Stepped => "stepped",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        Self::ALL.into_iter().find(|s| s.name() == name).unwrap_or_default()
+    }
+}
+
+/// Indices of the smallest and largest finite value in `data[range]`, or
+/// `None` if the range holds no finite value.
+fn extremes(data: &[f64], range: std::ops::Range<usize>) -> Option<(usize, usize)> {
+    let mut finite = range.filter(|&i| data[i].is_finite());
+    let first = finite.next()?;
+    Some(finite.fold((first, first), |(lo, hi), i| {
+        (if data[i] < data[lo] { i } else { lo }, if data[i] > data[hi] { i } else { hi })
+    }))
+}
+
+/// Min/max decimation: one bucket per pixel column, emitting the bucket
+/// extremes in the order they occur so short spikes survive.
+/// Returns `(x, value)` pairs; data that already fits is returned as is.
+/// A non-finite value (e.g. a NaN from a bad decode), or a bucket of only
+/// such values, becomes a NaN value that breaks the line there.
+fn decimate_min_max(data: &[f64], width: f64) -> Vec<(f64, f64)> {
+    let n = data.len();
+    let columns = width.floor().max(1.0) as usize;
+    if n <= columns {
+        let step = if n > 1 { width / (n - 1) as f64 } else { width };
+        return data.iter().enumerate().map(|(i, &v)| (step * i as f64, if v.is_finite() { v } else { f64::NAN })).collect();
+    }
+    let step = if columns > 1 { width / (columns - 1) as f64 } else { width };
+    let mut out = Vec::with_capacity(columns * 2);
+    for c in 0..columns {
+        let x = step * c as f64;
+        let Some((lo, hi)) = extremes(data, c * n / columns..(c + 1) * n / columns) else {
+            out.push((x, f64::NAN));
+            continue;
+        };
+        let (first, second) = if lo <= hi { (lo, hi) } else { (hi, lo) };
+        out.push((x, data[first]));
+        if second != first {
+            out.push((x, data[second]));
+        }
+    }
+    out
+}
+
+/// The newest samples that have a timestamp. The timestamps are kept in a
+/// separate buffer that may be shorter (e.g. restored sessions), so both
+/// are aligned at their newest end.
+fn timed<'a>(data: &'a [f64], times: &'a [i64]) -> (&'a [f64], &'a [i64]) {
+    let n = data.len().min(times.len());
+    (&data[data.len() - n..], &times[times.len() - n..])
+}
+
+/// X of timestamp `t` between the oldest and newest of `times`.
+fn time_x(times: &[i64], t: i64, width: f64) -> f64 {
+    let (first, last) = (times[0], times[times.len() - 1]);
+    (t - first) as f64 / (last - first).max(1) as f64 * width
+}
+
+/// Min/max decimation like [`decimate_min_max`], but placing each sample at
+/// its timestamp, so a stall in the feed shows as a long flat segment.
+fn decimate_timed(data: &[f64], times: &[i64], width: f64) -> Vec<(f64, f64)> {
+    let (data, times) = timed(data, times);
+    if data.len() < 2 { return decimate_min_max(data, width); }
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let column = time_x(times, times[start], width).floor();
+        let mut end = start + 1;
+        while end < data.len() && time_x(times, times[end], width).floor() == column {
+            end += 1;
+        }
+        match extremes(data, start..end) {
+            Some((lo, hi)) => {
+                let (first, second) = if lo <= hi { (lo, hi) } else { (hi, lo) };
+                out.push((time_x(times, times[first], width), data[first]));
+                if second != first {
+                    out.push((time_x(times, times[second], width), data[second]));
+                }
+            }
+            None => out.push((time_x(times, times[start], width), f64::NAN)),
+        }
+        start = end;
+    }
+    out
+}
+
+/// Outline of `data` in SVG space, scaled to `width` x `height` with the
+/// minimum at the bottom. Samples are spread evenly, or placed at their
+/// `times` when given. `Stepped` holds each value until the next sample's
+/// x before moving to it. Non-finite samples split the outline into
+/// segments, without points of their own.
+fn outline(data: &[f64], times: Option<&[i64]>, width: f64, height: f64, style: SparkStyle) -> Vec<Vec<(f64, f64)>> {
+    let points = match times {
+        Some(times) => decimate_timed(data, times, width),
+        None => decimate_min_max(data, width),
+    };
+    // Decimation keeps every bucket's extremes, so also the overall ones
+    let (min, max) = points.iter().filter(|(_, v)| !v.is_nan()).fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), &(_, v)| (mn.min(v), mx.max(v)));
+    let range = if (max - min).abs() < 1e-9 { 1.0 } else { max - min };
+    let mut segments: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut current: Vec<(f64, f64)> = Vec::new();
+    for (x, v) in points {
+        if v.is_nan() {
+            if !current.is_empty() { segments.push(std::mem::take(&mut current)); }
+            continue;
+        }
+        let y = height - ((v - min) / range) * height;
+        if let (SparkStyle::Stepped, Some(&(_, prev_y))) = (style, current.last()) {
+            current.push((x, prev_y));
+        }
+        current.push((x, y));
+    }
+    if !current.is_empty() { segments.push(current); }
+    segments
+}
+
+/// SVG path data drawing `points` as one open line
+fn format_segment(points: &[(f64, f64)]) -> String {
+    points.iter().enumerate().map(|(i, (x, y))| format!("{}{:.1},{:.1}", if i == 0 { "M" } else { "L" }, x, y)).collect::<Vec<_>>().join(" ")
+}
+
+/// SVG path data (`d`) of `data` drawn in `style`, scaled to `width` x
+/// `height` with the minimum at the bottom. Each run of finite samples is
+/// its own subpath, so the line breaks at a NaN or infinity.
+pub fn sparkline_points(data: &[f64], width: f64, height: f64, style: SparkStyle) -> String {
+    outline(data, None, width, height, style).iter().map(|s| format_segment(s)).collect::<Vec<_>>().join(" ")
+}
+
+/// [`sparkline_points`] on a time axis: x follows each sample's timestamp
+/// in `times` (µs) instead of its index.
+pub fn sparkline_points_timed(data: &[f64], times: &[i64], width: f64, height: f64, style: SparkStyle) -> String {
+    outline(data, Some(times), width, height, style).iter().map(|s| format_segment(s)).collect::<Vec<_>>().join(" ")
+}
+
+/// Closes each segment of an `Area` outline along the bottom edge; empty for
+/// the other styles, so the path can stay in the markup and draws nothing.
+fn area(data: &[f64], times: Option<&[i64]>, width: f64, height: f64, style: SparkStyle) -> String {
+    if style != SparkStyle::Area { return String::new(); }
+    outline(data, times, width, height, style)
+        .into_iter()
+        .map(|mut points| {
+            let (first_x, last_x) = (points[0].0, points[points.len() - 1].0);
+            points.push((last_x, height));
+            points.push((first_x, height));
+            format!("{} Z", format_segment(&points))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// SVG path data of the area under an `Area` sparkline: each segment of its
+/// outline closed along the bottom edge. Empty for the other styles, so the
+/// path can stay in the markup and draws nothing.
+pub fn sparkline_area(data: &[f64], width: f64, height: f64, style: SparkStyle) -> String {
+    area(data, None, width, height, style)
+}
+
+/// [`sparkline_area`] on the time axis of [`sparkline_points_timed`].
+pub fn sparkline_area_timed(data: &[f64], times: &[i64], width: f64, height: f64, style: SparkStyle) -> String {
+    area(data, Some(times), width, height, style)
+}
+
+/// X of sample `index` in `sparkline_points` for `len` samples, using
+/// the same column bucketing as the decimation.
+pub fn sparkline_x(len: usize, index: usize, width: f64) -> f64 {
+    let columns = width.floor().max(1.0) as usize;
+    if len <= columns {
+        let step = if len > 1 { width / (len - 1) as f64 } else { width };
+        return step * index as f64;
+    }
+    let step = if columns > 1 { width / (columns - 1) as f64 } else { width };
+    step * (index * columns / len) as f64
+}
+
+/// X of sample `index` of `data` in `sparkline_points_timed`. Samples older
+/// than the timestamps sit at the left edge.
+pub fn sparkline_x_timed(data: &[f64], times: &[i64], index: usize, width: f64) -> f64 {
+    let (timed_data, times) = timed(data, times);
+    if timed_data.len() < 2 { return sparkline_x(data.len(), index, width); }
+    match index.checked_sub(data.len() - timed_data.len()) {
+        Some(i) => time_x(times, times[i], width),
+        None => 0.0,
+    }
+}
+
+/// `count` labels spread evenly along the time axis, from the age of the
+/// oldest sample ("-12s") to the newest ("now"). Empty below two samples.
+pub fn time_axis_labels(data: &[f64], times: &[i64], count: usize) -> Vec<String> {
+    let (_, times) = timed(data, times);
+    if times.len() < 2 || count < 2 { return Vec::new(); }
+    let span_s = (times[times.len() - 1] - times[0]) as f64 / 1e6;
+    (0..count).map(|i| {
+        let age = span_s * (count - 1 - i) as f64 / (count - 1) as f64;
+        if i == count - 1 {
+            "now".to_string()
+        } else if span_s < 10.0 {
+            format!("-{:.1}s", age)
+        } else {
+            format!("-{:.0}s", age)
+        }
+    }).collect()
+}
+
+/// Text alternative of a sparkline for its `aria-label`: name, current
+/// value and the change over the window, e.g. "BTC/USD, current $45000.00,
+/// up 2.0% over window".
+pub fn sparkline_label(name: &str, current: &str, data: &[f64]) -> String {
+    let mut finite = data.iter().copied().filter(|v| v.is_finite());
+    let Some(first) = finite.next() else {
+        return format!("{}, no data yet", name);
+    };
+    let Some(last) = finite.next_back() else {
+        return format!("{}, current {}", name, current);
+    };
+    if first == 0.0 {
+        return format!("{}, current {}", name, current);
+    }
+    let pct = (last - first) / first.abs() * 100.0;
+    if pct.abs() < 0.05 {
+        format!("{}, current {}, flat over window", name, current)
+    } else {
+        format!("{}, current {}, {} {:.1}% over window", name, current, if pct > 0.0 { "up" } else { "down" }, pct.abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_finite(d: &str) {
+        assert!(!d.contains("NaN") && !d.contains("inf"), "{}", d);
+    }
+
+    #[test]
+    fn line_breaks_at_non_finite_samples() {
+        let d = sparkline_points(&[1.0, 2.0, f64::NAN, 3.0, 4.0], 40.0, 10.0, SparkStyle::Line);
+        assert_eq!(d, "M0.0,10.0 L10.0,6.7 M30.0,3.3 L40.0,0.0");
+
+        // Infinities neither scale the chart nor get a point
+        let d = sparkline_points(&[1.0, f64::INFINITY, 2.0, 3.0, f64::NEG_INFINITY, 4.0], 50.0, 10.0, SparkStyle::Line);
+        assert_eq!(d, "M0.0,10.0 M20.0,6.7 L30.0,3.3 M50.0,0.0");
+    }
+
+    #[test]
+    fn stepped_and_area_break_too() {
+        let data = [1.0, 2.0, f64::NAN, 3.0, 4.0];
+        let d = sparkline_points(&data, 40.0, 10.0, SparkStyle::Stepped);
+        assert_eq!(d, "M0.0,10.0 L10.0,10.0 L10.0,6.7 M30.0,3.3 L40.0,3.3 L40.0,0.0");
+        // Each segment is closed along the bottom edge on its own
+        let d = sparkline_area(&data, 40.0, 10.0, SparkStyle::Area);
+        assert_eq!(d, "M0.0,10.0 L10.0,6.7 L10.0,10.0 L0.0,10.0 Z M30.0,3.3 L40.0,0.0 L40.0,10.0 L30.0,10.0 Z");
+    }
+
+    #[test]
+    fn decimated_bucket_without_finite_values_breaks_the_line() {
+        let mut data: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+        data[100..200].fill(f64::NAN);
+        data[500] = f64::INFINITY;
+        let d = sparkline_points(&data, 10.0, 10.0, SparkStyle::Line);
+        assert_finite(&d);
+        // Bucket 1 holds only NaN; the lone infinity in bucket 5 does not break it
+        assert_eq!(d.matches('M').count(), 2, "{}", d);
+        assert!(d.starts_with("M0.0,10.0 L0.0,9.0 M2.2,8.0"), "{}", d);
+    }
+
+    #[test]
+    fn timed_line_breaks_at_non_finite_samples() {
+        let data = [1.0, f64::NAN, 3.0, 4.0];
+        let d = sparkline_points_timed(&data, &[0, 1_000_000, 2_000_000, 4_000_000], 40.0, 10.0, SparkStyle::Line);
+        assert_eq!(d, "M0.0,10.0 M20.0,3.3 L40.0,0.0");
+    }
+
+    #[test]
+    fn only_non_finite_samples_draw_nothing() {
+        let data = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY];
+        assert_eq!(sparkline_points(&data, 40.0, 10.0, SparkStyle::Line), "");
+        assert_eq!(sparkline_area(&data, 40.0, 10.0, SparkStyle::Area), "");
+        assert_eq!(sparkline_points(&[], 40.0, 10.0, SparkStyle::Line), "");
+    }
+}
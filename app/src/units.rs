@@ -0,0 +1,92 @@
+/// Unit system vehicle signals are shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl UnitSystem {
+    pub fn name(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "metric",
+            UnitSystem::Imperial => "imperial",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        if name == "imperial" { UnitSystem::Imperial } else { UnitSystem::Metric }
+    }
+}
+
+/// Metric unit, its imperial counterpart and how many imperial units one
+/// metric unit is. Temperatures also need an offset and are handled apart.
+const FACTORS: [(&str, &str, f64); 3] = [
+    ("km/h", "mph", 0.621_371),
+    ("kPa", "psi", 0.145_038),
+    ("L", "gal", 0.264_172), // US gallons
+];
+
+/// `value` in `from` expressed in `to_system`, with the unit to show. Units
+/// already in `to_system` and units not in the table pass through unchanged.
+pub fn convert(value: f64, from: &str, to_system: UnitSystem) -> (f64, String) {
+    match (from, to_system) {
+        ("°C", UnitSystem::Imperial) => return (value * 9.0 / 5.0 + 32.0, "°F".into()),
+        ("°F", UnitSystem::Metric) => return ((value - 32.0) * 5.0 / 9.0, "°C".into()),
+        _ => {}
+    }
+    for (metric, imperial, factor) in FACTORS {
+        match to_system {
+            UnitSystem::Imperial if from == metric => return (value * factor, imperial.into()),
+            UnitSystem::Metric if from == imperial => return (value / factor, metric.into()),
+            _ => {}
+        }
+    }
+    (value, from.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_converts(value: f64, from: &str, to_system: UnitSystem, expected: f64, unit: &str) {
+        let (converted, to) = convert(value, from, to_system);
+        assert!((converted - expected).abs() < 1e-6, "{} {} -> {} {}, expected {}", value, from, converted, to, expected);
+        assert_eq!(to, unit);
+    }
+
+    #[test]
+    fn metric_to_imperial() {
+        assert_converts(100.0, "km/h", UnitSystem::Imperial, 62.1371, "mph");
+        assert_converts(200.0, "kPa", UnitSystem::Imperial, 29.0076, "psi");
+        assert_converts(10.0, "L", UnitSystem::Imperial, 2.64172, "gal");
+        assert_converts(100.0, "°C", UnitSystem::Imperial, 212.0, "°F");
+        assert_converts(-40.0, "°C", UnitSystem::Imperial, -40.0, "°F");
+    }
+
+    #[test]
+    fn imperial_to_metric() {
+        assert_converts(62.1371, "mph", UnitSystem::Metric, 100.0, "km/h");
+        assert_converts(29.0076, "psi", UnitSystem::Metric, 200.0, "kPa");
+        assert_converts(2.64172, "gal", UnitSystem::Metric, 10.0, "L");
+        assert_converts(32.0, "°F", UnitSystem::Metric, 0.0, "°C");
+    }
+
+    #[test]
+    fn other_units_pass_through() {
+        // Already in the target system
+        assert_eq!(convert(80.0, "km/h", UnitSystem::Metric), (80.0, "km/h".to_string()));
+        assert_eq!(convert(50.0, "mph", UnitSystem::Imperial), (50.0, "mph".to_string()));
+        assert_eq!(convert(90.0, "°C", UnitSystem::Metric), (90.0, "°C".to_string()));
+        // Not in the table
+        assert_eq!(convert(3000.0, "rpm", UnitSystem::Imperial), (3000.0, "rpm".to_string()));
+        assert_eq!(convert(12.5, "V", UnitSystem::Metric), (12.5, "V".to_string()));
+    }
+
+    #[test]
+    fn unit_system_names() {
+        assert_eq!(UnitSystem::from_name(UnitSystem::Imperial.name()), UnitSystem::Imperial);
+        assert_eq!(UnitSystem::from_name("metric"), UnitSystem::Metric);
+        assert_eq!(UnitSystem::from_name("bogus"), UnitSystem::Metric);
+    }
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+
+/// Every message the server sends on `/ws`, tagged by `type`. The server
+/// serializes these and the client deserializes the very same enum, so the
+/// field definitions can't drift apart. Timestamps are micros since the
+/// epoch unless noted. Bump [`crate::SCHEMA_VERSION`] on incompatible changes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WireMessage {
+    Price {
+        symbol: String,
+        price: f64,
+        volume: u64,
+        ts: i64,
+    },
+    Trade {
+        symbol: String,
+        price: f64,
+        size: f64,
+        side: String, // "buy" or "sell"
+        ts: i64,
+        /// Exchange trade id, increasing per symbol; live data only
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        trade_id: Option<u64>,
+    },
+    /// The trades of one symbol in the second starting at `ts`
+    TradeSummary {
+        symbol: String,
+        count: u64,
+        volume: f64,
+        vwap: f64,
+        buy_ratio: f64, // share of `volume` bought, 0..=1
+        ts: i64,
+    },
+    /// `(price, size)` levels, best price first
+    Book {
+        symbol: String,
+        bids: Vec<(f64, f64)>,
+        asks: Vec<(f64, f64)>,
+        ts: i64,
+    },
+    /// Completed OHLC candle; `ts` is the bucket start
+    Candle {
+        symbol: String,
+        open: f64,
+        high: f64,
+        low: f64,
+        close: f64,
+        volume: u64,
+        ts: i64,
+    },
+    System {
+        cpu_pct: f64,
+        mem_mb: u64,
+        msg_rate: u64, // messages broadcast in the last second
+        ts: i64,
+    },
+    /// Decoded CAN signal
+    VehicleSignal {
+        signal_name: String,
+        value: f64,
+        unit: String,
+        bus_id: u8,
+        source_id: u32,
+        extended: bool, // `source_id` is a 29-bit ID
+        timestamp_us: u64,
+    },
+    /// Raw frame of the simulated CAN source (`USE_CAN=true`)
+    CanFrame {
+        bus_id: u8,
+        can_id: u32,
+        data: Vec<u8>,
+        timestamp_us: u64,
+        dlc: u8,
+        extended: bool,
+    },
+    /// Load of one CAN bus, once per second
+    BusStatus {
+        bus_id: u8,
+        load_percent: f32,
+        error_count: u32,
+        messages_per_sec: u32,
+        timestamp_us: u64,
+    },
+    /// Throughput and send latency of one CAN bus, once per second
+    CanStats {
+        bus_id: u8,
+        messages_per_second: f32,
+        bytes_per_second: f32,
+        bus_load_percent: f32,
+        error_rate: f32, // errors per second
+        max_latency_us: u64,
+        avg_latency_us: u64,
+    },
+    /// The connection fell behind the broadcast buffer; `total` counts all
+    /// messages skipped on this connection so far
+    Lag {
+        skipped: u64,
+        #[serde(default)]
+        total: u64,
+    },
+    /// Messages coalesced over `batch_ms`; never nested
+    Batch {
+        items: Vec<WireMessage>,
+    },
+    /// The server is stopping; the socket closes right after
+    Shutdown,
+    /// Answer to a client ping, echoing its `ts` (client clock, ms)
+    Pong {
+        ts: f64,
+        /// Server clock when answering, micros since the epoch; 0 from
+        /// servers that don't send it
+        #[serde(default)]
+        server_ts: i64,
+    },
+    /// First frame on every connection
+    Hello {
+        schema_version: u32,
+        symbols: Vec<String>,
+    },
+    /// A control message was rejected, e.g. `"rate limited"`
+    Error {
+        msg: String,
+        /// Number of a [`leptos_rust::error_code::ErrorCode`]; 0 from
+        /// servers that don't send it
+        #[serde(default)]
+        code: u32,
+    },
+    /// Reply to a control message carrying a `req_id`: the fields that took
+    /// effect, by their control message name, with the values after
+    /// clamping. Fields the server ignored (e.g. an unknown symbol) are
+    /// missing.
+    Ack {
+        req_id: u64,
+        applied: serde_json::Map<String, serde_json::Value>,
+    },
+    /// Simulated streams (of [`crate::STREAMS`]) currently sending; sent
+    /// after `hello` and to every client when `enabled_streams` changes
+    Streams {
+        enabled: Vec<String>,
+    },
+    /// Health of a symbol's upstream feed (live mode): `"degraded"` on
+    /// every retry once its circuit breaker is open, `"ok"` when it recovers
+    Upstream {
+        status: String,
+        symbol: String,
+    },
+    /// Any type this build doesn't know yet
+    #[serde(other)]
+    Other,
+}
+
+impl WireMessage {
+    /// The `type` tag, `"other"` for unknown types.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            WireMessage::Price { .. } => "price",
+            WireMessage::Trade { .. } => "trade",
+            WireMessage::TradeSummary { .. } => "trade_summary",
+            WireMessage::Book { .. } => "book",
+            WireMessage::Candle { .. } => "candle",
+            WireMessage::System { .. } => "system",
+            WireMessage::VehicleSignal { .. } => "vehicle_signal",
+            WireMessage::CanFrame { .. } => "can_frame",
+            WireMessage::BusStatus { .. } => "bus_status",
+            WireMessage::CanStats { .. } => "can_stats",
+            WireMessage::Lag { .. } => "lag",
+            WireMessage::Batch { .. } => "batch",
+            WireMessage::Shutdown => "shutdown",
+            WireMessage::Pong { .. } => "pong",
+            WireMessage::Hello { .. } => "hello",
+            WireMessage::Error { .. } => "error",
+            WireMessage::Ack { .. } => "ack",
+            WireMessage::Streams { .. } => "streams",
+            WireMessage::Upstream { .. } => "upstream",
+            WireMessage::Other => "other",
+        }
+    }
+}
+
+/// Body of the server's `GET /history?symbol=..&limit=..`: the most recent
+/// price ticks of one symbol, oldest first, as `(ts, price)` with `ts` in
+/// micros since the epoch like [`WireMessage::Price`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PriceHistory {
+    pub symbol: String,
+    pub prices: Vec<(i64, f64)>,
+}
+
+/// One entry of the server's `GET /symbols`: a market clients can subscribe to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    /// Start price of the simulated random walk
+    pub base_price: f64,
+    /// Decimals that show at least four significant digits of the latest
+    /// known price; a lower bound for the client's own format rules
+    pub decimals: usize,
+}
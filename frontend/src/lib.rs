@@ -2,7 +2,7 @@
 pub fn hydrate() {
     use app::*;
     
-    _ = console_log::init_with_level(log::Level::Debug);
+    _ = console_log::init_with_level(console_log_level());
 
     console_error_panic_hook::set_once();
     leptos::mount::hydrate_body(App);
@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use app::WireMessage;
+use rand::Rng;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+
+use crate::{health::Health, seed, SymbolParamMap};
+
+/// Most ticks a burst may send
+pub const MAX_MESSAGES: u32 = 100_000;
+
+/// Extra price ticks requested with `{"burst": {...}}`, sent on top of the
+/// regular streams
+#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+pub struct BurstConfig {
+    pub messages: u32,
+    pub over_ms: u32, // 0 = all at once
+}
+
+impl BurstConfig {
+    /// Clamps to at most `MAX_MESSAGES` ticks over at most one minute
+    pub fn sanitized(self) -> Self {
+        Self { messages: self.messages.clamp(1, MAX_MESSAGES), over_ms: self.over_ms.min(60_000) }
+    }
+}
+
+/// Admits one burst at a time across all connections, so concurrent
+/// requests can't stack up more than `MAX_MESSAGES` ticks in flight
+#[derive(Debug, Default)]
+pub struct BurstSlot(AtomicBool);
+
+impl BurstSlot {
+    /// `None` while another burst runs; the slot is free again once the
+    /// guard is dropped
+    pub fn try_acquire(self: &Arc<Self>) -> Option<BurstGuard> {
+        self.0
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| BurstGuard(self.clone()))
+    }
+}
+
+/// Held by the running burst
+pub struct BurstGuard(Arc<BurstSlot>);
+
+impl Drop for BurstGuard {
+    fn drop(&mut self) {
+        self.0 .0.store(false, Ordering::Release);
+    }
+}
+
+/// Broadcasts `config.messages` synthetic price ticks spread evenly over
+/// `config.over_ms`, cycling through the simulated symbols at their current
+/// walk price (the walk itself doesn't move). Resolves to the number of
+/// ticks that reached the broadcast channel, i.e. fewer than requested only
+/// when no client was listening.
+pub async fn run(
+    config: BurstConfig,
+    tx: broadcast::Sender<String>,
+    params: SymbolParamMap,
+    symbols: Arc<Vec<String>>,
+    health: Arc<Health>,
+    seed: Option<u64>,
+) -> u32 {
+    // Every burst of a seeded server repeats the same volumes
+    let mut rng = seed::stream_rng(seed, "burst");
+    let start = Instant::now();
+    let over = Duration::from_millis(config.over_ms as u64);
+    let mut sent = 0;
+    for i in 0..config.messages {
+        // Late wakeups catch up immediately instead of stretching the burst
+        tokio::time::sleep_until(start + over * i / config.messages).await;
+        let Some(symbol) = symbols.get(i as usize % symbols.len().max(1)) else { break };
+        let Some(mid) = params.lock().unwrap().get(symbol).map(|p| p.price) else { continue };
+        let tick = WireMessage::Price {
+            symbol: symbol.clone(),
+            price: (mid * 100.0_f64).round() / 100.0,
+            volume: rng.gen_range(100..10000),
+            ts: chrono::Utc::now().timestamp_micros(),
+        };
+        if let Ok(s) = serde_json::to_string(&tick) {
+            if tx.send(s).is_ok() {
+                sent += 1;
+                health.message_sent();
+            }
+        }
+    }
+    sent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_burst_at_a_time() {
+        let slot = Arc::new(BurstSlot::default());
+        let guard = slot.try_acquire().unwrap();
+        assert!(slot.try_acquire().is_none());
+        drop(guard);
+        assert!(slot.try_acquire().is_some());
+    }
+
+    #[test]
+    fn sanitized_caps_the_ticks() {
+        let config = BurstConfig { messages: u32::MAX, over_ms: u32::MAX }.sanitized();
+        assert_eq!((config.messages, config.over_ms), (MAX_MESSAGES, 60_000));
+        assert_eq!(BurstConfig { messages: 0, over_ms: 0 }.sanitized().messages, 1);
+    }
+}
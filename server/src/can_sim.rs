@@ -0,0 +1,281 @@
+use app::WireMessage;
+use leptos_rust::can_types::{
+    automotive_ids, decode_batch, ByteOrder, CANMessage, CANMessageBatch, CANPerformanceStats, CANSignal,
+};
+use rand::{rngs::StdRng, Rng};
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::{health::Health, seed};
+
+/// Scheduler resolution; every cycle time is a multiple of it
+const TICK_MS: u64 = 10;
+/// Frames always carry 8 data bytes, like most production ECUs
+const DLC: u8 = 8;
+const DEFAULT_BITRATE: u32 = 500_000;
+
+/// A periodically sent signal: CAN ID, cycle time and DBC-style layout
+/// (little endian from bit 0)
+struct SimSignal {
+    name: &'static str,
+    can_id: u32,
+    extended: bool,
+    period_ms: u64,
+    length: u8,
+    scale: f64,
+    offset: f64,
+    unit: &'static str,
+}
+
+const SIGNALS: [SimSignal; 7] = [
+    SimSignal { name: "engine_rpm", can_id: automotive_ids::ENGINE_RPM, extended: false, period_ms: 20, length: 16, scale: 0.25, offset: 0.0, unit: "rpm" },
+    SimSignal { name: "vehicle_speed", can_id: automotive_ids::VEHICLE_SPEED, extended: false, period_ms: 50, length: 16, scale: 0.01, offset: 0.0, unit: "km/h" },
+    SimSignal { name: "engine_temp", can_id: automotive_ids::ENGINE_TEMP, extended: false, period_ms: 1000, length: 8, scale: 1.0, offset: -40.0, unit: "°C" },
+    SimSignal { name: "battery_voltage", can_id: automotive_ids::BATTERY_VOLTAGE, extended: false, period_ms: 100, length: 16, scale: 0.001, offset: 0.0, unit: "V" },
+    SimSignal { name: "gear_position", can_id: automotive_ids::GEAR_POSITION, extended: false, period_ms: 200, length: 8, scale: 1.0, offset: 0.0, unit: "" },
+    SimSignal { name: "steering_angle", can_id: automotive_ids::STEERING_ANGLE, extended: false, period_ms: 20, length: 16, scale: 0.1, offset: -3276.8, unit: "°" },
+    SimSignal { name: "wheel_speed", can_id: automotive_ids::WHEEL_SPEED, extended: true, period_ms: 100, length: 16, scale: 1.0 / 256.0, offset: 0.0, unit: "km/h" },
+];
+
+/// Settings of the simulated CAN source
+#[derive(Clone, Copy, Debug)]
+pub struct CanSimConfig {
+    pub buses: u8,
+    pub bitrate: u32,
+}
+
+impl CanSimConfig {
+    /// `USE_CAN=true` enables the source; `CAN_BUSES` (1-8, default 1) and
+    /// `CAN_BITRATE` (bit/s, default 500000) configure it.
+    pub fn from_env() -> Option<Self> {
+        if std::env::var("USE_CAN").unwrap_or_default() != "true" {
+            return None;
+        }
+        let buses = match std::env::var("CAN_BUSES").ok().map(|v| v.parse::<u8>()) {
+            None => 1,
+            Some(Ok(n)) if (1..=8).contains(&n) => n,
+            Some(_) => {
+                eprintln!("CAN_BUSES must be 1-8, using 1");
+                1
+            }
+        };
+        let bitrate = match std::env::var("CAN_BITRATE").ok().map(|v| v.parse::<u32>()) {
+            None => DEFAULT_BITRATE,
+            Some(Ok(b)) if b > 0 => b,
+            Some(_) => {
+                eprintln!("Invalid CAN_BITRATE, using {}", DEFAULT_BITRATE);
+                DEFAULT_BITRATE
+            }
+        };
+        Some(Self { buses, bitrate })
+    }
+}
+
+/// Starts one sender task per bus. Each broadcasts its `DataFrame`s, the
+/// `VehicleSignal`s decoded from them, and once per second `BusStatus` and
+/// `CANPerformanceStats`.
+pub fn spawn(
+    config: CanSimConfig,
+    tx: broadcast::Sender<String>,
+    shutdown: Arc<AtomicBool>,
+    health: Arc<Health>,
+    seed: Option<u64>,
+) {
+    for bus_id in 0..config.buses {
+        let (tx, shutdown, health) = (tx.clone(), shutdown.clone(), health.clone());
+        let rng = seed::stream_rng(seed, &format!("can{}", bus_id));
+        tokio::spawn(async move { run_bus(bus_id, config.bitrate, tx, shutdown, health, rng).await });
+    }
+}
+
+/// Counters of the current one-second window
+#[derive(Default)]
+struct Window {
+    frames: u32,
+    bytes: u64,
+    bits: u64,
+    latency_sum_us: u64,
+    latency_max_us: u64,
+    ticks: u64,
+}
+
+async fn run_bus(
+    bus_id: u8,
+    bitrate: u32,
+    tx: broadcast::Sender<String>,
+    shutdown: Arc<AtomicBool>,
+    health: Arc<Health>,
+    mut rng: StdRng,
+) {
+    let db = signal_db();
+    let send = |msg: WireMessage| {
+        if let Ok(s) = serde_json::to_string(&msg) {
+            let _ = tx.send(s);
+            health.message_sent();
+        }
+    };
+    let mut interval = tokio::time::interval(Duration::from_millis(TICK_MS));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let start = Instant::now();
+    let mut window = Window::default();
+    let mut window_start = start;
+    let mut tick_ms = 0u64;
+    let mut batch_id = 0u32;
+
+    while !shutdown.load(Ordering::Relaxed) {
+        // How late this cycle starts is the send latency of its frames
+        let scheduled = interval.tick().await;
+        let latency_us = scheduled.elapsed().as_micros() as u64;
+        window.latency_sum_us += latency_us;
+        window.latency_max_us = window.latency_max_us.max(latency_us);
+        window.ticks += 1;
+
+        let timestamp_us = chrono::Utc::now().timestamp_micros() as u64;
+        // Buses are offset in time so they don't show identical values
+        let t = start.elapsed().as_secs_f64() + bus_id as f64 * 7.0;
+        let messages: Vec<CANMessage> = SIGNALS
+            .iter()
+            .filter(|sig| tick_ms.is_multiple_of(sig.period_ms))
+            .map(|sig| CANMessage::DataFrame {
+                bus_id,
+                can_id: sig.can_id,
+                data: encode(sig, physical_value(sig.can_id, t, &mut rng)),
+                timestamp_us,
+                dlc: DLC,
+                extended: sig.extended,
+            })
+            .collect();
+        tick_ms += TICK_MS;
+
+        if !messages.is_empty() {
+            window.frames += messages.len() as u32;
+            window.bytes += messages.len() as u64 * DLC as u64;
+            window.bits += messages
+                .iter()
+                .map(|msg| match msg {
+                    CANMessage::DataFrame { dlc, extended, .. } => frame_bits(*dlc, *extended) as u64,
+                    _ => 0,
+                })
+                .sum::<u64>();
+            batch_id = batch_id.wrapping_add(1);
+            let batch = CANMessageBatch { messages, batch_id, start_timestamp: timestamp_us, end_timestamp: timestamp_us };
+            let decoded = decode_batch(&batch, &db);
+            for msg in batch.messages.into_iter().chain(decoded) {
+                if let Some(msg) = to_wire(msg) {
+                    send(msg);
+                }
+            }
+        }
+
+        let elapsed = window_start.elapsed().as_secs_f64();
+        if elapsed >= 1.0 {
+            let load_percent = (window.bits as f64 / (bitrate as f64 * elapsed) * 100.0) as f32;
+            let status = CANMessage::BusStatus {
+                bus_id,
+                load_percent,
+                error_count: 0,
+                messages_per_sec: (window.frames as f64 / elapsed).round() as u32,
+                timestamp_us,
+            };
+            let stats = CANPerformanceStats {
+                bus_id,
+                messages_per_second: (window.frames as f64 / elapsed) as f32,
+                bytes_per_second: (window.bytes as f64 / elapsed) as f32,
+                bus_load_percent: load_percent,
+                error_rate: 0.0,
+                max_latency_us: window.latency_max_us,
+                avg_latency_us: window.latency_sum_us / window.ticks.max(1),
+            };
+            if let Some(msg) = to_wire(status) {
+                send(msg);
+            }
+            send(stats_to_wire(stats));
+            window = Window::default();
+            window_start = Instant::now();
+        }
+    }
+}
+
+/// Bits on the wire for one data frame: 47 (standard) or 67 (extended ID)
+/// framing bits plus the data, plus worst-case bit stuffing over the
+/// stuffed part (SOF up to the CRC).
+fn frame_bits(dlc: u8, extended: bool) -> u32 {
+    let data_bits = dlc.min(8) as u32 * 8;
+    let (framing, stuffed) = if extended { (67, 54) } else { (47, 34) };
+    framing + data_bits + (stuffed + data_bits - 1) / 4
+}
+
+/// Plausible, slowly varying physical values for a drive cycle
+fn physical_value(can_id: u32, t: f64, rng: &mut StdRng) -> f64 {
+    let speed = (60.0 + 50.0 * (t * 0.05).sin()).max(0.0);
+    match can_id {
+        automotive_ids::ENGINE_RPM => 2400.0 + 1600.0 * (t * 0.05 + 0.3).sin() + rng.gen_range(-30.0..30.0),
+        automotive_ids::VEHICLE_SPEED => speed,
+        automotive_ids::WHEEL_SPEED => speed + rng.gen_range(-0.5..0.5),
+        automotive_ids::ENGINE_TEMP => 90.0 - 70.0 * (-t / 120.0).exp(),
+        automotive_ids::BATTERY_VOLTAGE => 13.8 + 0.2 * (t * 2.0).sin() + rng.gen_range(-0.02..0.02),
+        automotive_ids::GEAR_POSITION => (1.0 + speed / 25.0).floor().min(6.0),
+        automotive_ids::STEERING_ANGLE => 30.0 * (t * 0.3).sin(),
+        _ => 0.0,
+    }
+}
+
+/// Packs `value` into an 8-byte payload, inverse of `CANSignal::decode`
+fn encode(sig: &SimSignal, value: f64) -> Vec<u8> {
+    let max_raw = (1u64 << sig.length) - 1;
+    let raw = ((value - sig.offset) / sig.scale).round().clamp(0.0, max_raw as f64) as u64;
+    let mut data = raw.to_le_bytes().to_vec();
+    data.truncate(DLC as usize);
+    data
+}
+
+/// Signal database matching `SIGNALS`, used to decode the frames again
+fn signal_db() -> Vec<CANSignal> {
+    SIGNALS
+        .iter()
+        .map(|sig| CANSignal {
+            name: sig.name.to_string(),
+            can_id: sig.can_id,
+            extended: sig.extended,
+            start_bit: 0,
+            length: sig.length,
+            byte_order: ByteOrder::LittleEndian,
+            signed: false,
+            scale: sig.scale,
+            offset: sig.offset,
+            unit: sig.unit.to_string(),
+            min_val: 0.0,
+            max_val: 0.0, // unbounded
+        })
+        .collect()
+}
+
+/// Wire form of a CAN message; error frames aren't simulated
+fn to_wire(msg: CANMessage) -> Option<WireMessage> {
+    Some(match msg {
+        CANMessage::DataFrame { bus_id, can_id, data, timestamp_us, dlc, extended } => {
+            WireMessage::CanFrame { bus_id, can_id, data, timestamp_us, dlc, extended }
+        }
+        CANMessage::BusStatus { bus_id, load_percent, error_count, messages_per_sec, timestamp_us } => {
+            WireMessage::BusStatus { bus_id, load_percent, error_count, messages_per_sec, timestamp_us }
+        }
+        CANMessage::VehicleSignal { signal_name, value, unit, bus_id, source_id, extended, timestamp_us } => {
+            WireMessage::VehicleSignal { signal_name, value, unit, bus_id, source_id, extended, timestamp_us }
+        }
+        CANMessage::ErrorFrame { .. } => return None,
+    })
+}
+
+fn stats_to_wire(stats: CANPerformanceStats) -> WireMessage {
+    WireMessage::CanStats {
+        bus_id: stats.bus_id,
+        messages_per_second: stats.messages_per_second,
+        bytes_per_second: stats.bytes_per_second,
+        bus_load_percent: stats.bus_load_percent,
+        error_rate: stats.error_rate,
+        max_latency_us: stats.max_latency_us,
+        avg_latency_us: stats.avg_latency_us,
+    }
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+/// Consecutive failures after which the breaker opens
+const FAILURE_THRESHOLD: u32 = 3;
+/// Reconnect delay while closed, and the steps it grows through once open
+const DELAYS: [Duration; 3] = [Duration::from_secs(5), Duration::from_secs(30), Duration::from_secs(120)];
+
+/// Counts consecutive failures of one upstream connection. Below
+/// [`FAILURE_THRESHOLD`] every retry waits 5s; from there on the breaker is
+/// open and the delay grows to 30s and then 2min, until a success closes it.
+#[derive(Default)]
+pub struct CircuitBreaker {
+    failures: u32,
+}
+
+impl CircuitBreaker {
+    /// Records a failure and returns how long to wait before retrying
+    pub fn failure(&mut self) -> Duration {
+        self.failures += 1;
+        let step = self.failures.saturating_sub(FAILURE_THRESHOLD) as usize;
+        DELAYS[step.min(DELAYS.len() - 1)]
+    }
+
+    /// Closes the breaker; `true` if it was open
+    pub fn success(&mut self) -> bool {
+        let was_open = self.is_open();
+        self.failures = 0;
+        was_open
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.failures >= FAILURE_THRESHOLD
+    }
+}
@@ -0,0 +1,84 @@
+use axum::{extract::State, http::HeaderMap, response::IntoResponse, Json};
+use leptos::logging::log;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}};
+use std::time::Instant;
+
+use crate::AppState;
+
+/// One open `/ws` connection as listed on `/stats/connections`
+#[derive(Clone, Debug, Serialize)]
+pub struct ConnectionInfo {
+    pub id: u64,
+    pub remote_addr: SocketAddr,
+    pub origin: Option<String>,
+    pub user_agent: Option<String>,
+    pub connected_at: i64, // micros since the epoch
+}
+
+/// Open `/ws` connections by id; ids count up from 1 for the process lifetime
+#[derive(Default)]
+pub struct Connections {
+    last_id: AtomicU64,
+    open: Mutex<BTreeMap<u64, ConnectionInfo>>,
+}
+
+impl Connections {
+    /// Logs and lists a new connection until the returned guard is dropped,
+    /// which logs the disconnect with the connection's duration and the
+    /// broadcast messages counted in [`ConnectionEntry::sent`].
+    pub fn open(self: &Arc<Self>, remote_addr: SocketAddr, headers: &HeaderMap) -> ConnectionEntry {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let info = ConnectionInfo {
+            id: self.last_id.fetch_add(1, Ordering::Relaxed) + 1,
+            remote_addr,
+            origin: header("origin"),
+            user_agent: header("user-agent"),
+            connected_at: chrono::Utc::now().timestamp_micros(),
+        };
+        log!(
+            "ws #{} connected from {} (origin {}, user-agent {})",
+            info.id,
+            info.remote_addr,
+            info.origin.as_deref().unwrap_or("-"),
+            info.user_agent.as_deref().unwrap_or("-")
+        );
+        let id = info.id;
+        self.open.lock().unwrap().insert(id, info);
+        ConnectionEntry { connections: self.clone(), id, since: Instant::now(), sent: 0 }
+    }
+}
+
+pub struct ConnectionEntry {
+    connections: Arc<Connections>,
+    id: u64,
+    since: Instant,
+    /// Broadcast messages delivered so far, batched ones counted singly
+    pub sent: u64,
+}
+
+impl ConnectionEntry {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for ConnectionEntry {
+    fn drop(&mut self) {
+        self.connections.open.lock().unwrap().remove(&self.id);
+        log!(
+            "ws #{} disconnected after {:.1}s, {} messages sent",
+            self.id,
+            self.since.elapsed().as_secs_f64(),
+            self.sent
+        );
+    }
+}
+
+/// Lists the open `/ws` connections, oldest first
+pub async fn connections_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let open: Vec<ConnectionInfo> = state.connections.open.lock().unwrap().values().cloned().collect();
+    Json(open)
+}
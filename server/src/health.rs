@@ -0,0 +1,71 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
+
+use crate::AppState;
+
+/// Readiness of the data streams, reported on `/readyz`
+#[derive(Debug)]
+pub struct Health {
+    first_message_sent: AtomicBool,
+    /// Live mode additionally needs an open Binance connection
+    live: bool,
+    upstream_connections: AtomicU64,
+}
+
+impl Health {
+    pub fn new(live: bool) -> Self {
+        Self { first_message_sent: AtomicBool::new(false), live, upstream_connections: AtomicU64::new(0) }
+    }
+
+    /// Called by the stream tasks after broadcasting a data message
+    pub fn message_sent(&self) {
+        if !self.first_message_sent.load(Ordering::Relaxed) {
+            self.first_message_sent.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts one open upstream connection until the guard is dropped
+    pub fn upstream_connected(self: &Arc<Self>) -> UpstreamGuard {
+        self.upstream_connections.fetch_add(1, Ordering::Relaxed);
+        UpstreamGuard(self.clone())
+    }
+
+    fn not_ready_reason(&self) -> Option<&'static str> {
+        if self.live && self.upstream_connections.load(Ordering::Relaxed) == 0 {
+            return Some("no upstream connection to Binance");
+        }
+        if !self.first_message_sent.load(Ordering::Relaxed) {
+            return Some("no data stream has produced a message yet");
+        }
+        None
+    }
+}
+
+pub struct UpstreamGuard(Arc<Health>);
+
+impl Drop for UpstreamGuard {
+    fn drop(&mut self) {
+        self.0.upstream_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Liveness probe: answering at all means the HTTP server is up
+pub async fn healthz() -> impl IntoResponse {
+    (StatusCode::OK, Json(serde_json::json!({ "status": "ok" })))
+}
+
+/// Readiness probe: 200 once data is flowing, 503 with the reason otherwise
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    match state.health.not_ready_reason() {
+        None => (StatusCode::OK, Json(serde_json::json!({ "status": "ready" }))),
+        Some(reason) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "status": "not_ready", "reason": reason })),
+        ),
+    }
+}
@@ -2,66 +2,217 @@ use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use futures_util::{SinkExt, StreamExt};
 use tokio::sync::broadcast;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
 use std::time::Duration;
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use rand::Rng;
+use app::WireMessage;
+use leptos_rust::error_code::ErrorCode;
 
+use crate::circuit_breaker::CircuitBreaker;
+use crate::health::Health;
+use crate::metrics::{Metrics, RateMeter};
+use crate::stats::{self, SymbolStats};
+use crate::symbols::{ConnectedSymbols, SymbolSpec};
+use crate::trade_summary::TradeAggregator;
+
+/// Quote assets split off by `normalize_symbol`; longer suffixes first so
+/// USDT wins over USD
+const QUOTE_ASSETS: [&str; 6] = ["USDT", "USDC", "BUSD", "USD", "BTC", "ETH"];
+/// Markets that keep the names of the simulated symbols
+const SYMBOL_OVERRIDES: [(&str, &str); 3] = [
+    ("BTCUSDT", "BTC/USD"),
+    ("ETHUSDT", "ETH/USD"),
+    ("SOLUSDT", "SOL/USD"),
+];
+
+/// Most markets streamed at once, those of the start included; each one
+/// holds two upstream connections
+pub const MAX_LIVE_SYMBOLS: usize = 20;
+
+/// Ticker and depth tasks of one streamed market
 #[derive(Debug)]
+struct LiveStream {
+    spec: SymbolSpec,
+    tasks: [JoinHandle<()>; 2],
+}
+
+/// Markets streamed right now, in the order they were added
+#[derive(Clone, Debug, Default)]
+pub struct LiveStreams(Arc<Mutex<Vec<LiveStream>>>);
+
+impl LiveStreams {
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.lock().unwrap().iter().any(|s| s.spec.name == name)
+    }
+
+    pub fn specs(&self) -> Vec<SymbolSpec> {
+        self.0.lock().unwrap().iter().map(|s| s.spec.clone()).collect()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0.lock().unwrap().iter().map(|s| s.spec.name.clone()).collect()
+    }
+}
+
+/// Stops the keep-alive pinger of a connection together with its reader,
+/// also when the reader's task is aborted
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct LiveDataClient {
     tx: broadcast::Sender<String>,
+    shutdown: Arc<AtomicBool>, // stops all streams once set
+    health: Arc<Health>,
+    stats: SymbolStats, // updated from the ticker streams
+    connected: ConnectedSymbols, // markets whose ticker stream is up
+    symbols: Vec<SymbolSpec>, // markets streamed from the start
+    pub streams: LiveStreams, // markets streamed now, see `subscribe`/`unsubscribe`
 }
 
 impl LiveDataClient {
-    pub fn new(tx: broadcast::Sender<String>) -> Self {
-        Self { tx }
+    pub fn new(
+        tx: broadcast::Sender<String>,
+        shutdown: Arc<AtomicBool>,
+        health: Arc<Health>,
+        stats: SymbolStats,
+        connected: ConnectedSymbols,
+        symbols: Vec<SymbolSpec>,
+    ) -> Self {
+        Self { tx, shutdown, health, stats, connected, symbols, streams: LiveStreams::default() }
     }
 
     /// Start live data streams from Binance WebSocket
     pub async fn start_binance_streams(&self) {
-        let symbols = vec!["btcusdt", "ethusdt", "solusdt"];
-        
-        for symbol in symbols {
-            let tx = self.tx.clone();
-            let symbol_clone = symbol.to_string();
-            
-            // Start price ticker stream
-            tokio::spawn(async move {
-                Self::binance_ticker_stream(&symbol_clone, tx).await;
-            });
-            
+        for symbol in &self.symbols {
+            let stream = self.spawn_streams(symbol.clone());
+            self.streams.0.lock().unwrap().push(stream);
             // Add small delay between connections
             sleep(Duration::from_millis(100)).await;
         }
         
         // Start a combined trade stream for all symbols
         let tx_trades = self.tx.clone();
+        let shutdown_trades = self.shutdown.clone();
+        let health_trades = self.health.clone();
+        let live_trades = self.streams.clone();
+        let streams: Vec<String> = self.symbols.iter().map(|s| format!("{}@trade", s.stream)).collect();
+        let trade_url = format!("wss://stream.binance.com:9443/stream?streams={}", streams.join("/"));
         tokio::spawn(async move {
-            Self::binance_trade_streams(tx_trades).await;
+            Self::binance_trade_streams(&trade_url, tx_trades, shutdown_trades, health_trades, live_trades).await;
+        });
+    }
+
+    /// Spawns the price ticker and order book depth streams of `symbol`
+    fn spawn_streams(&self, symbol: SymbolSpec) -> LiveStream {
+        let tx = self.tx.clone();
+        let symbol_clone = symbol.clone();
+        let shutdown = self.shutdown.clone();
+        let health = self.health.clone();
+        let stats = self.stats.clone();
+        let connected = self.connected.clone();
+        let ticker = tokio::spawn(async move {
+            Self::binance_ticker_stream(&symbol_clone, tx, shutdown, health, stats, connected).await;
         });
+
+        let tx_depth = self.tx.clone();
+        let symbol_depth = symbol.stream.clone();
+        let shutdown_depth = self.shutdown.clone();
+        let health_depth = self.health.clone();
+        let depth = tokio::spawn(async move {
+            Self::binance_depth_stream(&symbol_depth, tx_depth, shutdown_depth, health_depth).await;
+        });
+        LiveStream { spec: symbol, tasks: [ticker, depth] }
+    }
+
+    /// Starts streaming the ticker and depth of another market. Its trades
+    /// aren't streamed, the combined trade stream covers the markets of the
+    /// start only. Fails with `Conflict` if it is streamed already and with
+    /// `ResourceLimit` at [`MAX_LIVE_SYMBOLS`] markets.
+    pub fn subscribe(&self, symbol: SymbolSpec) -> Result<(), (ErrorCode, String)> {
+        let mut streams = self.streams.0.lock().unwrap();
+        if streams.iter().any(|s| s.spec.name == symbol.name) {
+            return Err((ErrorCode::Conflict, format!("{} is already streamed", symbol.name)));
+        }
+        if streams.len() >= MAX_LIVE_SYMBOLS {
+            return Err((ErrorCode::ResourceLimit, format!("at most {} markets can be streamed", MAX_LIVE_SYMBOLS)));
+        }
+        println!("➕ Streaming {} from now on", symbol.name);
+        streams.push(self.spawn_streams(symbol));
+        Ok(())
     }
 
-    /// Binance ticker stream for price updates (24hr rolling window stats)
-    async fn binance_ticker_stream(symbol: &str, tx: broadcast::Sender<String>) {
-        loop {
-            match Self::connect_ticker_stream(symbol, &tx).await {
+    /// Stops the streams of the market `name`; its trades are dropped from
+    /// then on.
+    pub fn unsubscribe(&self, name: &str) -> Result<(), String> {
+        let mut streams = self.streams.0.lock().unwrap();
+        let Some(index) = streams.iter().position(|s| s.spec.name == name) else {
+            return Err(format!("{} is not streamed", name));
+        };
+        let stream = streams.remove(index);
+        // Dropping the aborted tasks also drops their connections and guards
+        for task in &stream.tasks {
+            task.abort();
+        }
+        println!("➖ Stopped streaming {}", name);
+        Ok(())
+    }
+
+    /// Binance ticker stream for price updates (24hr rolling window stats).
+    /// The symbol counts as connected while its ticker stream is. Failed
+    /// connections back off through a [`CircuitBreaker`], which reports the
+    /// symbol as degraded to clients while open.
+    async fn binance_ticker_stream(
+        symbol: &SymbolSpec,
+        tx: broadcast::Sender<String>,
+        shutdown: Arc<AtomicBool>,
+        health: Arc<Health>,
+        stats: SymbolStats,
+        connected: ConnectedSymbols,
+    ) {
+        let mut breaker = CircuitBreaker::default();
+        while !shutdown.load(Ordering::Relaxed) {
+            match Self::connect_ticker_stream(symbol, &tx, &shutdown, &health, &stats, &connected, &mut breaker).await {
                 Ok(_) => {},
                 Err(error_msg) => {
-                    eprintln!("Ticker stream error for {}: {}", symbol, error_msg);
-                    sleep(Duration::from_secs(5)).await; // Reconnect delay
+                    let delay = breaker.failure();
+                    eprintln!("Ticker stream error for {}: {} (retrying in {}s)", symbol.stream, error_msg, delay.as_secs());
+                    if breaker.is_open() {
+                        Self::send_upstream_status(&tx, &symbol.name, "degraded");
+                    }
+                    sleep(delay).await;
                 }
             }
         }
     }
 
-    async fn connect_ticker_stream(symbol: &str, tx: &broadcast::Sender<String>) -> Result<(), String> {
-        let url = format!("wss://stream.binance.com:9443/ws/{}@ticker", symbol);
+    async fn connect_ticker_stream(
+        symbol: &SymbolSpec,
+        tx: &broadcast::Sender<String>,
+        shutdown: &AtomicBool,
+        health: &Arc<Health>,
+        stats: &SymbolStats,
+        connected: &ConnectedSymbols,
+        breaker: &mut CircuitBreaker,
+    ) -> Result<(), String> {
+        let url = format!("wss://stream.binance.com:9443/ws/{}@ticker", symbol.stream);
         println!("Connecting to Binance ticker stream: {}", url);
         
         let (ws_stream, _) = connect_async(&url).await.map_err(|e| e.to_string())?;
+        let _upstream = health.upstream_connected();
+        let _connected = connected.connected(&symbol.name);
         let (mut write, mut read) = ws_stream.split();
         
         // Keep connection alive with pings
-        tokio::spawn(async move {
+        let _pinger = AbortOnDrop(tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(30));
             loop {
                 interval.tick().await;
@@ -69,15 +220,23 @@ impl LiveDataClient {
                     break;
                 }
             }
-        });
+        }));
 
         while let Some(msg) = read.next().await {
+            if shutdown.load(Ordering::Relaxed) {
+                break;
+            }
             match msg {
                 Ok(Message::Text(text)) => {
                     if let Ok(data) = serde_json::from_str::<Value>(&text) {
                         // Transform Binance data to our format
-                        if let Some(transformed) = Self::transform_binance_ticker(&data) {
+                        if let Some(transformed) = Self::transform_binance_ticker(&data, stats) {
                             let _ = tx.send(transformed);
+                            health.message_sent();
+                            // Only a ticker coming through counts as recovered
+                            if breaker.success() {
+                                Self::send_upstream_status(tx, &symbol.name, "ok");
+                            }
                         }
                     }
                 }
@@ -94,10 +253,83 @@ impl LiveDataClient {
         Ok(())
     }
 
-    /// Combined trade streams for multiple symbols
-    async fn binance_trade_streams(tx: broadcast::Sender<String>) {
-        loop {
-            match Self::connect_trade_streams(&tx).await {
+    fn send_upstream_status(tx: &broadcast::Sender<String>, symbol: &str, status: &str) {
+        let msg = WireMessage::Upstream { status: status.into(), symbol: symbol.into() };
+        if let Ok(s) = serde_json::to_string(&msg) {
+            let _ = tx.send(s);
+        }
+    }
+
+    /// Binance partial book depth stream (top 20 levels every 100ms)
+    async fn binance_depth_stream(symbol: &str, tx: broadcast::Sender<String>, shutdown: Arc<AtomicBool>, health: Arc<Health>) {
+        while !shutdown.load(Ordering::Relaxed) {
+            match Self::connect_depth_stream(symbol, &tx, &shutdown, &health).await {
+                Ok(_) => {},
+                Err(error_msg) => {
+                    eprintln!("Depth stream error for {}: {}", symbol, error_msg);
+                    sleep(Duration::from_secs(5)).await; // Reconnect delay
+                }
+            }
+        }
+    }
+
+    async fn connect_depth_stream(symbol: &str, tx: &broadcast::Sender<String>, shutdown: &AtomicBool, health: &Arc<Health>) -> Result<(), String> {
+        let url = format!("wss://stream.binance.com:9443/ws/{}@depth20@100ms", symbol);
+        println!("Connecting to Binance depth stream: {}", url);
+
+        let (ws_stream, _) = connect_async(&url).await.map_err(|e| e.to_string())?;
+        let _upstream = health.upstream_connected();
+        let (mut write, mut read) = ws_stream.split();
+
+        // Keep connection alive with pings
+        let _pinger = AbortOnDrop(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                if write.send(Message::Ping(vec![])).await.is_err() {
+                    break;
+                }
+            }
+        }));
+
+        while let Some(msg) = read.next().await {
+            if shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+            match msg {
+                Ok(Message::Text(text)) => {
+                    if let Ok(data) = serde_json::from_str::<Value>(&text) {
+                        if let Some(transformed) = Self::transform_binance_depth(symbol, &data) {
+                            let _ = tx.send(transformed);
+                            health.message_sent();
+                        }
+                    }
+                }
+                Ok(Message::Pong(_)) => {}
+                Ok(Message::Close(_)) | Err(_) => {
+                    return Err("Depth connection closed".into());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Combined trade streams for multiple symbols. A reconnect replays some
+    /// recent trades, so the last trade id per Binance symbol outlives the
+    /// connection and anything at or below it is dropped.
+    async fn binance_trade_streams(
+        url: &str,
+        tx: broadcast::Sender<String>,
+        shutdown: Arc<AtomicBool>,
+        health: Arc<Health>,
+        live: LiveStreams,
+    ) {
+        let mut last_trade_ids = HashMap::new();
+        let mut summaries = TradeAggregator::default();
+        while !shutdown.load(Ordering::Relaxed) {
+            match Self::connect_trade_streams(url, &tx, &shutdown, &health, &live, &mut last_trade_ids, &mut summaries).await {
                 Ok(_) => {},
                 Err(error_msg) => {
                     eprintln!("Trade streams error: {}", error_msg);
@@ -107,15 +339,23 @@ impl LiveDataClient {
         }
     }
 
-    async fn connect_trade_streams(tx: &broadcast::Sender<String>) -> Result<(), String> {
-        let url = "wss://stream.binance.com:9443/stream?streams=btcusdt@trade/ethusdt@trade/solusdt@trade";
+    async fn connect_trade_streams(
+        url: &str,
+        tx: &broadcast::Sender<String>,
+        shutdown: &AtomicBool,
+        health: &Arc<Health>,
+        live: &LiveStreams,
+        last_trade_ids: &mut HashMap<String, u64>,
+        summaries: &mut TradeAggregator,
+    ) -> Result<(), String> {
         println!("Connecting to Binance trade streams: {}", url);
         
         let (ws_stream, _) = connect_async(url).await.map_err(|e| e.to_string())?;
+        let _upstream = health.upstream_connected();
         let (mut write, mut read) = ws_stream.split();
         
         // Keep connection alive
-        tokio::spawn(async move {
+        let _pinger = AbortOnDrop(tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(30));
             loop {
                 interval.tick().await;
@@ -123,14 +363,28 @@ impl LiveDataClient {
                     break;
                 }
             }
-        });
+        }));
 
         while let Some(msg) = read.next().await {
+            if shutdown.load(Ordering::Relaxed) {
+                break;
+            }
             match msg {
                 Ok(Message::Text(text)) => {
                     if let Ok(data) = serde_json::from_str::<Value>(&text) {
-                        if let Some(transformed) = Self::transform_binance_trade(&data) {
-                            let _ = tx.send(transformed);
+                        // Unsubscribed markets stay in the combined stream
+                        let trade = Self::transform_binance_trade(&data, last_trade_ids)
+                            .filter(|t| matches!(t, WireMessage::Trade { symbol, .. } if live.contains(symbol)));
+                        if let Some(trade) = trade {
+                            if let Ok(s) = serde_json::to_string(&trade) {
+                                let _ = tx.send(s);
+                                health.message_sent();
+                            }
+                            if let WireMessage::Trade { symbol, price, size, side, ts, .. } = &trade {
+                                for summary in summaries.record(symbol, *price, *size, side == "buy", *ts) {
+                                    if let Ok(s) = serde_json::to_string(&summary) { let _ = tx.send(s); }
+                                }
+                            }
                         }
                     }
                 }
@@ -145,26 +399,25 @@ impl LiveDataClient {
         Ok(())
     }
 
-    /// Transform Binance ticker data to our price format
-    fn transform_binance_ticker(data: &Value) -> Option<String> {
-        let symbol = data.get("s")?.as_str()?;
+    /// Transform Binance ticker data to our price format, recording the tick
+    /// in `stats`
+    fn transform_binance_ticker(data: &Value, stats: &SymbolStats) -> Option<String> {
+        let symbol = Self::normalize_symbol(data.get("s")?.as_str()?);
         let price = data.get("c")?.as_str()?.parse::<f64>().ok()?;
         let volume = data.get("v")?.as_str()?.parse::<f64>().ok()? as u64;
+        let ts = chrono::Utc::now().timestamp_micros();
+        stats::record(stats, &symbol, price, ts);
         
         // Convert to our format
-        let transformed = serde_json::json!({
-            "type": "price",
-            "symbol": Self::normalize_symbol(symbol),
-            "price": price,
-            "volume": volume,
-            "ts": chrono::Utc::now().timestamp_micros()
-        });
+        let transformed = WireMessage::Price { symbol, price, volume, ts };
         
         serde_json::to_string(&transformed).ok()
     }
 
-    /// Transform Binance trade data to our trade format
-    fn transform_binance_trade(data: &Value) -> Option<String> {
+    /// Transform Binance trade data to our trade format. `None` as well for
+    /// duplicate and stale trades: ids at or below the last one seen for the
+    /// symbol in `last_trade_ids`, which is updated otherwise.
+    fn transform_binance_trade(data: &Value, last_trade_ids: &mut HashMap<String, u64>) -> Option<WireMessage> {
         // Handle combined stream format
         let trade_data = if let Some(stream_data) = data.get("data") {
             stream_data
@@ -173,48 +426,89 @@ impl LiveDataClient {
         };
         
         let symbol = trade_data.get("s")?.as_str()?;
+        let trade_id = trade_data.get("t")?.as_u64()?;
+        match last_trade_ids.get_mut(symbol) {
+            Some(last) if trade_id <= *last => return None,
+            Some(last) => *last = trade_id,
+            None => {
+                last_trade_ids.insert(symbol.to_string(), trade_id);
+            }
+        }
         let price = trade_data.get("p")?.as_str()?.parse::<f64>().ok()?;
         let size = trade_data.get("q")?.as_str()?.parse::<f64>().ok()?;
         let is_buyer_maker = trade_data.get("m")?.as_bool()?;
         
-        let transformed = serde_json::json!({
-            "type": "trade",
-            "symbol": Self::normalize_symbol(symbol),
-            "price": price,
-            "size": size,
-            "side": if is_buyer_maker { "sell" } else { "buy" },
-            "ts": chrono::Utc::now().timestamp_micros()
-        });
-        
+        Some(WireMessage::Trade {
+            symbol: Self::normalize_symbol(symbol),
+            price,
+            size,
+            side: if is_buyer_maker { "sell" } else { "buy" }.to_string(),
+            ts: chrono::Utc::now().timestamp_micros(),
+            trade_id: Some(trade_id),
+        })
+    }
+
+    /// Transform Binance partial depth data to our book format.
+    /// The partial depth payload carries no symbol, so it is passed in.
+    fn transform_binance_depth(symbol: &str, data: &Value) -> Option<String> {
+        // Levels arrive as [["price", "qty"], ...], best price first
+        let levels = |side: &str| -> Option<Vec<(f64, f64)>> {
+            data.get(side)?
+                .as_array()?
+                .iter()
+                .take(5) // frontend shows the top 5 levels
+                .map(|level| {
+                    let price = level.get(0)?.as_str()?.parse::<f64>().ok()?;
+                    let qty = level.get(1)?.as_str()?.parse::<f64>().ok()?;
+                    Some((price, qty))
+                })
+                .collect()
+        };
+        let bids = levels("bids")?;
+        let asks = levels("asks")?;
+
+        let transformed = WireMessage::Book {
+            symbol: Self::normalize_symbol(symbol),
+            bids,
+            asks,
+            ts: chrono::Utc::now().timestamp_micros(),
+        };
+
         serde_json::to_string(&transformed).ok()
     }
 
-    /// Normalize symbol names (BTCUSDT -> BTC/USD)
-    fn normalize_symbol(binance_symbol: &str) -> String {
-        match binance_symbol.to_uppercase().as_str() {
-            "BTCUSDT" => "BTC/USD".to_string(),
-            "ETHUSDT" => "ETH/USD".to_string(),
-            "SOLUSDT" => "SOL/USD".to_string(),
-            _ => binance_symbol.to_uppercase()
+    /// Normalize symbol names: overrides first (BTCUSDT -> BTC/USD, matching
+    /// the simulated symbols), otherwise split off a known quote asset
+    /// (ADAUSDT -> ADA/USDT). Unknown quotes are just uppercased.
+    pub fn normalize_symbol(binance_symbol: &str) -> String {
+        let upper = binance_symbol.to_uppercase();
+        if let Some((_, name)) = SYMBOL_OVERRIDES.iter().find(|(raw, _)| *raw == upper) {
+            return name.to_string();
         }
+        QUOTE_ASSETS
+            .iter()
+            .find_map(|quote| {
+                let base = upper.strip_suffix(quote)?;
+                (!base.is_empty()).then(|| format!("{}/{}", base, quote))
+            })
+            .unwrap_or(upper)
     }
 }
 
-/// System metrics generator (still simulated for now)  
-pub fn start_system_metrics_stream(tx: broadcast::Sender<String>) {
+/// System metrics generator. CPU and memory are still simulated, `msg_rate`
+/// is the real broadcast throughput.
+pub fn start_system_metrics_stream(tx: broadcast::Sender<String>, metrics: Arc<Metrics>, shutdown: Arc<AtomicBool>) {
     tokio::spawn(async move {
-        let mut msg_count = 0u64;
-        loop {
+        let mut rate = RateMeter::new(&metrics);
+        while !shutdown.load(Ordering::Relaxed) {
             {
                 let mut rng = rand::thread_rng();
-                msg_count += rng.gen_range(50..200);
-                let metric = serde_json::json!({
-                    "type": "system",
-                    "cpu_pct": rng.gen_range(10.0..80.0),
-                    "mem_mb": rng.gen_range(500..2000),
-                    "msg_rate": msg_count,
-                    "ts": chrono::Utc::now().timestamp_micros()
-                });
+                let metric = WireMessage::System {
+                    cpu_pct: rng.gen_range(10.0..80.0),
+                    mem_mb: rng.gen_range(500..2000),
+                    msg_rate: rate.per_second(&metrics),
+                    ts: chrono::Utc::now().timestamp_micros(),
+                };
                 if let Ok(s) = serde_json::to_string(&metric) { 
                     let _ = tx.send(s); 
                 }
@@ -222,4 +516,62 @@ pub fn start_system_metrics_stream(tx: broadcast::Sender<String>) {
             sleep(Duration::from_secs(1)).await;
         }
     });
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_keep_the_simulated_names() {
+        assert_eq!(LiveDataClient::normalize_symbol("BTCUSDT"), "BTC/USD");
+        assert_eq!(LiveDataClient::normalize_symbol("ethusdt"), "ETH/USD");
+        assert_eq!(LiveDataClient::normalize_symbol("SOLUSDT"), "SOL/USD");
+    }
+
+    #[test]
+    fn known_quote_assets_are_split_off() {
+        assert_eq!(LiveDataClient::normalize_symbol("ADAUSDT"), "ADA/USDT");
+        assert_eq!(LiveDataClient::normalize_symbol("adausdc"), "ADA/USDC");
+        assert_eq!(LiveDataClient::normalize_symbol("DOGEBUSD"), "DOGE/BUSD");
+        assert_eq!(LiveDataClient::normalize_symbol("XRPUSD"), "XRP/USD");
+        assert_eq!(LiveDataClient::normalize_symbol("ETHBTC"), "ETH/BTC");
+        assert_eq!(LiveDataClient::normalize_symbol("LINKETH"), "LINK/ETH");
+    }
+
+    #[test]
+    fn unknown_quotes_are_uppercased() {
+        assert_eq!(LiveDataClient::normalize_symbol("btceur"), "BTCEUR");
+        // A bare quote asset has no base to split off
+        assert_eq!(LiveDataClient::normalize_symbol("usdt"), "USDT");
+        assert_eq!(LiveDataClient::normalize_symbol(""), "");
+    }
+
+    fn spec(name: &str) -> SymbolSpec {
+        SymbolSpec { name: name.into(), stream: name.to_lowercase(), base_price: 1.0 }
+    }
+
+    #[tokio::test]
+    async fn subscribe_rejects_duplicates_and_too_many_markets() {
+        // Shut down from the start, so the spawned streams end without connecting
+        let (tx, _) = broadcast::channel(16);
+        let client = LiveDataClient::new(
+            tx,
+            Arc::new(AtomicBool::new(true)),
+            Arc::new(Health::new(true)),
+            SymbolStats::default(),
+            ConnectedSymbols::default(),
+            Vec::new(),
+        );
+        for i in 0..MAX_LIVE_SYMBOLS {
+            client.subscribe(spec(&format!("S{}", i))).unwrap();
+        }
+        assert_eq!(client.subscribe(spec("S0")).unwrap_err().0, ErrorCode::Conflict);
+        assert_eq!(client.subscribe(spec("MORE")).unwrap_err().0, ErrorCode::ResourceLimit);
+        assert_eq!(client.streams.names().len(), MAX_LIVE_SYMBOLS);
+
+        // Unsubscribing frees a slot
+        client.unsubscribe("S0").unwrap();
+        client.subscribe(spec("MORE")).unwrap();
+    }
+}
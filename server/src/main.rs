@@ -1,8 +1,9 @@
 use axum::{
-    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State},
-    response::IntoResponse,
-    routing::get,
-    Router,
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, ConnectInfo, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
 };
 use leptos::prelude::*;
 use leptos_axum::{generate_route_list, LeptosRoutes};
@@ -11,17 +12,158 @@ use leptos::logging::log;
 use tokio::sync::broadcast;
 use rand::Rng;
 use std::time::Duration;
-use serde::Serialize;
-use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, AtomicU64, Ordering}};
+use leptos_rust::error_code::ErrorCode;
+use leptos_rust::multi_user_types::SystemStats;
 
+mod burst;
+mod can_sim;
+mod circuit_breaker;
+mod connections;
+mod health;
 mod live_data;
+mod measurement_sim;
+mod metrics;
+mod multi_user;
+mod rate_limit;
+mod replay;
+mod seed;
+mod stats;
+mod symbols;
+mod trade_summary;
+mod wire_format;
+
+/// Random walk parameters of one simulated symbol
+#[derive(Clone, Debug)]
+struct SymbolParams {
+    volatility: f64, // max relative change per tick
+    price: f64,      // current walk price; setting it restarts the walk there
+}
+
+type SymbolParamMap = Arc<Mutex<HashMap<String, SymbolParams>>>;
+
+/// Names from `app::STREAMS` whose simulated stream is currently sending
+type EnabledStreams = Arc<Mutex<HashSet<String>>>;
+
+fn stream_enabled(streams: &EnabledStreams, name: &str) -> bool {
+    streams.lock().unwrap().contains(name)
+}
+
+/// `streams` message announcing the enabled streams, in `STREAMS` order
+fn streams_message(streams: &EnabledStreams) -> WireMessage {
+    let enabled = streams.lock().unwrap();
+    WireMessage::Streams {
+        enabled: STREAMS.iter().filter(|s| enabled.contains(**s)).map(|s| s.to_string()).collect(),
+    }
+}
+
+/// Error reply of the REST endpoints: `{"msg": .., "code": ..}` with the
+/// number of `code`, the fields of the `error` message on `/ws`
+fn error_response(status: StatusCode, code: ErrorCode, msg: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({ "msg": msg.into(), "code": code.code() }))).into_response()
+}
+
+/// PEM files for serving HTTPS/WSS without a reverse proxy
+struct TlsPaths {
+    cert: String,
+    key: String,
+}
+
+/// Reads `TLS_CERT`/`TLS_KEY`. Both unset means plain HTTP; setting only one
+/// of them is a configuration error.
+fn tls_paths() -> Result<Option<TlsPaths>, String> {
+    let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+    match (var("TLS_CERT"), var("TLS_KEY")) {
+        (Some(cert), Some(key)) => Ok(Some(TlsPaths { cert, key })),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err("TLS_CERT is set but TLS_KEY is not; set both to enable HTTPS or neither for HTTP".into()),
+        (None, Some(_)) => Err("TLS_KEY is set but TLS_CERT is not; set both to enable HTTPS or neither for HTTP".into()),
+    }
+}
+
+/// Where the broadcast messages come from, chosen once at startup
+#[derive(Clone, Debug)]
+enum DataSource {
+    Simulated,
+    Live, // Binance WebSocket streams
+    Replay { path: std::path::PathBuf }, // recorded NDJSON message log
+}
 
 #[derive(Clone)]
 struct AppState {
     leptos_options: LeptosOptions,
     tx: broadcast::Sender<String>,
-    sleep_ms: Arc<AtomicU64>, // controls update frequency for all streams
-    use_live_data: bool,      // toggle between simulated and real data
+    // Update intervals of the simulated price, book and trade streams
+    price_ms: Arc<AtomicU64>,
+    book_ms: Arc<AtomicU64>,
+    trade_ms: Arc<AtomicU64>,
+    candle_ms: Arc<AtomicU64>, // OHLC candle interval of the simulated price stream
+    symbol_params: SymbolParamMap, // per-symbol volatility/price of the simulated stream
+    enabled_streams: EnabledStreams, // simulated streams that aren't paused
+    symbols: Arc<Vec<String>>, // announced in `hello`; empty when replaying
+    symbol_specs: Arc<Vec<symbols::SymbolSpec>>, // configured markets, listed on /symbols
+    connected_symbols: symbols::ConnectedSymbols, // live mode: markets with a connected ticker stream
+    users: Arc<multi_user::UserTable>,
+    sessions: multi_user::SessionMap, // multi-user sessions by session_id
+    measurements: multi_user::MeasurementMap,
+    metrics: Arc<metrics::Metrics>,
+    connections: Arc<connections::Connections>, // open /ws connections, listed on /stats/connections
+    health: Arc<health::Health>,
+    symbol_stats: stats::SymbolStats, // lifetime price tick statistics, served on /stats
+    mu_stats: broadcast::Sender<SystemStats>, // multi-user system stats, once per second
+    data_source: DataSource,
+    replay_speed: replay::ReplaySpeed,
+    seed: Option<u64>, // `SEED`: simulated data repeats run to run
+    live: Option<live_data::LiveDataClient>, // live mode: the Binance streams, changed on /live/subscribe
+    burst: Arc<burst::BurstSlot>, // taken while a `burst` runs
+}
+
+impl AppState {
+    /// Symbols announced in `hello` and accepted in `subscribe`; in live
+    /// mode the markets streamed right now
+    fn symbol_names(&self) -> Vec<String> {
+        match &self.live {
+            Some(live) => live.streams.names(),
+            None => self.symbols.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl AppState {
+    /// Simulated mode with a single `demo:demo` account and no running streams
+    fn for_tests() -> Self {
+        let (tx, _) = broadcast::channel(16);
+        let (mu_stats, _) = broadcast::channel(4);
+        Self {
+            leptos_options: LeptosOptions::builder().output_name("test").build(),
+            tx,
+            price_ms: Arc::new(AtomicU64::new(50)),
+            book_ms: Arc::new(AtomicU64::new(100)),
+            trade_ms: Arc::new(AtomicU64::new(150)),
+            candle_ms: Arc::new(AtomicU64::new(1000)),
+            symbol_params: Default::default(),
+            enabled_streams: Default::default(),
+            symbols: Arc::new(vec!["BTC/USD".into()]),
+            symbol_specs: Default::default(),
+            connected_symbols: Default::default(),
+            users: Arc::new(multi_user::UserTable::from_spec("demo:demo")),
+            sessions: Default::default(),
+            measurements: Default::default(),
+            metrics: Default::default(),
+            connections: Default::default(),
+            health: Arc::new(health::Health::new(false)),
+            symbol_stats: Default::default(),
+            mu_stats,
+            data_source: DataSource::Simulated,
+            replay_speed: replay::ReplaySpeed::new(1.0),
+            seed: Some(1),
+            live: None,
+            burst: Default::default(),
+        }
+    }
 }
 
 #[tokio::main]
@@ -30,88 +172,170 @@ async fn main() {
     let conf = get_configuration(None).unwrap();
     let addr = conf.leptos_options.site_addr;
     let leptos_options = conf.leptos_options;
+    let tls = tls_paths().unwrap_or_else(|e| {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    });
     // broadcast channel for live updates (high capacity for bursts)
     let (tx, _rx) = broadcast::channel::<String>(500);
 
-    // tunable: message frequency in milliseconds (lower = faster updates)
-    let sleep_ms = Arc::new(AtomicU64::new(50)); // default 50ms = ~20 Hz
+    // tunable: message interval per stream in milliseconds (lower = faster updates)
+    let price_ms = Arc::new(AtomicU64::new(50)); // default 50ms = ~20 Hz
+    let book_ms = Arc::new(AtomicU64::new(100));
+    let trade_ms = Arc::new(AtomicU64::new(150));
+    let candle_ms = Arc::new(AtomicU64::new(1000)); // default 1s candles
+    // all simulated streams send until paused from the control panel
+    let enabled_streams: EnabledStreams = Arc::new(Mutex::new(STREAMS.iter().map(|s| s.to_string()).collect()));
+    // set on Ctrl-C, stops all stream producers
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    let metrics = Arc::new(metrics::Metrics::default());
+    metrics::spawn_counter(&tx, metrics.clone());
 
     // Check if we should use live data (environment variable or command line arg)
     let use_live_data = std::env::var("USE_LIVE_DATA").unwrap_or_default() == "true" ||
                         std::env::args().any(|arg| arg == "--live-data");
+    // A replay file takes precedence over live and simulated data
+    let data_source = match std::env::var("REPLAY_FILE") {
+        Ok(path) if !path.is_empty() => DataSource::Replay { path: path.into() },
+        _ if use_live_data => DataSource::Live,
+        _ => DataSource::Simulated,
+    };
+    let replay_speed = replay::ReplaySpeed::new(1.0);
+    let symbol_specs = symbols::from_env(matches!(data_source, DataSource::Live));
+    let symbol_names: Vec<String> = symbol_specs.iter().map(|s| s.name.clone()).collect();
+    let symbol_params: SymbolParamMap = Arc::new(Mutex::new(
+        symbol_specs
+            .iter()
+            .map(|s| (s.name.clone(), SymbolParams { volatility: 0.002, price: s.base_price }))
+            .collect(),
+    ));
+    let health = Arc::new(health::Health::new(matches!(data_source, DataSource::Live)));
+    let symbol_stats = stats::SymbolStats::default();
+    let connected_symbols = symbols::ConnectedSymbols::default();
+    // Same seed, same data values; the timing still follows the scheduler
+    let seed = seed::from_env();
+    let mut live = None;
 
-    if use_live_data {
+    match &data_source {
+    DataSource::Live => {
         println!("🔥 Starting LIVE data streams from Binance WebSocket...");
-        let live_client = live_data::LiveDataClient::new(tx.clone());
+        let live_client = live_data::LiveDataClient::new(
+            tx.clone(),
+            shutdown.clone(),
+            health.clone(),
+            symbol_stats.clone(),
+            connected_symbols.clone(),
+            symbol_specs.clone(),
+        );
         live_client.start_binance_streams().await;
+        live = Some(live_client);
         
         // Still use simulated system metrics
-        live_data::start_system_metrics_stream(tx.clone());
+        live_data::start_system_metrics_stream(tx.clone(), metrics.clone(), shutdown.clone());
         
         println!("✅ Live data streams started! Connect to ws://127.0.0.1:3000/ws");
-    } else {
-        println!("🤖 Starting SIMULATED data streams (use_live_data={})...", use_live_data);
+    }
+    DataSource::Replay { path } => {
+        println!("⏯️ Replaying recorded messages from {}", path.display());
+        replay::start_replay(path.clone(), tx.clone(), replay_speed.clone(), shutdown.clone(), health.clone());
+    }
+    DataSource::Simulated => {
+        println!("🤖 Starting SIMULATED data streams for {}...", symbol_names.join(", "));
+        if let Some(seed) = seed {
+            println!("🎲 Seeded with SEED={}, the simulated values repeat run to run", seed);
+        }
         
         // ========== Realistic high-frequency simulated data streams ==========
         // We'll simulate 3 concurrent streams that fire at high rates to stress the frontend
 
         // Stream 1: Market price ticks (20-60 Hz) - simulates real-time price updates
-    #[derive(Serialize)]
-    struct PriceTick {
-        #[serde(rename = "type")] t: &'static str,
-        symbol: String,
-        price: f64,
-        volume: u64,
-        ts: i64, // micros since epoch
-    }
-
+    // OHLC candles are aggregated from the price ticks and emitted when a bucket rolls over
     let tx_price = tx.clone();
-    let freq_ms = sleep_ms.clone(); // use sleep_ms as update frequency control
+    let freq_ms = price_ms.clone();
+    let candle_len = candle_ms.clone();
+    let stop_price = shutdown.clone();
+    let health_price = health.clone();
+    let params = symbol_params.clone();
+    let price_symbols = symbol_names.clone();
+    let price_stats = symbol_stats.clone();
+    let price_streams = enabled_streams.clone();
+    let price_metrics = metrics.clone();
     tokio::spawn(async move {
-        let symbols = vec!["BTC/USD", "ETH/USD", "SOL/USD", "AAPL", "TSLA"];
-        let mut prices = vec![45000.0, 2500.0, 120.0, 175.0, 250.0];
-        loop {
-            {
-                let mut rng = rand::thread_rng();
-                for (idx, symbol) in symbols.iter().enumerate() {
+        let mut rng = seed::stream_rng(seed, "price");
+        let mut cadence = metrics::CadenceWatch::from_env();
+        // (bucket index, candle in progress) per symbol
+        let mut candles: Vec<Option<(i64, WireMessage)>> = price_symbols.iter().map(|_| None).collect();
+        while !stop_price.load(Ordering::Relaxed) {
+            if stream_enabled(&price_streams, "price") {
+                let mut params = params.lock().unwrap();
+                for (idx, symbol) in price_symbols.iter().enumerate() {
+                    let Some(p) = params.get_mut(symbol) else { continue };
                     // Random walk with volatility
-                    let change = rng.gen_range(-0.002..0.002);
-                    prices[idx] *= 1.0 + change;
+                    if p.volatility > 0.0 {
+                        p.price *= 1.0 + rng.gen_range(-p.volatility..p.volatility);
+                    }
                     let volume = rng.gen_range(100..10000);
-                    let tick = PriceTick {
-                        t: "price",
-                        symbol: symbol.to_string(),
-                        price: (prices[idx] * 100.0_f64).round() / 100.0,
-                        volume,
-                        ts: chrono::Utc::now().timestamp_micros(),
-                    };
-                    if let Ok(s) = serde_json::to_string(&tick) { let _ = tx_price.send(s); }
+                    let price = (p.price * 100.0_f64).round() / 100.0;
+                    let ts = chrono::Utc::now().timestamp_micros();
+                    stats::record(&price_stats, symbol, price, ts);
+                    let tick = WireMessage::Price { symbol: symbol.clone(), price, volume, ts };
+                    if let Ok(s) = serde_json::to_string(&tick) {
+                        let _ = tx_price.send(s);
+                        health_price.message_sent();
+                    }
+
+                    let bucket_us = candle_len.load(Ordering::Relaxed) as i64 * 1000;
+                    let bucket = ts / bucket_us;
+                    let slot = &mut candles[idx];
+                    match slot {
+                        Some((b, WireMessage::Candle { high, low, close, volume: v, .. })) if *b == bucket => {
+                            *high = high.max(price);
+                            *low = low.min(price);
+                            *close = price;
+                            *v += volume;
+                        }
+                        _ => {
+                            if let Some((_, done)) = slot.take() {
+                                if let Ok(s) = serde_json::to_string(&done) { let _ = tx_price.send(s); }
+                            }
+                            *slot = Some((bucket, WireMessage::Candle {
+                                symbol: symbol.clone(),
+                                open: price,
+                                high: price,
+                                low: price,
+                                close: price,
+                                volume,
+                                ts: bucket * bucket_us,
+                            }));
+                        }
+                    }
                 }
-            } // rng dropped here
-            let interval = freq_ms.load(Ordering::Relaxed).max(10); // min 10ms = 100Hz
-            tokio::time::sleep(Duration::from_millis(interval)).await;
+            }
+            let interval = Duration::from_millis(freq_ms.load(Ordering::Relaxed).max(10)); // min 10ms = 100Hz
+            cadence.tick(interval, &price_metrics);
+            tokio::time::sleep(interval).await;
         }
     });
 
     // Stream 2: Order book depth updates (10-30 Hz)
-    #[derive(Serialize)]
-    struct BookUpdate {
-        #[serde(rename = "type")] t: &'static str,
-        symbol: String,
-        bids: Vec<(f64, f64)>, // price, size
-        asks: Vec<(f64, f64)>,
-        ts: i64,
-    }
-
     let tx_book = tx.clone();
-    let freq_book = sleep_ms.clone();
+    let freq_book = book_ms.clone();
+    let stop_book = shutdown.clone();
+    let health_book = health.clone();
+    let book_params = symbol_params.clone();
+    let book_symbols = symbol_names.clone();
+    let book_streams = enabled_streams.clone();
     tokio::spawn(async move {
-        let symbols = vec!["BTC/USD", "ETH/USD"];
-        loop {
-            {
-                let mut rng = rand::thread_rng();
-                for symbol in &symbols {
-                    let mid = if *symbol == "BTC/USD" { 45000.0 } else { 2500.0 };
+        let mut rng = seed::stream_rng(seed, "book");
+        while !stop_book.load(Ordering::Relaxed) {
+            if stream_enabled(&book_streams, "book") {
+                // Books are centered on the current walk price
+                let mids: Vec<(String, f64)> = {
+                    let params = book_params.lock().unwrap();
+                    book_symbols.iter().filter_map(|s| Some((s.clone(), params.get(s)?.price))).collect()
+                };
+                for (symbol, mid) in mids {
                     let mut bids = Vec::new();
                     let mut asks = Vec::new();
                     for i in 0..5 {
@@ -120,82 +344,80 @@ async fn main() {
                         bids.push((bid_price, rng.gen_range(0.1..10.0)));
                         asks.push((ask_price, rng.gen_range(0.1..10.0)));
                     }
-                    let update = BookUpdate {
-                        t: "book",
-                        symbol: symbol.to_string(),
+                    let update = WireMessage::Book {
+                        symbol,
                         bids,
                         asks,
                         ts: chrono::Utc::now().timestamp_micros(),
                     };
-                    if let Ok(s) = serde_json::to_string(&update) { let _ = tx_book.send(s); }
+                    if let Ok(s) = serde_json::to_string(&update) {
+                        let _ = tx_book.send(s);
+                        health_book.message_sent();
+                    }
                 }
             }
-            let interval = (freq_book.load(Ordering::Relaxed) * 2).max(50);
+            let interval = freq_book.load(Ordering::Relaxed);
             tokio::time::sleep(Duration::from_millis(interval)).await;
         }
     });
 
     // Stream 3: Trade executions (sporadic bursts, 5-20 Hz)
-    #[derive(Serialize)]
-    struct Trade {
-        #[serde(rename = "type")] t: &'static str,
-        symbol: String,
-        price: f64,
-        size: f64,
-        side: &'static str, // "buy" or "sell"
-        ts: i64,
-    }
-
     let tx_trade = tx.clone();
-    let freq_trade = sleep_ms.clone();
+    let freq_trade = trade_ms.clone();
+    let stop_trade = shutdown.clone();
+    let health_trade = health.clone();
+    let trade_params = symbol_params.clone();
+    let trade_symbols = symbol_names.clone();
+    let trade_streams = enabled_streams.clone();
     tokio::spawn(async move {
-        let symbols = vec!["BTC/USD", "ETH/USD", "SOL/USD"];
-        loop {
-            {
-                let mut rng = rand::thread_rng();
-                let symbol = symbols[rng.gen_range(0..symbols.len())];
-                let price = match symbol {
-                    "BTC/USD" => 45000.0 + rng.gen_range(-100.0..100.0),
-                    "ETH/USD" => 2500.0 + rng.gen_range(-10.0..10.0),
-                    _ => 120.0 + rng.gen_range(-1.0..1.0),
-                };
-                let trade = Trade {
-                    t: "trade",
-                    symbol: symbol.to_string(),
-                    price: (price * 100.0_f64).round() / 100.0,
-                    size: rng.gen_range(0.01..5.0),
-                    side: if rng.gen_bool(0.5) { "buy" } else { "sell" },
-                    ts: chrono::Utc::now().timestamp_micros(),
-                };
-                if let Ok(s) = serde_json::to_string(&trade) { let _ = tx_trade.send(s); }
+        let mut rng = seed::stream_rng(seed, "trade");
+        let mut summaries = trade_summary::TradeAggregator::default();
+        while !stop_trade.load(Ordering::Relaxed) {
+            if stream_enabled(&trade_streams, "trade") {
+                let symbol = &trade_symbols[rng.gen_range(0..trade_symbols.len())];
+                // Trades land within ±0.2% of the current walk price
+                let mid = trade_params.lock().unwrap().get(symbol).map(|p| p.price);
+                if let Some(mid) = mid {
+                    let price = (mid * (1.0 + rng.gen_range(-0.002..0.002)) * 100.0_f64).round() / 100.0;
+                    let size = rng.gen_range(0.01..5.0);
+                    let buy = rng.gen_bool(0.5);
+                    let ts = chrono::Utc::now().timestamp_micros();
+                    let trade = WireMessage::Trade {
+                        symbol: symbol.clone(),
+                        price,
+                        size,
+                        side: if buy { "buy" } else { "sell" }.to_string(),
+                        ts,
+                        trade_id: None,
+                    };
+                    if let Ok(s) = serde_json::to_string(&trade) {
+                        let _ = tx_trade.send(s);
+                        health_trade.message_sent();
+                    }
+                    for summary in summaries.record(symbol, price, size, buy, ts) {
+                        if let Ok(s) = serde_json::to_string(&summary) { let _ = tx_trade.send(s); }
+                    }
+                }
             }
-            let interval = (freq_trade.load(Ordering::Relaxed) * 3).max(50);
+            let interval = freq_trade.load(Ordering::Relaxed);
             tokio::time::sleep(Duration::from_millis(interval)).await;
         }
     });
 
     // Stream 4: System metrics (lower frequency but adds context)
-    #[derive(Serialize)]
-    struct SystemMetric {
-        #[serde(rename = "type")] t: &'static str,
-        cpu_pct: f64,
-        mem_mb: u64,
-        msg_rate: u64, // messages per second
-        ts: i64,
-    }
-
     let tx_sys = tx.clone();
+    let stop_sys = shutdown.clone();
+    let metrics_sys = metrics.clone();
+    let sys_streams = enabled_streams.clone();
     tokio::spawn(async move {
-        let mut msg_count = 0u64;
-        loop {
-            {
-                let mut rng = rand::thread_rng();
-                msg_count += rng.gen_range(50..200); // simulate msg throughput
-                let metric = SystemMetric {
-                    t: "system",
+        let mut rng = seed::stream_rng(seed, "system");
+        let mut rate = metrics::RateMeter::new(&metrics_sys);
+        while !stop_sys.load(Ordering::Relaxed) {
+            if stream_enabled(&sys_streams, "system") {
+                let metric = WireMessage::System {
                     cpu_pct: rng.gen_range(10.0..80.0),
                     mem_mb: rng.gen_range(500..2000),
-                    msg_rate: msg_count,
+                    msg_rate: rate.per_second(&metrics_sys),
                     ts: chrono::Utc::now().timestamp_micros(),
                 };
                 if let Ok(s) = serde_json::to_string(&metric) { let _ = tx_sys.send(s); }
@@ -206,18 +428,63 @@ async fn main() {
         
         println!("✅ Simulated data streams started!");
     }
+    }
+
+    // The CAN source runs next to whichever market data source is active
+    if let Some(can) = can_sim::CanSimConfig::from_env() {
+        println!("🚗 Starting simulated CAN source: {} bus(es) at {} bit/s", can.buses, can.bitrate);
+        can_sim::spawn(can, tx.clone(), shutdown.clone(), health.clone(), seed);
+    }
+
+    let users = Arc::new(multi_user::UserTable::from_env());
+    let sessions = multi_user::SessionMap::default();
+    let measurements = multi_user::MeasurementMap::default();
+    let (mu_stats, _) = broadcast::channel(4);
+    multi_user::spawn_stats(users.clone(), sessions.clone(), measurements.clone(), mu_stats.clone(), shutdown.clone());
 
     let state = AppState {
         leptos_options: leptos_options.clone(),
         tx,
-        sleep_ms,
-        use_live_data,
+        price_ms,
+        book_ms,
+        trade_ms,
+        candle_ms,
+        symbol_params,
+        enabled_streams,
+        symbols: Arc::new(if matches!(data_source, DataSource::Replay { .. }) { Vec::new() } else { symbol_names }),
+        symbol_specs: Arc::new(symbol_specs),
+        connected_symbols,
+        users,
+        sessions,
+        measurements,
+        metrics,
+        connections: Default::default(),
+        health,
+        symbol_stats,
+        mu_stats,
+        data_source,
+        replay_speed,
+        seed,
+        live,
+        burst: Default::default(),
     };
+    let metrics = state.metrics.clone();
+    let tx_shutdown = state.tx.clone();
     // Generate the list of routes in your Leptos App
     let routes = generate_route_list(App);
 
     let app = Router::new()
         .route("/ws", get(ws_handler))
+        .route("/mu", get(multi_user::mu_handler))
+        .route("/metrics", get(metrics::metrics_handler))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .route("/stats", get(stats::stats_handler))
+        .route("/stats/connections", get(connections::connections_handler))
+        .route("/history", get(stats::history_handler))
+        .route("/symbols", get(symbols::symbols_handler))
+        .route("/live/subscribe", post(symbols::live_subscribe_handler))
+        .route("/live/unsubscribe", post(symbols::live_unsubscribe_handler))
         .leptos_routes(&state, routes, {
             let leptos_options = state.leptos_options.clone();
             move || shell(leptos_options.clone())
@@ -225,13 +492,60 @@ async fn main() {
     .fallback(leptos_axum::file_and_error_handler::<AppState, _>(shell))
         .with_state(state);
 
-    // run our app with hyper
-    // `axum::Server` is a re-export of `hyper::Server`
-    log!("listening on http://{}", &addr);
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app.into_make_service())
-        .await
-        .unwrap();
+    match tls {
+        Some(TlsPaths { cert, key }) => {
+            let config = axum_server::tls_rustls::RustlsConfig::from_pem_file(&cert, &key)
+                .await
+                .unwrap_or_else(|e| {
+                    eprintln!("❌ failed to load TLS certificate {} / key {}: {}", cert, key, e);
+                    std::process::exit(1);
+                });
+            log!("listening on https://{} (TLS, cert {})", &addr, cert);
+            let handle = axum_server::Handle::new();
+            tokio::spawn({
+                let handle = handle.clone();
+                async move {
+                    shutdown_signal(shutdown, tx_shutdown).await;
+                    handle.graceful_shutdown(Some(Duration::from_secs(2)));
+                }
+            });
+            axum_server::bind_rustls(addr, config)
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap();
+        }
+        None => {
+            // run our app with hyper
+            log!("listening on http://{} (plain HTTP, set TLS_CERT and TLS_KEY for HTTPS)", &addr);
+            let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(shutdown_signal(shutdown, tx_shutdown))
+                .await
+                .unwrap();
+        }
+    }
+
+    // Give open /ws connections a moment to deliver the notice and close
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while metrics.open_connections() > 0 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    log!("shutdown complete");
+}
+
+/// Broadcast to every client right before the server stops; the serialized
+/// `WireMessage::Shutdown`, kept as a literal so connections can compare it
+/// without parsing every message
+const SHUTDOWN_MSG: &str = r#"{"type":"shutdown"}"#;
+
+/// Resolves on Ctrl-C after stopping the stream producers and announcing the
+/// shutdown; `ws_connection` closes its socket when it sees the notice.
+async fn shutdown_signal(shutdown: Arc<AtomicBool>, tx: broadcast::Sender<String>) {
+    let _ = tokio::signal::ctrl_c().await;
+    log!("shutting down");
+    shutdown.store(true, Ordering::Relaxed);
+    let _ = tx.send(SHUTDOWN_MSG.to_string());
 }
 
 // Allow leptos_axum to extract LeptosOptions from our composite AppState
@@ -242,35 +556,359 @@ impl axum::extract::FromRef<AppState> for LeptosOptions {
 }
 
 
-async fn ws_handler(State(state): State<AppState>, ws: WebSocketUpgrade) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| ws_connection(socket, state))
+#[derive(serde::Deserialize)]
+struct WsParams {
+    fmt: Option<String>, // "msgpack" for binary frames, JSON otherwise
+}
+
+async fn ws_handler(
+    State(state): State<AppState>,
+    ConnectInfo(remote_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<WsParams>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let format = wire_format::WireFormat::from_query(params.fmt.as_deref());
+    let connection = state.connections.open(remote_addr, &headers);
+    ws.on_upgrade(move |socket| ws_connection(socket, state, format, connection))
 }
 
 #[derive(serde::Deserialize)]
 struct ControlMsg {
-    frequency_ms: Option<u64>, // controls update rate for all streams
+    #[serde(rename = "type")]
+    kind: Option<String>, // "ping" asks for a pong echoing `ts`
+    ts: Option<f64>, // client clock (ms), echoed in the pong
+    frequency_ms: Option<u64>, // sets the interval of all three streams at once
+    price_ms: Option<u64>,
+    book_ms: Option<u64>,
+    trade_ms: Option<u64>,
+    subscribe: Option<Vec<String>>, // per-connection symbol filter, empty = all
+    candle_ms: Option<u64>, // OHLC candle interval
+    replay_speed: Option<f64>, // playback speed factor in replay mode
+    batch_ms: Option<u64>, // per-connection batching window, 0 = one frame per message
+    set_volatility: Option<(String, f64)>, // simulated symbol, max relative change per tick
+    set_base_price: Option<(String, f64)>, // simulated symbol, price to continue the walk from
+    enabled_streams: Option<Vec<String>>, // simulated streams to keep sending, the rest pause
+    burst: Option<burst::BurstConfig>, // extra price ticks for all clients, simulated source only
+    req_id: Option<u64>, // client-chosen id; asks for an `ack` listing what was applied
 }
 
-async fn ws_connection(mut socket: WebSocket, state: AppState) {
+impl ControlMsg {
+    /// Clamps every numeric field to its accepted range and drops values
+    /// that can't be used (non-finite, non-positive prices), so the handler
+    /// can apply what's left as is.
+    fn sanitized(mut self) -> Self {
+        let interval = |ms: Option<u64>| ms.map(|ms| ms.clamp(10, 5000));
+        self.ts = self.ts.filter(|ts| ts.is_finite());
+        self.frequency_ms = interval(self.frequency_ms);
+        self.price_ms = interval(self.price_ms);
+        self.book_ms = interval(self.book_ms);
+        self.trade_ms = interval(self.trade_ms);
+        self.candle_ms = self.candle_ms.map(|ms| ms.clamp(100, 60_000));
+        self.replay_speed = self.replay_speed.filter(|s| s.is_finite()).map(|s| s.clamp(0.01, 100.0));
+        self.batch_ms = self.batch_ms.map(|ms| ms.min(1000));
+        self.set_volatility = self
+            .set_volatility
+            .filter(|(_, v)| v.is_finite())
+            .map(|(symbol, v)| (symbol, v.clamp(0.0, 0.5)));
+        self.set_base_price = self.set_base_price.filter(|(_, p)| p.is_finite() && *p > 0.0);
+        self.burst = self.burst.map(burst::BurstConfig::sanitized);
+        self
+    }
+}
+
+/// Control messages a connection may send per second; bursts up to the
+/// same number pass at once
+const CONTROL_RATE: f64 = 5.0;
+/// Pings a connection may send per second, counted apart from `CONTROL_RATE`
+const PING_RATE: f64 = 5.0;
+
+/// Wraps already serialized messages into one `WireMessage::Batch` frame
+/// (`{"type":"batch","items":[...]}`) without parsing them again.
+fn batch_frame(items: &[String]) -> String {
+    format!(r#"{{"type":"batch","items":[{}]}}"#, items.join(","))
+}
+
+/// Extracts the `symbol` field of a broadcast message, if it has one.
+fn message_symbol(msg: &str) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct SymbolOnly {
+        symbol: Option<String>,
+    }
+    serde_json::from_str::<SymbolOnly>(msg).ok()?.symbol
+}
+
+async fn ws_connection(
+    mut socket: WebSocket,
+    state: AppState,
+    format: wire_format::WireFormat,
+    mut connection: connections::ConnectionEntry,
+) {
+    let _connection = state.metrics.connection();
     let mut rx = state.tx.subscribe();
+    // None = no filter; messages without a symbol (e.g. system) always pass
+    let mut subscription: Option<HashSet<String>> = None;
+    // Total broadcast messages this client missed by lagging behind
+    let mut skipped_total: u64 = 0;
+    // Messages collected during the current batch window (batch_ms > 0)
+    let mut batch_ms: u64 = 0;
+    let mut pending: Vec<String> = Vec::new();
+    let mut flush_at = tokio::time::Instant::now();
+    // Inbound control messages beyond the rate are answered with an error
+    let mut control_limit = rate_limit::TokenBucket::new(CONTROL_RATE, CONTROL_RATE);
+    let mut ping_limit = rate_limit::TokenBucket::new(PING_RATE, PING_RATE);
+    // (req_id, burst, ticks sent) of finished bursts that asked for an ack
+    let (burst_done_tx, mut burst_done) = tokio::sync::mpsc::unbounded_channel::<(u64, burst::BurstConfig, u32)>();
+
+    // First frame: lets the client detect schema skew and list our symbols
+    let hello = WireMessage::Hello {
+        schema_version: SCHEMA_VERSION,
+        symbols: state.symbol_names(),
+    };
+    let Some(hello) = format.encode(&hello) else { return };
+    if socket.send(hello).await.is_err() {
+        return;
+    }
+    let Some(streams) = format.encode(&streams_message(&state.enabled_streams)) else { return };
+    if socket.send(streams).await.is_err() {
+        return;
+    }
 
     loop {
         tokio::select! {
+            _ = tokio::time::sleep_until(flush_at), if !pending.is_empty() => {
+                let frame = format.frame(batch_frame(&pending));
+                let count = pending.len() as u64;
+                pending.clear();
+                let Some(frame) = frame else { continue };
+                if socket.send(frame).await.is_err() { break; }
+                connection.sent += count;
+            }
+            Some((req_id, config, sent)) = burst_done.recv() => {
+                // Second ack for the same req_id, once the burst is over
+                let mut applied = serde_json::Map::new();
+                applied.insert("burst".into(), serde_json::json!({
+                    "messages": config.messages,
+                    "over_ms": config.over_ms,
+                    "sent": sent,
+                }));
+                let Some(ack) = format.encode(&WireMessage::Ack { req_id, applied }) else { continue };
+                if socket.send(ack).await.is_err() { break; }
+            }
             res = rx.recv() => {
                 match res {
+                    Ok(msg) if msg == SHUTDOWN_MSG => {
+                        // Deliver anything still batched, then the notice itself
+                        if !pending.is_empty() {
+                            if let Some(frame) = format.frame(batch_frame(&pending)) {
+                                if socket.send(frame).await.is_ok() {
+                                    connection.sent += pending.len() as u64;
+                                }
+                            }
+                        }
+                        if let Some(frame) = format.frame(msg) {
+                            let _ = socket.send(frame).await;
+                        }
+                        let _ = socket.send(Message::Close(None)).await;
+                        break;
+                    }
                     Ok(msg) => {
-                        if socket.send(Message::Text(msg.into())).await.is_err() { break; }
+                        if let Some(symbols) = &subscription {
+                            if let Some(symbol) = message_symbol(&msg) {
+                                if !symbols.contains(&symbol) { continue; }
+                            }
+                        }
+                        if batch_ms > 0 {
+                            if pending.is_empty() {
+                                flush_at = tokio::time::Instant::now() + Duration::from_millis(batch_ms);
+                            }
+                            pending.push(msg);
+                            continue;
+                        }
+                        let Some(frame) = format.frame(msg) else { continue };
+                        if socket.send(frame).await.is_err() { break; }
+                        connection.sent += 1;
                     }
-                    Err(_) => break,
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        skipped_total += n;
+                        log!("ws #{} lagged, skipped {} messages ({} total)", connection.id(), n, skipped_total);
+                        let notice = WireMessage::Lag { skipped: n, total: skipped_total };
+                        let Some(notice) = format.encode(&notice) else { continue };
+                        if socket.send(notice).await.is_err() { break; }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
                 }
             }
             maybe_in = socket.recv() => {
                 match maybe_in {
-                    Some(Ok(Message::Text(txt))) => {
-                        if let Ok(ctrl) = serde_json::from_str::<ControlMsg>(&txt) {
-                            if let Some(freq) = ctrl.frequency_ms {
-                                state.sleep_ms.store(freq.max(10).min(1000), Ordering::Relaxed);
+                    Some(Ok(Message::Ping(payload))) => {
+                        // Answered here even though tungstenite queues a pong
+                        // too; unsolicited pongs are allowed and simply ignored
+                        if socket.send(Message::Pong(payload)).await.is_err() { break; }
+                    }
+                    Some(Ok(msg @ (Message::Text(_) | Message::Binary(_)))) => {
+                        // Binary frames carry the same fields as MessagePack
+                        // maps, whatever format this connection receives in
+                        let ctrl = match &msg {
+                            Message::Text(txt) => serde_json::from_str::<ControlMsg>(txt).ok(),
+                            Message::Binary(bytes) => rmp_serde::from_slice::<ControlMsg>(bytes).ok(),
+                            _ => None,
+                        };
+                        // Pings have their own budget, so keep-alives never
+                        // crowd out settings
+                        let allowed = match &ctrl {
+                            Some(ctrl) if ctrl.kind.as_deref() == Some("ping") => ping_limit.try_take(),
+                            _ => control_limit.try_take(),
+                        };
+                        if !allowed {
+                            let error = WireMessage::Error { msg: "rate limited".to_string(), code: ErrorCode::RateLimited.into() };
+                            let Some(error) = format.encode(&error) else { continue };
+                            if socket.send(error).await.is_err() { break; }
+                            continue;
+                        }
+                        if let Some(ctrl) = ctrl.map(ControlMsg::sanitized) {
+                            let mut applied = serde_json::Map::new();
+                            if ctrl.kind.as_deref() == Some("ping") {
+                                let pong = WireMessage::Pong {
+                                    ts: ctrl.ts.unwrap_or_default(),
+                                    server_ts: chrono::Utc::now().timestamp_micros(),
+                                };
+                                let Some(pong) = format.encode(&pong) else { continue };
+                                if socket.send(pong).await.is_err() { break; }
+                            }
+                            let streams = [
+                                (&state.price_ms, ctrl.price_ms),
+                                (&state.book_ms, ctrl.book_ms),
+                                (&state.trade_ms, ctrl.trade_ms),
+                            ];
+                            for (interval, ms) in streams {
+                                if let Some(ms) = ms.or(ctrl.frequency_ms) {
+                                    interval.store(ms, Ordering::Relaxed);
+                                }
+                            }
+                            for (field, ms) in [
+                                ("frequency_ms", ctrl.frequency_ms),
+                                ("price_ms", ctrl.price_ms),
+                                ("book_ms", ctrl.book_ms),
+                                ("trade_ms", ctrl.trade_ms),
+                            ] {
+                                if let Some(ms) = ms {
+                                    applied.insert(field.into(), ms.into());
+                                }
+                            }
+                            if let Some(ms) = ctrl.candle_ms {
+                                state.candle_ms.store(ms, Ordering::Relaxed);
+                                applied.insert("candle_ms".into(), ms.into());
+                            }
+                            if let Some((symbol, volatility)) = ctrl.set_volatility {
+                                // Unknown symbols are ignored
+                                if let Some(p) = state.symbol_params.lock().unwrap().get_mut(&symbol) {
+                                    p.volatility = volatility;
+                                    applied.insert("set_volatility".into(), serde_json::json!((symbol, volatility)));
+                                }
+                            }
+                            if let Some((symbol, price)) = ctrl.set_base_price {
+                                if let Some(p) = state.symbol_params.lock().unwrap().get_mut(&symbol) {
+                                    p.price = price;
+                                    applied.insert("set_base_price".into(), serde_json::json!((symbol, price)));
+                                }
+                            }
+                            if let (Some(speed), DataSource::Replay { .. }) = (ctrl.replay_speed, &state.data_source) {
+                                state.replay_speed.set(speed);
+                                applied.insert("replay_speed".into(), speed.into());
+                            }
+                            if let (Some(names), DataSource::Simulated) = (ctrl.enabled_streams, &state.data_source) {
+                                // Unknown names are ignored
+                                let enabled: HashSet<String> =
+                                    names.into_iter().filter(|n| STREAMS.contains(&n.as_str())).collect();
+                                let listed: Vec<&str> = STREAMS.iter().copied().filter(|s| enabled.contains(*s)).collect();
+                                applied.insert("enabled_streams".into(), listed.into());
+                                *state.enabled_streams.lock().unwrap() = enabled;
+                                // Pausing affects every client, so all of them hear about it
+                                if let Ok(s) = serde_json::to_string(&streams_message(&state.enabled_streams)) {
+                                    let _ = state.tx.send(s);
+                                }
                             }
+                            if let (Some(config), DataSource::Simulated) = (ctrl.burst, &state.data_source) {
+                                // Bursts reach every client, so only one runs at a time
+                                if let Some(guard) = state.burst.try_acquire() {
+                                    applied.insert("burst".into(), serde_json::json!(config));
+                                    let task = burst::run(
+                                        config,
+                                        state.tx.clone(),
+                                        state.symbol_params.clone(),
+                                        state.symbols.clone(),
+                                        state.health.clone(),
+                                        state.seed,
+                                    );
+                                    let (req_id, done) = (ctrl.req_id, burst_done_tx.clone());
+                                    tokio::spawn(async move {
+                                        let sent = task.await;
+                                        drop(guard);
+                                        log!("burst of {} ticks over {} ms done, {} sent", config.messages, config.over_ms, sent);
+                                        if let Some(req_id) = req_id {
+                                            let _ = done.send((req_id, config, sent));
+                                        }
+                                    });
+                                } else {
+                                    let error = WireMessage::Error {
+                                        msg: "a burst is already running".to_string(),
+                                        code: ErrorCode::Conflict.into(),
+                                    };
+                                    if let Some(error) = format.encode(&error) {
+                                        if socket.send(error).await.is_err() { break; }
+                                    }
+                                }
+                            }
+                            if let Some(ms) = ctrl.batch_ms {
+                                batch_ms = ms;
+                                applied.insert("batch_ms".into(), ms.into());
+                                // Switching batching off delivers what was collected right away
+                                if batch_ms == 0 && !pending.is_empty() {
+                                    let count = pending.len() as u64;
+                                    if let Some(frame) = format.frame(batch_frame(&pending)) {
+                                        if socket.send(frame).await.is_err() { break; }
+                                        connection.sent += count;
+                                    }
+                                    pending.clear();
+                                }
+                            }
+                            if let Some(symbols) = ctrl.subscribe {
+                                // Replays announce no symbols, so nothing can be checked
+                                let known = state.symbol_names();
+                                let unknown: Vec<&str> = symbols
+                                    .iter()
+                                    .filter(|s| !known.is_empty() && !known.contains(s))
+                                    .map(String::as_str)
+                                    .collect();
+                                if !unknown.is_empty() {
+                                    let error = WireMessage::Error {
+                                        msg: format!("unknown symbols: {}", unknown.join(", ")),
+                                        code: ErrorCode::UnknownSymbol.into(),
+                                    };
+                                    let Some(error) = format.encode(&error) else { continue };
+                                    if socket.send(error).await.is_err() { break; }
+                                } else {
+                                    applied.insert("subscribe".into(), symbols.clone().into());
+                                    subscription = if symbols.is_empty() {
+                                        None
+                                    } else {
+                                        Some(symbols.into_iter().collect())
+                                    };
+                                }
+                            }
+                            if let Some(req_id) = ctrl.req_id {
+                                let ack = WireMessage::Ack { req_id, applied };
+                                let Some(ack) = format.encode(&ack) else { continue };
+                                if socket.send(ack).await.is_err() { break; }
+                            }
+                        } else {
+                            let error = WireMessage::Error {
+                                msg: "invalid control message".to_string(),
+                                code: ErrorCode::InvalidMessage.into(),
+                            };
+                            let Some(error) = format.encode(&error) else { continue };
+                            if socket.send(error).await.is_err() { break; }
                         }
                     }
                     Some(Ok(Message::Close(_))) | None => break,
@@ -280,3 +918,49 @@ async fn ws_connection(mut socket: WebSocket, state: AppState) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    /// Serves `/ws` of `state` on a free local port
+    async fn serve(state: AppState) -> SocketAddr {
+        let app = Router::new().route("/ws", get(ws_handler)).with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+        });
+        addr
+    }
+
+    fn decode(frame: ClientMessage) -> WireMessage {
+        match frame {
+            ClientMessage::Binary(bytes) => rmp_serde::from_slice(&bytes).unwrap(),
+            other => panic!("expected a binary frame, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn batching_off_flushes_pending_items_as_msgpack() {
+        let state = AppState::for_tests();
+        let tx = state.tx.clone();
+        let addr = serve(state).await;
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?fmt=msgpack", addr)).await.unwrap();
+        assert!(matches!(decode(ws.next().await.unwrap().unwrap()), WireMessage::Hello { .. }));
+        assert!(matches!(decode(ws.next().await.unwrap().unwrap()), WireMessage::Streams { .. }));
+
+        ws.send(ClientMessage::Text(r#"{"batch_ms":1000,"req_id":1}"#.into())).await.unwrap();
+        assert!(matches!(decode(ws.next().await.unwrap().unwrap()), WireMessage::Ack { req_id: 1, .. }));
+        let price = WireMessage::Price { symbol: "BTC/USD".into(), price: 45000.0, volume: 1, ts: 1 };
+        tx.send(serde_json::to_string(&price).unwrap()).unwrap();
+        // Let the connection pick the tick up before batching goes off
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        ws.send(ClientMessage::Text(r#"{"batch_ms":0,"req_id":2}"#.into())).await.unwrap();
+        assert_eq!(decode(ws.next().await.unwrap().unwrap()), WireMessage::Batch { items: vec![price] });
+        assert!(matches!(decode(ws.next().await.unwrap().unwrap()), WireMessage::Ack { req_id: 2, .. }));
+    }
+}
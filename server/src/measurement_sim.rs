@@ -0,0 +1,489 @@
+use leptos_rust::multi_user_types::{
+    ChannelConfig, ChannelData, DataQuality, MeasurementConfig, MeasurementData, MeasurementId,
+    MeasurementStatus, MeasurementType, MultiUserMessage, SampleMetadata, SystemEvent,
+};
+use leptos::logging::log;
+use leptos_rust::storage::MeasurementWriter;
+use leptos_rust::trigger::TriggerEngine;
+use rand::{rngs::StdRng, Rng};
+use std::f64::consts::TAU;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::multi_user::MeasurementMap;
+use crate::seed;
+
+/// Shortest pause between two emitted frames. Faster sample rates are sent
+/// as `MeasurementBatch` instead of one message per sample.
+const MIN_TICK: Duration = Duration::from_millis(20);
+/// Noise amplitude relative to the channel range; lets values near the
+/// edges leave the range so `Overflow`/`Underflow` show up too
+const NOISE: f64 = 0.03;
+/// Batches queued for the file before the generator waits for the disk
+const RECORD_BUFFER: usize = 64;
+
+/// Emits simulated `MeasurementData` for a running measurement at
+/// `config.sample_rate_hz`, one `ChannelData` per enabled channel.
+///
+/// While the measurement is `Paused` nothing is emitted. After a resume the
+/// `sample_index` continues where it stopped while `timestamp_ns` restarts
+/// from the current time, so the pause shows up as a time gap; paused time
+/// doesn't count towards `duration_seconds`. Timestamps are the wall clock
+/// at the start plus the monotonic time since, so adjusting the system
+/// clock can't make them step back.
+///
+/// With a `trigger_config`, every sample runs through a [`TriggerEngine`]
+/// and carries the events it fired in `metadata.trigger_events`. The engine
+/// starts over after a resume, so the gap can't fire an edge.
+///
+/// An `UpdateMeasurementConfig` changes the rate and channels in the
+/// measurement map; the task adopts them on its next tick. The samples
+/// continue at the next `sample_index` and timestamp without a gap,
+/// disabled channels are left out from then on, and the tick interval and
+/// what remains of `duration_seconds` follow the new rate.
+///
+/// `out` is bounded: when the connection's queue is full, the frame is
+/// dropped instead of waiting for the client, like a lagging `/ws`
+/// subscriber skips broadcast messages. The samples are still recorded,
+/// and the first sample delivered afterwards carries a `DataOverrun`
+/// system event next to the `sample_index` gap.
+///
+/// With `MEASUREMENT_DIR` set, the samples are also written to
+/// `<dir>/<measurement_id>.arrow`, bounded by the config's `data_retention`.
+/// The writing happens on the blocking pool, so flushes and retention
+/// rewrites never stall the runtime.
+///
+/// The task ends when the measurement is neither `Running` nor `Paused`
+/// (stopped, or removed with its session), when `duration_seconds` is
+/// reached (the measurement is then marked `Completed` and the client is
+/// told), or when the connection behind `out` is gone.
+pub fn spawn_generator(
+    measurement_id: MeasurementId,
+    config: MeasurementConfig,
+    measurements: MeasurementMap,
+    out: mpsc::Sender<MultiUserMessage>,
+    seed: Option<u64>,
+) {
+    tokio::spawn(async move {
+        // File I/O, including the rewrites for `data_retention`, runs on the
+        // blocking pool and is fed the batches through `recorder`
+        let (mut recorder, recording) = match std::env::var("MEASUREMENT_DIR") {
+            Ok(dir) => {
+                let (tx, rx) = mpsc::channel(RECORD_BUFFER);
+                let config = config.clone();
+                (Some(tx), Some(tokio::task::spawn_blocking(move || record(measurement_id, &dir, &config, rx))))
+            }
+            Err(_) => (None, None),
+        };
+        // Ids are random, so with a seed every measurement gets the same noise
+        let mut rng = seed::stream_rng(seed, "measurement");
+        generate(measurement_id, &config, &measurements, &out, &mut recorder, &mut rng).await;
+        // Closing the channel lets the recorder finish the file
+        drop(recorder);
+        if let Some(recording) = recording {
+            let _ = recording.await;
+        }
+    });
+}
+
+/// Writes the batches arriving on `batches` to the measurement's file until
+/// the channel closes, then applies the retention. Returns early, dropping
+/// `batches` so the generator stops sending, if the file can't be written.
+fn record(
+    measurement_id: MeasurementId,
+    dir: &str,
+    config: &MeasurementConfig,
+    mut batches: mpsc::Receiver<Vec<MeasurementData>>,
+) {
+    let mut writer = match MeasurementWriter::create(dir, measurement_id, config) {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("measurement {}: not recorded: {}", measurement_id, e);
+            return;
+        }
+    };
+    while let Some(batch) = batches.blocking_recv() {
+        if let Err(e) = batch.iter().try_for_each(|sample| writer.write(sample)) {
+            eprintln!("measurement {}: recording stopped: {}", measurement_id, e);
+            return;
+        }
+    }
+    match writer.finish() {
+        Ok(Some(path)) => log!("measurement {} recorded to {}", measurement_id, path.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("measurement {}: recording failed: {}", measurement_id, e),
+    }
+}
+
+async fn generate(
+    measurement_id: MeasurementId,
+    config: &MeasurementConfig,
+    measurements: &MeasurementMap,
+    out: &mpsc::Sender<MultiUserMessage>,
+    recorder: &mut Option<mpsc::Sender<Vec<MeasurementData>>>,
+    rng: &mut StdRng,
+) {
+    let mut rate = config.sample_rate_hz.max(1) as u64;
+    let mut channels = config.channels.clone();
+    let mut total = config.duration_seconds.map(|s| s as u64 * rate);
+    let mut sample_index = 0u64;
+    // Samples are timed from the start of the current run, i.e. the
+    // start, the last resume or the last rate change, and its first sample index
+    let clock = Clock::new();
+    let mut run = Run::new(sample_index, 0.0, &clock);
+    let mut paused = false;
+    let mut trigger = config.trigger_config.clone().map(TriggerEngine::new);
+    // Samples dropped since the last frame that reached the queue
+    let mut dropped = 0u64;
+
+    let mut interval = tick_interval(rate);
+    loop {
+        interval.tick().await;
+        let Some((current, update)) = poll(measurements, measurement_id, rate, &channels) else { return };
+        if let Some((new_rate, new_channels)) = update {
+            if new_rate != rate {
+                // The next sample follows the last one as if nothing changed
+                run = Run {
+                    start: tokio::time::Instant::now(),
+                    started_ns: run.timestamp_ns(sample_index, rate),
+                    first_index: sample_index,
+                    t0: run.t(sample_index, rate),
+                };
+                total = total.map(|t| sample_index + t.saturating_sub(sample_index) * new_rate / rate);
+                rate = new_rate;
+                interval = tick_interval(rate);
+            }
+            channels = new_channels;
+        }
+        match current {
+            MeasurementStatus::Running => {}
+            MeasurementStatus::Paused => {
+                paused = true;
+                continue;
+            }
+            _ => return,
+        }
+        if paused {
+            run = Run::new(sample_index, run.t(sample_index, rate), &clock);
+            paused = false;
+            if let Some(trigger) = &mut trigger {
+                trigger.reset();
+            }
+        }
+        // Samples due by now, capped at the configured duration
+        let due = run.first_index + (run.start.elapsed().as_secs_f64() * rate as f64) as u64 + 1;
+        let due = total.map_or(due, |t| due.min(t));
+
+        let enabled: Vec<&ChannelConfig> = channels.iter().filter(|c| c.enabled).collect();
+        let mut batch: Vec<MeasurementData> = {
+            (sample_index..due)
+                .map(|i| {
+                    let t = run.t(i, rate);
+                    let timestamp_ns = run.timestamp_ns(i, rate);
+                    let samples: Vec<ChannelData> = enabled
+                        .iter()
+                        .map(|c| channel_sample(&config.measurement_type, c, t, rng.gen_range(-NOISE..NOISE)))
+                        .collect();
+                    let trigger_events = match &mut trigger {
+                        Some(trigger) => samples
+                            .iter()
+                            .filter_map(|c| trigger.evaluate(c.channel_id, c.value, timestamp_ns))
+                            .collect(),
+                        None => Vec::new(),
+                    };
+                    MeasurementData {
+                        measurement_id,
+                        timestamp_ns,
+                        sample_index: i,
+                        channels: samples,
+                        metadata: SampleMetadata { trigger_events, system_events: Vec::new() },
+                    }
+                })
+                .collect()
+        };
+
+        {
+            let mut map = measurements.lock().unwrap();
+            let Some(m) = map.get_mut(&measurement_id) else { return };
+            match m.status {
+                MeasurementStatus::Running => {}
+                // Paused while generating: drop the batch, it is
+                // generated again after the resume
+                MeasurementStatus::Paused => {
+                    paused = true;
+                    continue;
+                }
+                _ => return,
+            }
+            sample_index = due;
+            m.sample_count = sample_index;
+            if let Some(last) = batch.last() {
+                m.last_sample_time = last.timestamp_ns;
+            }
+        }
+
+        if let Some(tx) = recorder.as_ref() {
+            // Waiting here only delays the next tick, which then catches up
+            if tx.send(batch.clone()).await.is_err() {
+                *recorder = None;
+            }
+        }
+
+        if dropped > 0 {
+            if let Some(first) = batch.first_mut() {
+                first.metadata.system_events.push(SystemEvent::DataOverrun);
+            }
+        }
+        let count = batch.len() as u64;
+        let msg = match batch.len() {
+            0 => None,
+            1 => batch.into_iter().next().map(MultiUserMessage::MeasurementData),
+            _ => Some(MultiUserMessage::MeasurementBatch { measurements: batch }),
+        };
+        if let Some(msg) = msg {
+            match out.try_send(msg) {
+                Ok(()) if dropped > 0 => {
+                    log!("measurement {} lagged, dropped {} samples", measurement_id, dropped);
+                    dropped = 0;
+                }
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => dropped += count,
+                Err(TrySendError::Closed(_)) => return,
+            }
+        }
+
+        if total.is_some_and(|t| sample_index >= t) {
+            if let Some(m) = measurements.lock().unwrap().get_mut(&measurement_id) {
+                m.status = MeasurementStatus::Completed;
+            }
+            // Waits for room, the client must not miss the end
+            let _ = out
+                .send(MultiUserMessage::MeasurementStatus { measurement_id, status: MeasurementStatus::Completed })
+                .await;
+            return;
+        }
+    }
+}
+
+/// Wall clock read once, advanced with monotonic time
+struct Clock {
+    at: tokio::time::Instant,
+    ns: u64,
+}
+
+impl Clock {
+    fn new() -> Self {
+        Self { at: tokio::time::Instant::now(), ns: now_ns() }
+    }
+
+    fn now_ns(&self) -> u64 {
+        self.ns + self.at.elapsed().as_nanos() as u64
+    }
+}
+
+/// Wall-clock anchor of an uninterrupted stretch of samples at one rate
+struct Run {
+    start: tokio::time::Instant,
+    started_ns: u64,
+    first_index: u64,
+    t0: f64, // signal time of `first_index` in seconds; doesn't advance while paused
+}
+
+impl Run {
+    fn new(first_index: u64, t0: f64, clock: &Clock) -> Self {
+        Self { start: tokio::time::Instant::now(), started_ns: clock.now_ns(), first_index, t0 }
+    }
+
+    /// Signal time of sample `i` in seconds
+    fn t(&self, i: u64, rate: u64) -> f64 {
+        self.t0 + (i - self.first_index) as f64 / rate as f64
+    }
+
+    fn timestamp_ns(&self, i: u64, rate: u64) -> u64 {
+        self.started_ns + (i - self.first_index) * 1_000_000_000 / rate
+    }
+}
+
+fn tick_interval(rate: u64) -> tokio::time::Interval {
+    let tick = Duration::from_secs_f64(1.0 / rate as f64).max(MIN_TICK);
+    let mut interval = tokio::time::interval(tick);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    interval
+}
+
+/// Rate and channels adopted from an `UpdateMeasurementConfig`
+type ConfigUpdate = (u64, Vec<ChannelConfig>);
+
+/// Status of the measurement, plus its rate and channels if they differ
+/// from `rate` and `channels`, i.e. after an `UpdateMeasurementConfig`
+fn poll(
+    measurements: &MeasurementMap,
+    measurement_id: MeasurementId,
+    rate: u64,
+    channels: &[ChannelConfig],
+) -> Option<(MeasurementStatus, Option<ConfigUpdate>)> {
+    let map = measurements.lock().unwrap();
+    let m = map.get(&measurement_id)?;
+    let new_rate = m.config.sample_rate_hz.max(1) as u64;
+    let update = (new_rate != rate || m.config.channels != channels).then(|| (new_rate, m.config.channels.clone()));
+    Some((m.status.clone(), update))
+}
+
+/// Value of one channel at `t` seconds, scaled into the channel range
+fn channel_sample(kind: &MeasurementType, channel: &ChannelConfig, t: f64, noise: f64) -> ChannelData {
+    // Channels of one measurement are phase shifted so they don't overlap
+    let phase = channel.channel_id as f64 * 0.25;
+    let shape = match kind {
+        MeasurementType::Voltage => 0.5 + 0.5 * (TAU * (t + phase)).sin(),
+        MeasurementType::Current => 0.5 + 0.4 * (TAU * 0.5 * (t + phase)).sin(),
+        MeasurementType::Temperature => ((t + phase * 10.0) / 30.0).fract(), // slow ramp
+        MeasurementType::Pressure => 0.5 + 0.2 * (TAU * 0.1 * (t + phase)).sin(),
+        MeasurementType::Acceleration => 0.5, // vibration only, i.e. noise
+        MeasurementType::Custom { .. } => 0.5 + 0.5 * (TAU * 0.2 * (t + phase)).sin(),
+    };
+    let span = channel.range_max - channel.range_min;
+    let value = channel.range_min + (shape + noise) * span;
+    let quality = if value > channel.range_max {
+        DataQuality::Overflow
+    } else if value < channel.range_min {
+        DataQuality::Underflow
+    } else {
+        DataQuality::Good
+    };
+    ChannelData { channel_id: channel.channel_id, value, quality }
+}
+
+fn now_ns() -> u64 {
+    chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use leptos_rust::multi_user_types::{DataRetention, MeasurementSession};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    fn config(sample_rate_hz: u32) -> MeasurementConfig {
+        MeasurementConfig {
+            measurement_type: MeasurementType::Voltage,
+            sample_rate_hz,
+            duration_seconds: None,
+            channels: vec![ChannelConfig {
+                channel_id: 0,
+                name: "ch0".into(),
+                unit: "V".into(),
+                range_min: -1.0,
+                range_max: 1.0,
+                enabled: true,
+            }],
+            trigger_config: None,
+            auto_scale: false,
+            data_retention: DataRetention::KeepAll,
+        }
+    }
+
+    /// Map holding one `Running` measurement with `config`
+    fn running(config: &MeasurementConfig) -> (MeasurementMap, MeasurementId) {
+        let measurement_id = Uuid::new_v4();
+        let measurement = MeasurementSession {
+            measurement_id,
+            user_id: Uuid::new_v4(),
+            name: "test".into(),
+            measurement_type: config.measurement_type.clone(),
+            config: config.clone(),
+            status: MeasurementStatus::Running,
+            started_at: 0,
+            sample_count: 0,
+            last_sample_time: 0,
+        };
+        (Arc::new(Mutex::new(HashMap::from([(measurement_id, measurement)]))), measurement_id)
+    }
+
+    fn samples(msg: MultiUserMessage) -> Vec<MeasurementData> {
+        match msg {
+            MultiUserMessage::MeasurementData(data) => vec![data],
+            MultiUserMessage::MeasurementBatch { measurements } => measurements,
+            other => panic!("expected samples, got {:?}", other),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_queue_drops_samples_and_marks_the_overrun() {
+        let config = config(100);
+        let (measurements, id) = running(&config);
+        let (tx, mut rx) = mpsc::channel(1);
+        spawn_generator(id, config, measurements.clone(), tx, Some(1));
+
+        // Nobody reads: the first frame fills the queue, later ones are dropped
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let first = samples(rx.recv().await.unwrap());
+        assert!(first.iter().all(|s| s.metadata.system_events.is_empty()));
+        let next = samples(rx.recv().await.unwrap());
+        assert!(next[0].sample_index > first.last().unwrap().sample_index + 1);
+        assert!(matches!(next[0].metadata.system_events[..], [SystemEvent::DataOverrun]));
+        // Only the first frame after the drop is marked
+        let after = samples(rx.recv().await.unwrap());
+        assert_eq!(after[0].sample_index, next.last().unwrap().sample_index + 1);
+        assert!(after[0].metadata.system_events.is_empty());
+        // Dropped samples still count as generated
+        assert!(measurements.lock().unwrap()[&id].sample_count > after.last().unwrap().sample_index);
+    }
+
+    fn set_status(measurements: &MeasurementMap, id: MeasurementId, status: MeasurementStatus) {
+        measurements.lock().unwrap().get_mut(&id).unwrap().status = status;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resume_continues_the_index_after_a_time_gap() {
+        let config = config(100);
+        let (measurements, id) = running(&config);
+        let (tx, mut rx) = mpsc::channel(1024);
+        spawn_generator(id, config, measurements.clone(), tx, Some(1));
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        set_status(&measurements, id, MeasurementStatus::Paused);
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        set_status(&measurements, id, MeasurementStatus::Running);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        set_status(&measurements, id, MeasurementStatus::Stopping);
+
+        let mut received = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            received.extend(samples(msg));
+        }
+        assert!(received.len() > 60, "{} samples", received.len());
+        assert!(received.iter().enumerate().all(|(i, s)| s.sample_index == i as u64));
+        // 10 ms apart, except for the one step across the pause
+        let steps: Vec<u64> = received.windows(2).map(|w| w[1].timestamp_ns - w[0].timestamp_ns).collect();
+        let gaps: Vec<u64> = steps.iter().copied().filter(|&dt| dt != 10_000_000).collect();
+        assert_eq!(gaps.len(), 1, "{:?}", gaps);
+        assert!((2_000_000_000..2_100_000_000).contains(&gaps[0]), "{}", gaps[0]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_change_keeps_the_index_and_respaces_timestamps() {
+        let config = config(100);
+        let (measurements, id) = running(&config);
+        let (tx, mut rx) = mpsc::channel(1024);
+        spawn_generator(id, config, measurements.clone(), tx, Some(1));
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        measurements.lock().unwrap().get_mut(&id).unwrap().config.sample_rate_hz = 50;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        set_status(&measurements, id, MeasurementStatus::Stopping);
+
+        let mut received = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            received.extend(samples(msg));
+        }
+        assert!(received.iter().enumerate().all(|(i, s)| s.sample_index == i as u64));
+        // 10 ms apart up to the change, 20 ms after it
+        let steps: Vec<u64> = received.windows(2).map(|w| w[1].timestamp_ns - w[0].timestamp_ns).collect();
+        let switch = steps.iter().position(|&dt| dt != 10_000_000).expect("no step at the new rate");
+        assert!((45..=55).contains(&switch), "switch after {} steps", switch);
+        assert!(steps[switch..].iter().all(|&dt| dt == 20_000_000), "{:?}", &steps[switch..]);
+        assert!(steps.len() - switch > 20, "{} steps at 50 Hz", steps.len() - switch);
+    }
+}
@@ -0,0 +1,178 @@
+use axum::{
+    extract::State,
+    http::header,
+    response::IntoResponse,
+};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::AppState;
+
+/// Counters exported on `/metrics` in Prometheus text format
+#[derive(Default)]
+pub struct Metrics {
+    messages_broadcast: AtomicU64,
+    ws_connections: AtomicU64,
+    tick_overruns: AtomicU64,
+    price_ticks: Mutex<BTreeMap<String, u64>>, // sorted for stable output
+}
+
+impl Metrics {
+    /// Counts one open `/ws` connection until the returned guard is dropped,
+    /// so every exit path of the connection loop decrements the gauge.
+    pub fn connection(self: &Arc<Self>) -> ConnectionGuard {
+        self.ws_connections.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard(self.clone())
+    }
+
+    pub fn open_connections(&self) -> u64 {
+        self.ws_connections.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, msg: &str) {
+        #[derive(serde::Deserialize)]
+        struct Head {
+            #[serde(rename = "type")]
+            kind: Option<String>,
+            symbol: Option<String>,
+        }
+
+        self.messages_broadcast.fetch_add(1, Ordering::Relaxed);
+        if let Ok(Head { kind: Some(kind), symbol: Some(symbol) }) = serde_json::from_str(msg) {
+            if kind == "price" {
+                *self.price_ticks.lock().unwrap().entry(symbol).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Turns the cumulative broadcast counter into messages per second
+pub struct RateMeter {
+    last_count: u64,
+    last_at: Instant,
+}
+
+impl RateMeter {
+    pub fn new(metrics: &Metrics) -> Self {
+        Self { last_count: metrics.messages_broadcast.load(Ordering::Relaxed), last_at: Instant::now() }
+    }
+
+    /// Rate since the previous call, scaled by the actual elapsed time
+    pub fn per_second(&mut self, metrics: &Metrics) -> u64 {
+        let count = metrics.messages_broadcast.load(Ordering::Relaxed);
+        let now = Instant::now();
+        let secs = now.duration_since(self.last_at).as_secs_f64();
+        let delta = count.saturating_sub(self.last_count);
+        self.last_count = count;
+        self.last_at = now;
+        if secs > 0.0 { (delta as f64 / secs).round() as u64 } else { 0 }
+    }
+}
+
+/// How late a price tick may start before it counts as an overrun, unless
+/// `TICK_TOLERANCE_MS` says otherwise
+const DEFAULT_TICK_TOLERANCE_MS: u64 = 25;
+
+/// Overrun warnings are summarized instead of logged per tick, a starved
+/// runtime would otherwise flood the log at the tick rate
+const OVERRUN_LOG_EVERY: Duration = Duration::from_secs(5);
+
+/// Compares the time between two price loop iterations with the sleep the
+/// loop asked for. An iteration that starts later than that by more than the
+/// tolerance means the runtime couldn't schedule the task in time, so the
+/// server rather than the client is the bottleneck.
+pub struct CadenceWatch {
+    tolerance: Duration,
+    /// Start of the previous iteration and the interval it slept for
+    last: Option<(Instant, Duration)>,
+    logged_at: Option<Instant>,
+    unlogged: u64,
+}
+
+impl CadenceWatch {
+    pub fn from_env() -> Self {
+        let ms = std::env::var("TICK_TOLERANCE_MS").ok().and_then(|v| v.trim().parse().ok());
+        Self {
+            tolerance: Duration::from_millis(ms.unwrap_or(DEFAULT_TICK_TOLERANCE_MS)),
+            last: None,
+            logged_at: None,
+            unlogged: 0,
+        }
+    }
+
+    /// Call once per iteration right before sleeping for `interval`
+    pub fn tick(&mut self, interval: Duration, metrics: &Metrics) {
+        let now = Instant::now();
+        if let Some((at, intended)) = self.last.replace((now, interval)) {
+            let actual = now.duration_since(at);
+            if actual > intended + self.tolerance {
+                metrics.tick_overruns.fetch_add(1, Ordering::Relaxed);
+                self.unlogged += 1;
+                if self.logged_at.is_none_or(|t| now.duration_since(t) >= OVERRUN_LOG_EVERY) {
+                    eprintln!(
+                        "⚠️ price tick took {} ms instead of {} ms ({} overruns since last warning)",
+                        actual.as_millis(),
+                        intended.as_millis(),
+                        self.unlogged,
+                    );
+                    self.logged_at = Some(now);
+                    self.unlogged = 0;
+                }
+            }
+        }
+    }
+}
+
+pub struct ConnectionGuard(Arc<Metrics>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.ws_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Observes the broadcast channel so simulated and live streams are counted
+/// the same way without touching every sender.
+pub fn spawn_counter(tx: &broadcast::Sender<String>, metrics: Arc<Metrics>) {
+    let mut rx = tx.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => metrics.record(&msg),
+                // Skipped messages were still broadcast, only their symbol is unknown
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    metrics.messages_broadcast.fetch_add(n, Ordering::Relaxed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+pub async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let m = &state.metrics;
+    let mut out = String::new();
+    let _ = writeln!(out, "# HELP messages_broadcast_total Messages sent on the broadcast channel.");
+    let _ = writeln!(out, "# TYPE messages_broadcast_total counter");
+    let _ = writeln!(out, "messages_broadcast_total {}", m.messages_broadcast.load(Ordering::Relaxed));
+    let _ = writeln!(out, "# HELP ws_connections Open /ws connections.");
+    let _ = writeln!(out, "# TYPE ws_connections gauge");
+    let _ = writeln!(out, "ws_connections {}", m.ws_connections.load(Ordering::Relaxed));
+    let _ = writeln!(out, "# HELP update_interval_ms Current update interval per simulated stream.");
+    let _ = writeln!(out, "# TYPE update_interval_ms gauge");
+    for (stream, ms) in [("price", &state.price_ms), ("book", &state.book_ms), ("trade", &state.trade_ms)] {
+        let _ = writeln!(out, "update_interval_ms{{stream=\"{}\"}} {}", stream, ms.load(Ordering::Relaxed));
+    }
+    let _ = writeln!(out, "# HELP tick_overruns_total Price ticks that started later than the update interval plus tolerance.");
+    let _ = writeln!(out, "# TYPE tick_overruns_total counter");
+    let _ = writeln!(out, "tick_overruns_total {}", m.tick_overruns.load(Ordering::Relaxed));
+    let _ = writeln!(out, "# HELP price_ticks_total Price ticks broadcast per symbol.");
+    let _ = writeln!(out, "# TYPE price_ticks_total counter");
+    for (symbol, count) in m.price_ticks.lock().unwrap().iter() {
+        let _ = writeln!(out, "price_ticks_total{{symbol=\"{}\"}} {}", symbol, count);
+    }
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], out)
+}
@@ -0,0 +1,638 @@
+use axum::{
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State},
+    response::IntoResponse,
+};
+use leptos_rust::error_code::ErrorCode;
+use leptos_rust::multi_user_types::{
+    KernelCommand, KernelCommandType, MeasurementConfig, MeasurementId, MeasurementSession, MeasurementStatus,
+    MeasurementType, MultiUserMessage, ResourceUsage, SystemStats, UserPermissions, UserSession,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use crate::{measurement_sim, AppState};
+
+/// Active sessions keyed by `session_id`
+pub type SessionMap = Arc<Mutex<HashMap<Uuid, UserSession>>>;
+/// All measurements started through `/mu`, keyed by `measurement_id`
+pub type MeasurementMap = Arc<Mutex<HashMap<MeasurementId, MeasurementSession>>>;
+
+/// Size of one JSON `MeasurementData` message without channels, and what
+/// each channel adds to it, for the bandwidth estimate
+const SAMPLE_BYTES: u64 = 180;
+const CHANNEL_BYTES: u64 = 62;
+/// Data messages queued per connection. A generator finding the queue full
+/// drops its samples instead of buffering them, see `spawn_generator`.
+const DATA_BUFFER: usize = 256;
+
+/// Registered user with a fixed `user_id` for the lifetime of the process
+struct UserAccount {
+    user_id: Uuid,
+    password: String,
+    permissions: UserPermissions,
+}
+
+/// In-memory user table, read once at startup
+pub struct UserTable {
+    users: HashMap<String, UserAccount>,
+}
+
+impl UserTable {
+    /// Reads `MU_USERS=alice:secret,bob:hunter2`, defaulting to a single
+    /// `demo:demo` account when unset.
+    pub fn from_env() -> Self {
+        Self::from_spec(&std::env::var("MU_USERS").unwrap_or_else(|_| "demo:demo".into()))
+    }
+
+    /// Accounts from a `name:password` list as in `MU_USERS`
+    pub fn from_spec(spec: &str) -> Self {
+        let users = spec
+            .split(',')
+            .filter_map(|entry| entry.split_once(':'))
+            .map(|(name, password)| {
+                (name.trim().to_string(), UserAccount {
+                    user_id: Uuid::new_v4(),
+                    password: password.trim().to_string(),
+                    permissions: UserPermissions::default(),
+                })
+            })
+            .collect();
+        Self { users }
+    }
+
+    fn authenticate(&self, username: &str, password: &str) -> Option<&UserAccount> {
+        self.users.get(username).filter(|u| u.password == password)
+    }
+
+    /// Registered accounts, logged in or not
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+}
+
+fn now_us() -> u64 {
+    chrono::Utc::now().timestamp_micros() as u64
+}
+
+pub async fn mu_handler(State(state): State<AppState>, ws: WebSocketUpgrade) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| mu_connection(socket, state))
+}
+
+/// Estimated bits per second a running measurement sends to its client
+fn measurement_bandwidth_bps(config: &MeasurementConfig) -> u64 {
+    let channels = config.channels.iter().filter(|c| c.enabled).count() as u64;
+    config.sample_rate_hz as u64 * (SAMPLE_BYTES + channels * CHANNEL_BYTES) * 8
+}
+
+/// Aggregates the `Running` measurements across all sessions. Paused and
+/// completed measurements count towards `total_measurements` only. CPU and
+/// memory aren't measured per process yet and stay 0.
+pub fn system_stats(
+    total_users: usize,
+    sessions: &HashMap<Uuid, UserSession>,
+    measurements: &HashMap<MeasurementId, MeasurementSession>,
+) -> SystemStats {
+    let running: Vec<&MeasurementSession> =
+        measurements.values().filter(|m| matches!(m.status, MeasurementStatus::Running)).collect();
+    let active_users: HashSet<Uuid> = sessions.values().map(|s| s.user_id).collect();
+    SystemStats {
+        total_users: total_users as u32,
+        active_users: active_users.len() as u32,
+        total_measurements: measurements.len() as u32,
+        active_measurements: running.len() as u32,
+        total_samples_per_second: running.iter().map(|m| m.config.sample_rate_hz).sum(),
+        cpu_usage_percent: 0.0,
+        memory_usage_mb: 0,
+        network_throughput_bps: running.iter().map(|m| measurement_bandwidth_bps(&m.config)).sum(),
+    }
+}
+
+/// Once per second: refreshes each session's `ResourceUsage` from its
+/// running measurements and publishes the `SystemStats` on `tx`, which every
+/// logged in `/mu` connection forwards together with its own usage.
+pub fn spawn_stats(
+    users: Arc<UserTable>,
+    sessions: SessionMap,
+    measurements: MeasurementMap,
+    tx: broadcast::Sender<SystemStats>,
+    shutdown: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        while !shutdown.load(Ordering::Relaxed) {
+            interval.tick().await;
+            let stats = {
+                // Same lock order as the command handlers
+                let mut sessions = sessions.lock().unwrap();
+                let measurements = measurements.lock().unwrap();
+                for session in sessions.values_mut() {
+                    let running = session
+                        .active_measurements
+                        .iter()
+                        .filter_map(|id| measurements.get(id))
+                        .filter(|m| matches!(m.status, MeasurementStatus::Running));
+                    session.resource_usage.bandwidth_bps = running.map(|m| measurement_bandwidth_bps(&m.config)).sum();
+                    session.resource_usage.active_measurements = session.active_measurements.len() as u8;
+                }
+                system_stats(users.len(), &sessions, &measurements)
+            };
+            // No subscribers just means no /mu connection is open
+            let _ = tx.send(stats);
+        }
+    });
+}
+
+/// One multi-user WebSocket. A connection is bound to at most one session,
+/// created by `UserLogin`; every other command requires that session to still
+/// be registered in `AppState::sessions`. Measurement data produced for the
+/// session arrives on `data_rx` and is forwarded as it comes, the periodic
+/// `SystemStats` only while logged in, followed by the session's `UserStats`.
+/// `data_rx` is bounded, so a client that can't keep up loses samples
+/// rather than growing the server's memory.
+async fn mu_connection(mut socket: WebSocket, state: AppState) {
+    let mut session_id: Option<Uuid> = None;
+    let (data_tx, mut data_rx) = mpsc::channel(DATA_BUFFER);
+    let mut stats_rx = state.mu_stats.subscribe();
+
+    loop {
+        let replies = tokio::select! {
+            msg = socket.recv() => {
+                let txt = match msg {
+                    Some(Ok(Message::Text(txt))) => txt,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                match serde_json::from_str::<MultiUserMessage>(&txt) {
+                    Ok(cmd) => handle_command(&state, &mut session_id, &data_tx, cmd),
+                    Err(e) => vec![error(ErrorCode::InvalidMessage, format!("invalid message: {}", e))],
+                }
+            }
+            // Never `None` since `data_tx` lives as long as the loop
+            Some(data) = data_rx.recv() => vec![data],
+            // A lagging connection just skips to the next update
+            Ok(stats) = stats_rx.recv() => {
+                let usage = session_id.and_then(|id| {
+                    state.sessions.lock().unwrap().get(&id).map(|s| s.resource_usage.clone())
+                });
+                match usage {
+                    Some(usage) => vec![MultiUserMessage::SystemStats(stats), MultiUserMessage::UserStats(usage)],
+                    None => continue,
+                }
+            }
+        };
+        let mut closed = false;
+        for reply in replies {
+            let Ok(out) = serde_json::to_string(&reply) else { continue };
+            if socket.send(Message::Text(out.into())).await.is_err() {
+                closed = true;
+                break;
+            }
+        }
+        if closed { break; }
+    }
+
+    // A session can't outlive its connection since messages carry no session id
+    if let Some(id) = session_id {
+        end_session(&state, id);
+    }
+}
+
+/// Removes a session together with the measurements it owns
+fn end_session(state: &AppState, session_id: Uuid) {
+    if let Some(session) = state.sessions.lock().unwrap().remove(&session_id) {
+        let mut measurements = state.measurements.lock().unwrap();
+        for id in &session.active_measurements {
+            measurements.remove(id);
+        }
+    }
+}
+
+fn handle_command(
+    state: &AppState,
+    session_id: &mut Option<Uuid>,
+    data_tx: &mpsc::Sender<MultiUserMessage>,
+    cmd: MultiUserMessage,
+) -> Vec<MultiUserMessage> {
+    if let MultiUserMessage::UserLogin { username, password } = cmd {
+        let Some(account) = state.users.authenticate(&username, &password) else {
+            return vec![error(ErrorCode::Unauthorized, "invalid username or password")];
+        };
+        let now = now_us();
+        let session = UserSession {
+            user_id: account.user_id,
+            session_id: Uuid::new_v4(),
+            username,
+            created_at: now,
+            last_activity: now,
+            active_measurements: Vec::new(),
+            resource_usage: ResourceUsage::default(),
+            permissions: account.permissions.clone(),
+        };
+        // Logging in again on the same connection replaces the old session
+        if let Some(old) = session_id.take() {
+            end_session(state, old);
+        }
+        *session_id = Some(session.session_id);
+        state.sessions.lock().unwrap().insert(session.session_id, session.clone());
+        return vec![MultiUserMessage::SessionStarted { session }];
+    }
+
+    let mut sessions = state.sessions.lock().unwrap();
+    let Some(session) = session_id.and_then(|id| sessions.get_mut(&id)) else {
+        return vec![error(ErrorCode::Unauthorized, "unknown session, please log in")];
+    };
+    session.last_activity = now_us();
+
+    match cmd {
+        MultiUserMessage::SessionHeartbeat => Vec::new(),
+        MultiUserMessage::UserLogout => {
+            drop(sessions);
+            if let Some(id) = session_id.take() {
+                end_session(state, id);
+            }
+            vec![MultiUserMessage::UserLogout]
+        }
+        MultiUserMessage::StartMeasurement { config } => {
+            if let Err((resource, limit)) = check_permissions(session, &config) {
+                return vec![MultiUserMessage::ResourceLimitExceeded { resource: resource.into(), limit }];
+            }
+            start_measurement(state, session, config, data_tx)
+        }
+        MultiUserMessage::StopMeasurement { measurement_id } => {
+            if !session.active_measurements.contains(&measurement_id) {
+                return vec![unknown_measurement(measurement_id)];
+            }
+            session.active_measurements.retain(|id| *id != measurement_id);
+            session.resource_usage.active_measurements = session.active_measurements.len() as u8;
+            let mut measurements = state.measurements.lock().unwrap();
+            if let Some(m) = measurements.get_mut(&measurement_id) {
+                m.status = MeasurementStatus::Completed;
+            }
+            vec![MultiUserMessage::MeasurementStatus { measurement_id, status: MeasurementStatus::Completed }]
+        }
+        MultiUserMessage::PauseMeasurement { measurement_id } => {
+            set_paused(state, session, measurement_id, true)
+        }
+        MultiUserMessage::ResumeMeasurement { measurement_id } => {
+            set_paused(state, session, measurement_id, false)
+        }
+        MultiUserMessage::UpdateMeasurementConfig { measurement_id, config } => {
+            let cmd = KernelCommand {
+                user_id: session.user_id,
+                measurement_id,
+                command: KernelCommandType::UpdateConfig(config),
+            };
+            update_config(state, session, cmd)
+        }
+        _ => vec![error(ErrorCode::Unsupported, "unsupported command")],
+    }
+}
+
+/// Moves one of the session's measurements between `Running` and `Paused`.
+/// The generator task notices the status on its next tick: it stops
+/// emitting while paused and continues the `sample_index` after a resume.
+fn set_paused(
+    state: &AppState,
+    session: &UserSession,
+    measurement_id: MeasurementId,
+    pause: bool,
+) -> Vec<MultiUserMessage> {
+    if !session.active_measurements.contains(&measurement_id) {
+        return vec![unknown_measurement(measurement_id)];
+    }
+    let mut measurements = state.measurements.lock().unwrap();
+    let Some(m) = measurements.get_mut(&measurement_id) else {
+        return vec![unknown_measurement(measurement_id)];
+    };
+    let (from, to) = if pause {
+        (MeasurementStatus::Running, MeasurementStatus::Paused)
+    } else {
+        (MeasurementStatus::Paused, MeasurementStatus::Running)
+    };
+    if std::mem::discriminant(&m.status) != std::mem::discriminant(&from) {
+        return vec![error(
+            ErrorCode::Conflict,
+            format!("measurement {} is {:?}, not {:?}", measurement_id, m.status, from),
+        )];
+    }
+    m.status = to.clone();
+    vec![MultiUserMessage::MeasurementStatus { measurement_id, status: to }]
+}
+
+/// Applies a `KernelCommandType::UpdateConfig`: adopts the `sample_rate_hz`
+/// and `channels` of its config into a running or paused measurement; the
+/// other fields stay as started. The generator task picks them up on its
+/// next tick and continues the `sample_index`.
+fn update_config(state: &AppState, session: &UserSession, cmd: KernelCommand) -> Vec<MultiUserMessage> {
+    let KernelCommand { measurement_id, command: KernelCommandType::UpdateConfig(config), .. } = cmd else {
+        return vec![error(ErrorCode::Unsupported, "unsupported command")];
+    };
+    if !session.active_measurements.contains(&measurement_id) {
+        return vec![unknown_measurement(measurement_id)];
+    }
+    if config.sample_rate_hz > session.permissions.max_sample_rate_hz {
+        return vec![MultiUserMessage::ResourceLimitExceeded {
+            resource: "sample_rate_hz".into(),
+            limit: session.permissions.max_sample_rate_hz as u64,
+        }];
+    }
+    let mut measurements = state.measurements.lock().unwrap();
+    let Some(m) = measurements.get_mut(&measurement_id) else {
+        return vec![unknown_measurement(measurement_id)];
+    };
+    if !matches!(m.status, MeasurementStatus::Running | MeasurementStatus::Paused) {
+        return vec![error(ErrorCode::Conflict, format!("measurement {} is {:?}", measurement_id, m.status))];
+    }
+    if config.measurement_type != m.config.measurement_type {
+        return vec![error(ErrorCode::InvalidConfig, "the measurement type can't change while running")];
+    }
+    if !config.channels.iter().any(|c| c.enabled) {
+        return vec![error(ErrorCode::InvalidConfig, "at least one channel must stay enabled")];
+    }
+    m.config.sample_rate_hz = config.sample_rate_hz.max(1);
+    m.config.channels = config.channels;
+    vec![MultiUserMessage::UpdateMeasurementConfig { measurement_id, config: m.config.clone() }]
+}
+
+fn error(code: ErrorCode, message: impl Into<String>) -> MultiUserMessage {
+    MultiUserMessage::Error { message: message.into(), error_code: code.into() }
+}
+
+fn unknown_measurement(measurement_id: MeasurementId) -> MultiUserMessage {
+    error(ErrorCode::NotFound, format!("unknown measurement {}", measurement_id))
+}
+
+/// Checks a requested measurement against the session's `UserPermissions`,
+/// returning the first violated resource and its limit. For
+/// `measurement_type` there is no numeric limit, so the limit is 0.
+fn check_permissions(session: &UserSession, config: &MeasurementConfig) -> Result<(), (&'static str, u64)> {
+    let perms = &session.permissions;
+    if config.sample_rate_hz > perms.max_sample_rate_hz {
+        return Err(("sample_rate_hz", perms.max_sample_rate_hz as u64));
+    }
+    if session.active_measurements.len() >= perms.max_concurrent_measurements as usize {
+        return Err(("concurrent_measurements", perms.max_concurrent_measurements as u64));
+    }
+    if !perms.allowed_measurement_types.contains(&config.measurement_type) {
+        return Err(("measurement_type", 0));
+    }
+    Ok(())
+}
+
+fn measurement_name(measurement_type: &MeasurementType) -> String {
+    match measurement_type {
+        MeasurementType::Custom { name, .. } => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Registers a new measurement for the session, walking it through
+/// `Starting` → `Running`, and starts its simulated data stream. Both
+/// transitions are reported to the client.
+fn start_measurement(
+    state: &AppState,
+    session: &mut UserSession,
+    config: MeasurementConfig,
+    data_tx: &mpsc::Sender<MultiUserMessage>,
+) -> Vec<MultiUserMessage> {
+    let now = now_us();
+    let measurement_id = Uuid::new_v4();
+    let mut measurement = MeasurementSession {
+        measurement_id,
+        user_id: session.user_id,
+        name: measurement_name(&config.measurement_type),
+        measurement_type: config.measurement_type.clone(),
+        config: config.clone(),
+        status: MeasurementStatus::Starting,
+        started_at: now,
+        sample_count: 0,
+        last_sample_time: 0,
+    };
+    session.active_measurements.push(measurement_id);
+    session.resource_usage.active_measurements = session.active_measurements.len() as u8;
+
+    let mut replies = vec![MultiUserMessage::MeasurementStatus { measurement_id, status: MeasurementStatus::Starting }];
+    measurement.status = MeasurementStatus::Running;
+    replies.push(MultiUserMessage::MeasurementStatus { measurement_id, status: MeasurementStatus::Running });
+    state.measurements.lock().unwrap().insert(measurement_id, measurement);
+    measurement_sim::spawn_generator(measurement_id, config, state.measurements.clone(), data_tx.clone(), state.seed);
+    replies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use leptos_rust::multi_user_types::{ChannelConfig, DataRetention};
+
+    fn login(state: &AppState, session_id: &mut Option<Uuid>, password: &str) -> Vec<MultiUserMessage> {
+        let (data_tx, _) = mpsc::channel(DATA_BUFFER);
+        let cmd = MultiUserMessage::UserLogin { username: "demo".into(), password: password.into() };
+        handle_command(state, session_id, &data_tx, cmd)
+    }
+
+    fn command(state: &AppState, session_id: &mut Option<Uuid>, cmd: MultiUserMessage) -> Vec<MultiUserMessage> {
+        let (data_tx, _) = mpsc::channel(DATA_BUFFER);
+        handle_command(state, session_id, &data_tx, cmd)
+    }
+
+    fn config(measurement_type: MeasurementType, sample_rate_hz: u32) -> MeasurementConfig {
+        MeasurementConfig {
+            measurement_type,
+            sample_rate_hz,
+            duration_seconds: None,
+            channels: vec![ChannelConfig {
+                channel_id: 0,
+                name: "ch0".into(),
+                unit: "V".into(),
+                range_min: -1.0,
+                range_max: 1.0,
+                enabled: true,
+            }],
+            trigger_config: None,
+            auto_scale: false,
+            data_retention: DataRetention::KeepAll,
+        }
+    }
+
+    /// Logged in session as registered by `UserLogin`
+    fn session(state: &AppState) -> (Option<Uuid>, UserSession) {
+        let mut session_id = None;
+        login(state, &mut session_id, "demo");
+        let session = state.sessions.lock().unwrap()[&session_id.unwrap()].clone();
+        (session_id, session)
+    }
+
+    fn error_code(replies: &[MultiUserMessage]) -> Option<u32> {
+        match replies {
+            [MultiUserMessage::Error { error_code, .. }] => Some(*error_code),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn login_starts_a_session() {
+        let state = AppState::for_tests();
+        let mut session_id = None;
+        let replies = login(&state, &mut session_id, "demo");
+        let [MultiUserMessage::SessionStarted { session }] = replies.as_slice() else { panic!("{:?}", replies) };
+        assert_eq!(session.username, "demo");
+        assert_eq!(session_id, Some(session.session_id));
+        assert!(state.sessions.lock().unwrap().contains_key(&session.session_id));
+    }
+
+    #[test]
+    fn wrong_password_is_unauthorized() {
+        let state = AppState::for_tests();
+        let mut session_id = None;
+        let replies = login(&state, &mut session_id, "wrong");
+        assert_eq!(error_code(&replies), Some(ErrorCode::Unauthorized.code()));
+        assert_eq!(session_id, None);
+        assert!(state.sessions.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn commands_without_session_are_unauthorized() {
+        let state = AppState::for_tests();
+        let replies = command(&state, &mut None, MultiUserMessage::SessionHeartbeat);
+        assert_eq!(error_code(&replies), Some(ErrorCode::Unauthorized.code()));
+    }
+
+    #[test]
+    fn heartbeat_refreshes_last_activity() {
+        let state = AppState::for_tests();
+        let mut session_id = None;
+        login(&state, &mut session_id, "demo");
+        let id = session_id.unwrap();
+        state.sessions.lock().unwrap().get_mut(&id).unwrap().last_activity = 0;
+        assert!(command(&state, &mut session_id, MultiUserMessage::SessionHeartbeat).is_empty());
+        assert!(state.sessions.lock().unwrap()[&id].last_activity > 0);
+    }
+
+    #[test]
+    fn logout_removes_the_session() {
+        let state = AppState::for_tests();
+        let mut session_id = None;
+        login(&state, &mut session_id, "demo");
+        let replies = command(&state, &mut session_id, MultiUserMessage::UserLogout);
+        assert!(matches!(replies.as_slice(), [MultiUserMessage::UserLogout]));
+        assert_eq!(session_id, None);
+        assert!(state.sessions.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn sample_rate_above_limit_is_rejected() {
+        let state = AppState::for_tests();
+        let (mut session_id, session) = session(&state);
+        let max = session.permissions.max_sample_rate_hz;
+        assert_eq!(check_permissions(&session, &config(MeasurementType::Voltage, max + 1)), Err(("sample_rate_hz", max as u64)));
+        // Through the command, nothing is started
+        let cmd = MultiUserMessage::StartMeasurement { config: config(MeasurementType::Voltage, max + 1) };
+        let replies = command(&state, &mut session_id, cmd);
+        assert!(matches!(
+            replies.as_slice(),
+            [MultiUserMessage::ResourceLimitExceeded { resource, limit }] if resource == "sample_rate_hz" && *limit == max as u64
+        ));
+        assert!(state.measurements.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn too_many_concurrent_measurements_are_rejected() {
+        let state = AppState::for_tests();
+        let (_, mut session) = session(&state);
+        let max = session.permissions.max_concurrent_measurements;
+        session.active_measurements = (0..max).map(|_| Uuid::new_v4()).collect();
+        assert_eq!(
+            check_permissions(&session, &config(MeasurementType::Voltage, 100)),
+            Err(("concurrent_measurements", max as u64))
+        );
+        session.active_measurements.pop();
+        assert_eq!(check_permissions(&session, &config(MeasurementType::Voltage, 100)), Ok(()));
+    }
+
+    #[test]
+    fn measurement_type_not_allowed_is_rejected() {
+        let state = AppState::for_tests();
+        let (_, session) = session(&state);
+        let custom = MeasurementType::Custom { name: "strain".into(), unit: "µε".into() };
+        assert_eq!(check_permissions(&session, &config(custom, 100)), Err(("measurement_type", 0)));
+    }
+
+    fn measurement(user_id: Uuid, config: MeasurementConfig, status: MeasurementStatus) -> (MeasurementId, MeasurementSession) {
+        let measurement_id = Uuid::new_v4();
+        let m = MeasurementSession {
+            measurement_id,
+            user_id,
+            name: measurement_name(&config.measurement_type),
+            measurement_type: config.measurement_type.clone(),
+            config,
+            status,
+            started_at: 0,
+            sample_count: 0,
+            last_sample_time: 0,
+        };
+        (measurement_id, m)
+    }
+
+    #[test]
+    fn system_stats_aggregate_running_measurements() {
+        let state = AppState::for_tests();
+        // Two sessions of the same user count as one active user
+        let (_, first) = session(&state);
+        let (_, second) = session(&state);
+        assert_eq!(first.user_id, second.user_id);
+        let sessions = state.sessions.lock().unwrap().clone();
+        assert_eq!(sessions.len(), 2);
+
+        let mut two_channels = config(MeasurementType::Current, 1000);
+        two_channels.channels.push(ChannelConfig { channel_id: 1, name: "ch1".into(), ..two_channels.channels[0].clone() });
+        two_channels.channels.push(ChannelConfig { channel_id: 2, enabled: false, ..two_channels.channels[0].clone() });
+        let measurements = HashMap::from([
+            measurement(first.user_id, config(MeasurementType::Voltage, 100), MeasurementStatus::Running),
+            measurement(first.user_id, two_channels, MeasurementStatus::Running),
+            measurement(first.user_id, config(MeasurementType::Voltage, 5000), MeasurementStatus::Paused),
+            measurement(first.user_id, config(MeasurementType::Voltage, 7000), MeasurementStatus::Completed),
+        ]);
+
+        let stats = system_stats(3, &sessions, &measurements);
+        assert_eq!((stats.total_users, stats.active_users), (3, 1));
+        assert_eq!((stats.total_measurements, stats.active_measurements), (4, 2));
+        assert_eq!(stats.total_samples_per_second, 1100);
+        // Disabled channels aren't sent
+        let expected = 100 * (SAMPLE_BYTES + CHANNEL_BYTES) * 8 + 1000 * (SAMPLE_BYTES + 2 * CHANNEL_BYTES) * 8;
+        assert_eq!(stats.network_throughput_bps, expected);
+        assert_eq!((stats.cpu_usage_percent, stats.memory_usage_mb), (0.0, 0));
+    }
+
+    #[test]
+    fn config_update_changes_rate_and_channels() {
+        let state = AppState::for_tests();
+        let (mut session_id, session) = session(&state);
+        let (id, m) = measurement(session.user_id, config(MeasurementType::Voltage, 100), MeasurementStatus::Running);
+        state.measurements.lock().unwrap().insert(id, m);
+        state.sessions.lock().unwrap().get_mut(&session_id.unwrap()).unwrap().active_measurements.push(id);
+
+        let mut update = config(MeasurementType::Voltage, 50);
+        update.channels[0].name = "renamed".into();
+        update.auto_scale = true;
+        let replies = command(&state, &mut session_id, MultiUserMessage::UpdateMeasurementConfig { measurement_id: id, config: update });
+        let [MultiUserMessage::UpdateMeasurementConfig { config: adopted, .. }] = replies.as_slice() else { panic!("{:?}", replies) };
+        // Only the rate and the channels are adopted
+        assert_eq!((adopted.sample_rate_hz, adopted.channels[0].name.as_str(), adopted.auto_scale), (50, "renamed", false));
+        assert_eq!(state.measurements.lock().unwrap()[&id].config.sample_rate_hz, 50);
+
+        let cmd = MultiUserMessage::UpdateMeasurementConfig { measurement_id: id, config: config(MeasurementType::Current, 50) };
+        assert_eq!(error_code(&command(&state, &mut session_id, cmd)), Some(ErrorCode::InvalidConfig.code()));
+        let cmd = MultiUserMessage::UpdateMeasurementConfig { measurement_id: Uuid::new_v4(), config: config(MeasurementType::Voltage, 50) };
+        assert_eq!(error_code(&command(&state, &mut session_id, cmd)), Some(ErrorCode::NotFound.code()));
+    }
+
+    #[test]
+    fn system_stats_without_sessions() {
+        let stats = system_stats(1, &HashMap::new(), &HashMap::new());
+        assert_eq!((stats.total_users, stats.active_users, stats.total_measurements), (1, 0, 0));
+        assert_eq!((stats.total_samples_per_second, stats.network_throughput_bps), (0, 0));
+    }
+}
@@ -0,0 +1,54 @@
+use tokio::time::Instant;
+
+/// Token bucket: holds up to `capacity` tokens and refills `rate` tokens per
+/// second, so short bursts pass while the sustained rate stays bounded.
+pub struct TokenBucket {
+    capacity: f64,
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    /// Starts full
+    pub fn new(capacity: f64, rate: f64) -> Self {
+        Self { capacity, rate, tokens: capacity, last: Instant::now() }
+    }
+
+    /// Takes one token; `false` if the bucket is empty
+    pub fn try_take(&mut self) -> bool {
+        let now = Instant::now();
+        let refill = now.duration_since(self.last).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refill).min(self.capacity);
+        self.last = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn empty_bucket_rejects_until_refilled() {
+        let mut bucket = TokenBucket::new(3.0, 2.0);
+        assert!((0..3).all(|_| bucket.try_take()));
+        assert!(!bucket.try_take());
+
+        // 2 tokens per second: one back after 500 ms
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert!(bucket.try_take());
+        assert!(!bucket.try_take());
+
+        // Refills never exceed the capacity
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!((0..3).all(|_| bucket.try_take()));
+        assert!(!bucket.try_take());
+    }
+}
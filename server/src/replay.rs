@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::broadcast;
+
+use crate::health::Health;
+
+/// Playback speed factor, stored as `f64` bits so it fits in an atomic
+#[derive(Clone)]
+pub struct ReplaySpeed(Arc<AtomicU64>);
+
+impl ReplaySpeed {
+    pub fn new(speed: f64) -> Self {
+        Self(Arc::new(AtomicU64::new(speed.to_bits())))
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    /// 1.0 = original pace, 2.0 = twice as fast
+    pub fn set(&self, speed: f64) {
+        if speed.is_finite() {
+            self.0.store(speed.clamp(0.01, 100.0).to_bits(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// Replays a newline-delimited JSON log of broadcast messages, honoring the
+/// `ts` (micros) deltas between lines scaled by `speed`. Lines without `ts`
+/// are sent right away. Starts over at the end of the file until `shutdown` is set.
+pub fn start_replay(
+    path: PathBuf,
+    tx: broadcast::Sender<String>,
+    speed: ReplaySpeed,
+    shutdown: Arc<AtomicBool>,
+    health: Arc<Health>,
+) {
+    tokio::spawn(async move {
+        while !shutdown.load(Ordering::Relaxed) {
+            if let Err(e) = replay_file(&path, &tx, &speed, &shutdown, &health).await {
+                eprintln!("Replay of {} failed: {}", path.display(), e);
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    });
+}
+
+async fn replay_file(
+    path: &PathBuf,
+    tx: &broadcast::Sender<String>,
+    speed: &ReplaySpeed,
+    shutdown: &AtomicBool,
+    health: &Health,
+) -> std::io::Result<()> {
+    #[derive(serde::Deserialize)]
+    struct TsOnly {
+        ts: Option<i64>,
+    }
+
+    let file = tokio::fs::File::open(path).await?;
+    let mut lines = BufReader::new(file).lines();
+    let mut last_ts: Option<i64> = None;
+    let mut sent = 0u64;
+    while let Some(line) = lines.next_line().await? {
+        if shutdown.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let ts = serde_json::from_str::<TsOnly>(line).ok().and_then(|m| m.ts);
+        if let (Some(prev), Some(ts)) = (last_ts, ts) {
+            let delta_us = (ts - prev).max(0) as f64 / speed.get();
+            if delta_us >= 1.0 {
+                tokio::time::sleep(Duration::from_micros(delta_us as u64)).await;
+            }
+        }
+        if ts.is_some() {
+            last_ts = ts;
+        }
+        let _ = tx.send(line.to_string());
+        health.message_sent();
+        sent += 1;
+    }
+    if sent == 0 {
+        // Nothing to play; avoid spinning on an empty file
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    Ok(())
+}
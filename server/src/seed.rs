@@ -0,0 +1,23 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Base seed from `SEED`; `None` (fresh random data every run) when unset
+/// or not a number
+pub fn from_env() -> Option<u64> {
+    std::env::var("SEED").ok()?.trim().parse().ok()
+}
+
+/// Generator for one simulated stream. With a base seed every stream gets
+/// its own seed derived from it and the stream's name, so each replays the
+/// same values run to run however the tasks interleave; without one it is
+/// seeded from the OS.
+pub fn stream_rng(seed: Option<u64>, stream: &str) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed ^ fnv1a(stream)),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// FNV-1a, unlike `DefaultHasher` stable across Rust versions
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
@@ -0,0 +1,92 @@
+use app::PriceHistory;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use crate::{error_response, AppState};
+use leptos_rust::error_code::ErrorCode;
+
+/// Ticks kept per symbol for `/history`, also the largest `limit` served
+const HISTORY_LEN: usize = 1000;
+/// Ticks returned by `/history` without a `limit`
+const HISTORY_DEFAULT_LIMIT: usize = 200;
+
+/// Lifetime statistics of one symbol's price ticks, plus its most recent
+/// ticks for `/history`
+#[derive(Clone, Debug, Serialize)]
+pub struct SymbolStat {
+    pub count: u64,
+    pub last_price: f64,
+    pub min: f64,
+    pub max: f64,
+    pub first_ts: i64, // micros since the epoch, like the tick's `ts`
+    pub last_ts: i64,
+    #[serde(skip)]
+    pub history: VecDeque<(i64, f64)>, // (ts, price), oldest first, at most HISTORY_LEN
+}
+
+/// Symbol → tick statistics, shared by the price streams and `/stats`
+pub type SymbolStats = Arc<Mutex<HashMap<String, SymbolStat>>>;
+
+/// Folds one price tick into the statistics of `symbol`
+pub fn record(stats: &SymbolStats, symbol: &str, price: f64, ts: i64) {
+    let mut stats = stats.lock().unwrap();
+    match stats.get_mut(symbol) {
+        Some(stat) => {
+            stat.count += 1;
+            stat.last_price = price;
+            stat.min = stat.min.min(price);
+            stat.max = stat.max.max(price);
+            stat.last_ts = ts;
+            if stat.history.len() == HISTORY_LEN {
+                stat.history.pop_front();
+            }
+            stat.history.push_back((ts, price));
+        }
+        None => {
+            stats.insert(
+                symbol.to_string(),
+                SymbolStat {
+                    count: 1,
+                    last_price: price,
+                    min: price,
+                    max: price,
+                    first_ts: ts,
+                    last_ts: ts,
+                    history: VecDeque::from([(ts, price)]),
+                },
+            );
+        }
+    }
+}
+
+/// Returns the statistics of all symbols seen so far
+pub async fn stats_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let stats = state.symbol_stats.lock().unwrap().clone();
+    Json(stats)
+}
+
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    symbol: String,
+    limit: Option<usize>,
+}
+
+/// Returns the last `limit` (default 200, at most 1000) price ticks of
+/// `symbol`, so a client can fill its charts before live updates arrive.
+/// Unknown symbols are a 404.
+pub async fn history_handler(State(state): State<AppState>, Query(query): Query<HistoryQuery>) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(HISTORY_DEFAULT_LIMIT).min(HISTORY_LEN);
+    let stats = state.symbol_stats.lock().unwrap();
+    let Some(stat) = stats.get(&query.symbol) else {
+        return error_response(StatusCode::NOT_FOUND, ErrorCode::UnknownSymbol, format!("unknown symbol {}", query.symbol));
+    };
+    let skip = stat.history.len().saturating_sub(limit);
+    Json(PriceHistory { symbol: query.symbol, prices: stat.history.iter().skip(skip).copied().collect() }).into_response()
+}
@@ -0,0 +1,196 @@
+use app::SymbolInfo;
+use axum::{extract::State, http::StatusCode, response::{IntoResponse, Response}, Json};
+use leptos_rust::error_code::ErrorCode;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::live_data::LiveDataClient;
+use crate::{error_response, seed, AppState, DataSource};
+
+/// Longest symbol accepted on `/live/subscribe` and `/live/unsubscribe`
+const MAX_SYMBOL_LEN: usize = 20;
+/// Markets streamed in live mode when `SYMBOLS` is unset
+const DEFAULT_LIVE: [&str; 3] = ["btcusdt", "ethusdt", "solusdt"];
+/// Starting prices of the built-in simulated symbols
+const DEFAULT_PRICES: [(&str, f64); 5] = [
+    ("BTC/USD", 45000.0),
+    ("ETH/USD", 2500.0),
+    ("SOL/USD", 120.0),
+    ("AAPL", 175.0),
+    ("TSLA", 250.0),
+];
+
+/// One configured market
+#[derive(Clone, Debug)]
+pub struct SymbolSpec {
+    /// Name used in messages, e.g. "BTC/USD"
+    pub name: String,
+    /// Binance stream name, e.g. "btcusdt"
+    pub stream: String,
+    /// Start of the simulated random walk
+    pub base_price: f64,
+}
+
+/// Reads `SYMBOLS=btcusdt,ethusdt,dogeusdt=0.12`: Binance market names, each
+/// optionally followed by `=<base price>` for the simulation. Names with a
+/// slash (`BTC/USD=45000`) are used as is. Without `SYMBOLS` the built-in
+/// symbols of the respective mode are used.
+pub fn from_env(live: bool) -> Vec<SymbolSpec> {
+    let parsed = std::env::var("SYMBOLS").map(|spec| parse(&spec)).unwrap_or_default();
+    if !parsed.is_empty() {
+        return parsed;
+    }
+    if live {
+        DEFAULT_LIVE.iter().map(|s| spec(s, None)).collect()
+    } else {
+        app::SYMBOLS.iter().map(|s| spec(s, None)).collect()
+    }
+}
+
+fn parse(list: &str) -> Vec<SymbolSpec> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once('=') {
+            Some((raw, price)) => {
+                let price = price.trim().parse::<f64>().ok().filter(|p| p.is_finite() && *p > 0.0);
+                if price.is_none() {
+                    eprintln!("Ignoring invalid base price in SYMBOLS entry {:?}", entry);
+                }
+                spec(raw.trim(), price)
+            }
+            None => spec(entry, None),
+        })
+        .collect()
+}
+
+fn spec(raw: &str, base_price: Option<f64>) -> SymbolSpec {
+    let name = if raw.contains('/') {
+        raw.to_uppercase()
+    } else {
+        LiveDataClient::normalize_symbol(raw)
+    };
+    // Unknown symbols start somewhere plausible instead of at zero
+    let base_price = base_price
+        .or_else(|| DEFAULT_PRICES.iter().find(|(n, _)| *n == name).map(|(_, p)| *p))
+        .unwrap_or_else(|| (seed::stream_rng(seed::from_env(), &name).gen_range(10.0..1000.0_f64) * 100.0).round() / 100.0);
+    SymbolSpec {
+        stream: raw.replace('/', "").to_lowercase(),
+        name,
+        base_price,
+    }
+}
+
+/// Names of the markets whose live ticker stream is connected
+#[derive(Clone, Debug, Default)]
+pub struct ConnectedSymbols(Arc<Mutex<HashSet<String>>>);
+
+impl ConnectedSymbols {
+    /// Lists `name` as connected until the guard is dropped
+    pub fn connected(&self, name: &str) -> ConnectedGuard {
+        self.0.lock().unwrap().insert(name.to_string());
+        ConnectedGuard { symbols: self.clone(), name: name.to_string() }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.lock().unwrap().contains(name)
+    }
+}
+
+pub struct ConnectedGuard {
+    symbols: ConnectedSymbols,
+    name: String,
+}
+
+impl Drop for ConnectedGuard {
+    fn drop(&mut self) {
+        self.symbols.0.lock().unwrap().remove(&self.name);
+    }
+}
+
+/// Decimals that show at least four significant digits of `price`
+fn decimals_hint(price: f64) -> usize {
+    if !(price.is_finite() && price > 0.0) {
+        return 0;
+    }
+    (3 - price.log10().floor() as i32).clamp(0, 8) as usize
+}
+
+/// Lists the symbols clients can subscribe to, in configuration order. In
+/// live mode only those whose Binance stream is connected; empty when
+/// replaying, since a recording's symbols aren't known up front.
+pub async fn symbols_handler(State(state): State<AppState>) -> impl IntoResponse {
+    // Live mode lists the markets streamed now, including added ones
+    let specs = state.live.as_ref().map_or_else(|| state.symbol_specs.to_vec(), |live| live.streams.specs());
+    let stats = state.symbol_stats.lock().unwrap();
+    let symbols: Vec<SymbolInfo> = specs
+        .iter()
+        .filter(|spec| match state.data_source {
+            DataSource::Simulated => true,
+            DataSource::Live => state.connected_symbols.contains(&spec.name),
+            DataSource::Replay { .. } => false,
+        })
+        .map(|spec| SymbolInfo {
+            symbol: spec.name.clone(),
+            base_price: spec.base_price,
+            // Simulated prices are rounded to cents, so a zero tick says nothing
+            decimals: decimals_hint(
+                stats.get(&spec.name).map(|s| s.last_price).filter(|p| *p > 0.0).unwrap_or(spec.base_price),
+            ),
+        })
+        .collect();
+    Json(symbols)
+}
+
+/// Body of `/live/subscribe` and `/live/unsubscribe`: a Binance market
+/// (`dogeusdt`) or the name it is streamed under (`DOGE/USDT`)
+#[derive(Deserialize)]
+pub struct LiveSymbolRequest {
+    symbol: String,
+}
+
+/// Spec of the market in a request; 400 unless it's letters and digits,
+/// with an optional slash, and at most [`MAX_SYMBOL_LEN`] long
+fn live_spec(req: &LiveSymbolRequest) -> Result<SymbolSpec, String> {
+    let raw = req.symbol.trim();
+    if raw.is_empty() || raw.len() > MAX_SYMBOL_LEN || !raw.chars().all(|c| c.is_ascii_alphanumeric() || c == '/') {
+        return Err(format!("invalid symbol {:?}", req.symbol));
+    }
+    Ok(spec(raw, None))
+}
+
+fn not_live() -> Response {
+    error_response(StatusCode::CONFLICT, ErrorCode::Conflict, "only the streams of live mode can be changed")
+}
+
+/// Starts streaming the ticker and book of another Binance market and
+/// answers the streamed symbols. 409 if it is streamed already, 507 once
+/// [`MAX_LIVE_SYMBOLS`](crate::live_data::MAX_LIVE_SYMBOLS) markets are streamed.
+pub async fn live_subscribe_handler(State(state): State<AppState>, Json(req): Json<LiveSymbolRequest>) -> Response {
+    let Some(live) = &state.live else { return not_live() };
+    let spec = match live_spec(&req) {
+        Ok(spec) => spec,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, ErrorCode::InvalidMessage, e),
+    };
+    match live.subscribe(spec) {
+        Ok(()) => Json(live.streams.names()).into_response(),
+        Err((code @ ErrorCode::ResourceLimit, e)) => error_response(StatusCode::INSUFFICIENT_STORAGE, code, e),
+        Err((code, e)) => error_response(StatusCode::CONFLICT, code, e),
+    }
+}
+
+/// Stops streaming a market and answers the remaining symbols. 404 if it
+/// isn't streamed.
+pub async fn live_unsubscribe_handler(State(state): State<AppState>, Json(req): Json<LiveSymbolRequest>) -> Response {
+    let Some(live) = &state.live else { return not_live() };
+    let spec = match live_spec(&req) {
+        Ok(spec) => spec,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, ErrorCode::InvalidMessage, e),
+    };
+    match live.unsubscribe(&spec.name) {
+        Ok(()) => Json(live.streams.names()).into_response(),
+        Err(e) => error_response(StatusCode::NOT_FOUND, ErrorCode::UnknownSymbol, e),
+    }
+}
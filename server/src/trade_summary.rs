@@ -0,0 +1,107 @@
+use app::WireMessage;
+use std::collections::BTreeMap;
+
+/// Running sums of one symbol's trades in the current second
+#[derive(Default)]
+struct Window {
+    count: u64,
+    volume: f64,
+    notional: f64, // Σ price × size
+    buy_volume: f64,
+}
+
+/// Sums trades per symbol and second into `TradeSummary` messages. A
+/// second's summaries are emitted by the first trade of a later second, so a
+/// symbol without trades in a second gets no summary for it.
+#[derive(Default)]
+pub struct TradeAggregator {
+    second: i64,
+    windows: BTreeMap<String, Window>,
+}
+
+impl TradeAggregator {
+    /// Adds a trade at `ts` (micros) and returns the summaries of the
+    /// seconds it closed, by symbol. Trades late for an emitted second count
+    /// towards the current one.
+    pub fn record(&mut self, symbol: &str, price: f64, size: f64, buy: bool, ts: i64) -> Vec<WireMessage> {
+        let second = ts.div_euclid(1_000_000);
+        let done = if second > self.second {
+            let done = self.flush();
+            self.second = second;
+            done
+        } else {
+            Vec::new()
+        };
+        let window = self.windows.entry(symbol.to_string()).or_default();
+        window.count += 1;
+        window.volume += size;
+        window.notional += price * size;
+        if buy {
+            window.buy_volume += size;
+        }
+        done
+    }
+
+    fn flush(&mut self) -> Vec<WireMessage> {
+        let ts = self.second * 1_000_000;
+        std::mem::take(&mut self.windows)
+            .into_iter()
+            .filter(|(_, w)| w.volume > 0.0)
+            .map(|(symbol, w)| WireMessage::TradeSummary {
+                symbol,
+                count: w.count,
+                volume: w.volume,
+                vwap: w.notional / w.volume,
+                buy_ratio: w.buy_volume / w.volume,
+                ts,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: i64 = 1_000_000;
+
+    fn summary(symbol: &str, count: u64, volume: f64, vwap: f64, buy_ratio: f64, ts: i64) -> WireMessage {
+        WireMessage::TradeSummary { symbol: symbol.into(), count, volume, vwap, buy_ratio, ts }
+    }
+
+    #[test]
+    fn summaries_per_symbol_and_second() {
+        let mut agg = TradeAggregator::default();
+        assert!(agg.record("BTC/USD", 100.0, 2.0, true, 5 * S).is_empty());
+        assert!(agg.record("ETH/USD", 50.0, 1.0, true, 5 * S + 10).is_empty());
+        assert!(agg.record("BTC/USD", 110.0, 2.0, false, 6 * S - 1).is_empty());
+
+        // The first trade of second 6 closes second 5, symbols in order
+        let done = agg.record("BTC/USD", 200.0, 1.0, false, 6 * S);
+        assert_eq!(
+            done,
+            [summary("BTC/USD", 2, 4.0, 105.0, 0.5, 5 * S), summary("ETH/USD", 1, 1.0, 50.0, 1.0, 5 * S)]
+        );
+    }
+
+    #[test]
+    fn late_trades_and_empty_seconds() {
+        let mut agg = TradeAggregator::default();
+        agg.record("BTC/USD", 100.0, 1.0, true, 6 * S);
+        // Older than the open second: counts towards second 6
+        agg.record("BTC/USD", 100.0, 1.0, true, 5 * S);
+        // Nothing traded in second 7, so no summary for it
+        let done = agg.record("BTC/USD", 100.0, 1.0, true, 8 * S);
+        assert_eq!(done, [summary("BTC/USD", 2, 2.0, 100.0, 1.0, 6 * S)]);
+        assert!(agg.record("BTC/USD", 100.0, 1.0, true, 8 * S + 1).is_empty());
+    }
+
+    #[test]
+    fn windows_without_volume_are_dropped() {
+        let mut agg = TradeAggregator::default();
+        agg.record("BTC/USD", 100.0, 0.0, true, S);
+        agg.record("ETH/USD", 10.0, 3.0, false, S);
+        let done = agg.record("BTC/USD", 100.0, 1.0, true, 2 * S);
+        assert_eq!(done, [summary("ETH/USD", 1, 3.0, 10.0, 0.0, S)]);
+    }
+}
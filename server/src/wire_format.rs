@@ -0,0 +1,42 @@
+use axum::extract::ws::Message;
+use app::WireMessage;
+
+/// Encoding of the frames on one `/ws` connection, chosen with `?fmt=`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WireFormat {
+    /// JSON text frames
+    #[default]
+    Json,
+    /// MessagePack binary frames (`?fmt=msgpack`), structs encoded as maps
+    MsgPack,
+}
+
+impl WireFormat {
+    pub fn from_query(fmt: Option<&str>) -> Self {
+        match fmt {
+            Some("msgpack") => WireFormat::MsgPack,
+            _ => WireFormat::Json,
+        }
+    }
+
+    /// Turns a serialized JSON message into a frame of this format. Broadcast
+    /// messages are serialized once as JSON, so MessagePack connections pay
+    /// for a re-encode here; the client saves the JSON parse in exchange.
+    pub fn frame(self, json: String) -> Option<Message> {
+        match self {
+            WireFormat::Json => Some(Message::Text(json.into())),
+            WireFormat::MsgPack => {
+                let msg: WireMessage = serde_json::from_str(&json).ok()?;
+                self.encode(&msg)
+            }
+        }
+    }
+
+    pub fn encode(self, msg: &WireMessage) -> Option<Message> {
+        match self {
+            WireFormat::Json => serde_json::to_string(msg).ok().map(|s| Message::Text(s.into())),
+            // Named fields keep the `type` tag, which the enum needs to decode
+            WireFormat::MsgPack => rmp_serde::to_vec_named(msg).ok().map(|b| Message::Binary(b.into())),
+        }
+    }
+}
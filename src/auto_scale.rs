@@ -0,0 +1,153 @@
+// 📏 Auto-Scaling of Measurement Channels
+
+use crate::multi_user_types::MeasurementConfig;
+use std::collections::HashMap;
+
+/// Observed envelope of one channel
+#[derive(Clone, Copy, Debug)]
+struct Envelope {
+    min: f64,
+    max: f64,
+}
+
+/// Display ranges for `MeasurementConfig::auto_scale`.
+///
+/// Tracks a min/max envelope per channel: a new extreme widens it at once,
+/// a sample inside moves both bounds towards it by `1 / window` of their
+/// distance. A one-off spike therefore fades out over a few `window`s of
+/// samples (to under half after `window`, ~1% after `5 * window`) instead of
+/// widening the range for good.
+#[derive(Clone, Debug)]
+pub struct AutoScaler {
+    window: usize,
+    channels: HashMap<u8, Envelope>,
+    /// Ranges reported before a channel's first sample
+    fallback: HashMap<u8, (f64, f64)>,
+}
+
+impl AutoScaler {
+    /// `window` is the decay time constant in samples (at least 1)
+    pub fn new(window: usize) -> Self {
+        Self { window: window.max(1), channels: HashMap::new(), fallback: HashMap::new() }
+    }
+
+    /// Scaler for a measurement, or `None` if `auto_scale` is off. The
+    /// configured `range_min`/`range_max` apply until a channel has data.
+    pub fn from_config(config: &MeasurementConfig, window: usize) -> Option<Self> {
+        if !config.auto_scale {
+            return None;
+        }
+        let mut scaler = Self::new(window);
+        scaler.fallback = config
+            .channels
+            .iter()
+            .map(|ch| (ch.channel_id, (ch.range_min, ch.range_max)))
+            .collect();
+        Some(scaler)
+    }
+
+    /// Feeds one sample; non-finite values are ignored
+    pub fn observe(&mut self, channel_id: u8, v: f64) {
+        if !v.is_finite() {
+            return;
+        }
+        let k = 1.0 / self.window as f64;
+        self.channels
+            .entry(channel_id)
+            .and_modify(|env| {
+                if v >= env.max {
+                    env.max = v;
+                } else if v <= env.min {
+                    env.min = v;
+                } else {
+                    env.max -= (env.max - v) * k;
+                    env.min += (v - env.min) * k;
+                }
+            })
+            .or_insert(Envelope { min: v, max: v });
+    }
+
+    /// Current `(min, max)` display range of a channel. A flat signal gets a
+    /// span of 1 around its value, like the sparkline normalization; unknown
+    /// channels get their configured range or `(0, 1)`.
+    pub fn range(&self, channel_id: u8) -> (f64, f64) {
+        match self.channels.get(&channel_id) {
+            Some(env) if env.max - env.min < 1e-9 => (env.min - 0.5, env.max + 0.5),
+            Some(env) => (env.min, env.max),
+            None => self.fallback.get(&channel_id).copied().unwrap_or((0.0, 1.0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multi_user_types::{ChannelConfig, DataRetention, MeasurementType};
+
+    #[test]
+    fn spike_widens_at_once_and_decays() {
+        let mut scaler = AutoScaler::new(10);
+        scaler.observe(0, -1.0);
+        scaler.observe(0, 1.0);
+        scaler.observe(0, 100.0);
+        assert_eq!(scaler.range(0), (-1.0, 100.0));
+
+        // Each sample inside moves the bounds by 1/10 towards it
+        scaler.observe(0, 0.0);
+        let (min, max) = scaler.range(0);
+        assert!((min - -0.9).abs() < 1e-12 && (max - 90.0).abs() < 1e-12, "{:?}", (min, max));
+        for _ in 1..10 {
+            scaler.observe(0, 0.0);
+        }
+        // Under half after `window` samples
+        assert!(scaler.range(0).1 < 50.0);
+        for _ in 10..50 {
+            scaler.observe(0, 0.0);
+        }
+        // About 1% after `5 * window`
+        assert!(scaler.range(0).1 < 1.0);
+        // A new extreme is adopted as is
+        scaler.observe(0, -5.0);
+        assert_eq!(scaler.range(0).0, -5.0);
+    }
+
+    #[test]
+    fn flat_and_non_finite_values() {
+        let mut scaler = AutoScaler::new(0);
+        scaler.observe(1, f64::NAN);
+        scaler.observe(1, f64::INFINITY);
+        assert_eq!(scaler.range(1), (0.0, 1.0));
+        scaler.observe(1, 3.0);
+        scaler.observe(1, f64::NEG_INFINITY);
+        assert_eq!(scaler.range(1), (2.5, 3.5));
+    }
+
+    #[test]
+    fn configured_range_until_first_sample() {
+        let mut config = MeasurementConfig {
+            measurement_type: MeasurementType::Voltage,
+            sample_rate_hz: 100,
+            duration_seconds: None,
+            channels: vec![ChannelConfig {
+                channel_id: 2,
+                name: "ch2".into(),
+                unit: "V".into(),
+                range_min: -10.0,
+                range_max: 10.0,
+                enabled: true,
+            }],
+            trigger_config: None,
+            auto_scale: false,
+            data_retention: DataRetention::KeepAll,
+        };
+        assert!(AutoScaler::from_config(&config, 10).is_none());
+
+        config.auto_scale = true;
+        let mut scaler = AutoScaler::from_config(&config, 10).unwrap();
+        assert_eq!(scaler.range(2), (-10.0, 10.0));
+        assert_eq!(scaler.range(3), (0.0, 1.0));
+        scaler.observe(2, 1.0);
+        scaler.observe(2, 2.0);
+        assert_eq!(scaler.range(2), (1.0, 2.0));
+    }
+}
@@ -0,0 +1,198 @@
+// 📄 DBC Loader für CAN Signal Datenbanken
+
+use crate::can_types::{valid_can_id, ByteOrder, CANSignal};
+use std::fmt;
+
+/// Fehler beim Laden einer DBC Datei
+#[derive(Debug)]
+pub enum DbcError {
+    Io(std::io::Error),
+    /// `line` ist 1-basiert
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for DbcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbcError::Io(e) => write!(f, "DBC read error: {}", e),
+            DbcError::Parse { line, message } => write!(f, "DBC line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for DbcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DbcError::Io(e) => Some(e),
+            DbcError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DbcError {
+    fn from(e: std::io::Error) -> Self {
+        DbcError::Io(e)
+    }
+}
+
+/// Lädt alle Signale einer DBC Datei, siehe [`parse_dbc`]
+pub fn load_dbc(path: &str) -> Result<Vec<CANSignal>, DbcError> {
+    parse_dbc(&std::fs::read_to_string(path)?)
+}
+
+/// Parst `BO_` (Messages) und `SG_` (Signale) aus DBC Text.
+///
+/// Unterstützt wird nur was `CANSignal` abbildet: Bit-Layout, Byte Order
+/// (`@1` = Intel/`LittleEndian`, `@0` = Motorola/`BigEndian`), Faktor/Offset,
+/// `[min|max]`, Vorzeichen (`+`/`-`) und Einheit. Multiplexer und Empfänger
+/// werden gelesen aber ignoriert, alle anderen Sektionen übersprungen.
+/// `BO_` IDs mit gesetztem Bit 31 sind Extended (29 bit) IDs, alle anderen
+/// müssen in 11 bit passen.
+pub fn parse_dbc(src: &str) -> Result<Vec<CANSignal>, DbcError> {
+    let mut signals = Vec::new();
+    let mut current_id: Option<(u32, bool)> = None;
+
+    for (idx, raw) in src.lines().enumerate() {
+        let line = raw.trim();
+        let err = |message: String| DbcError::Parse { line: idx + 1, message };
+
+        if let Some(rest) = line.strip_prefix("BO_ ") {
+            let id = rest
+                .split_whitespace()
+                .next()
+                .and_then(|s| s.parse::<u32>().ok())
+                .ok_or_else(|| err("invalid message id".into()))?;
+            // Bit 31 markiert Extended IDs (29 bit)
+            let extended = id & 0x8000_0000 != 0;
+            let can_id = id & !0x8000_0000;
+            if !valid_can_id(can_id, extended) {
+                return Err(err(format!("message id {} does not fit in {} bits", id, if extended { 29 } else { 11 })));
+            }
+            current_id = Some((can_id, extended));
+        } else if let Some(rest) = line.strip_prefix("SG_ ") {
+            let (can_id, extended) = current_id.ok_or_else(|| err("SG_ outside of BO_".into()))?;
+            signals.push(parse_signal(rest, can_id, extended).map_err(err)?);
+        } else if !line.is_empty() && !raw.starts_with(char::is_whitespace) {
+            // Neue Top-Level Sektion beendet die aktuelle Message
+            current_id = None;
+        }
+    }
+
+    Ok(signals)
+}
+
+/// `Engine_RPM : 24|16@1+ (0.125,0) [0|8031.875] "rpm" ECU`
+fn parse_signal(src: &str, can_id: u32, extended: bool) -> Result<CANSignal, String> {
+    let (head, body) = src.split_once(':').ok_or("missing ':'")?;
+    // Bei Multiplexing folgt auf den Namen noch `M` bzw. `m<n>`
+    let name = head.split_whitespace().next().ok_or("missing signal name")?;
+
+    let body = body.trim_start();
+    let (layout, body) = body.split_once(char::is_whitespace).ok_or("missing factor/offset")?;
+    let (start_bit, layout) = layout.split_once('|').ok_or("invalid bit layout")?;
+    let (length, order) = layout.split_once('@').ok_or("invalid bit layout")?;
+    let byte_order = match order.chars().next() {
+        Some('1') => ByteOrder::LittleEndian,
+        Some('0') => ByteOrder::BigEndian,
+        _ => return Err(format!("invalid byte order '{}'", order)),
+    };
+    let signed = match order.chars().nth(1) {
+        Some('+') => false,
+        Some('-') => true,
+        _ => return Err(format!("invalid sign in '{}'", order)),
+    };
+
+    let body = body.trim_start().strip_prefix('(').ok_or("missing '('")?;
+    let (factor_offset, body) = body.split_once(')').ok_or("missing ')'")?;
+    let (scale, offset) = factor_offset.split_once(',').ok_or("invalid factor/offset")?;
+
+    let body = body.trim_start().strip_prefix('[').ok_or("missing '['")?;
+    let (range, body) = body.split_once(']').ok_or("missing ']'")?;
+    let (min_val, max_val) = range.split_once('|').ok_or("invalid range")?;
+
+    let body = body.trim_start().strip_prefix('"').ok_or("missing unit")?;
+    let (unit, _receivers) = body.split_once('"').ok_or("unterminated unit")?;
+
+    let number = |s: &str, what: &str| s.trim().parse::<f64>().map_err(|_| format!("invalid {} '{}'", what, s));
+    Ok(CANSignal {
+        name: name.to_string(),
+        can_id,
+        extended,
+        start_bit: start_bit.trim().parse().map_err(|_| format!("invalid start bit '{}'", start_bit))?,
+        length: length.trim().parse().map_err(|_| format!("invalid length '{}'", length))?,
+        byte_order,
+        signed,
+        scale: number(scale, "factor")?,
+        offset: number(offset, "offset")?,
+        unit: unit.to_string(),
+        min_val: number(min_val, "minimum")?,
+        max_val: number(max_val, "maximum")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DBC: &str = r#"VERSION ""
+
+NS_ :
+    CM_
+
+BU_: ECU Dash
+
+BO_ 256 EngineData: 8 ECU
+ SG_ Engine_RPM : 24|16@1+ (0.125,0) [0|8031.875] "rpm" Dash
+ SG_ Coolant_Temp : 7|8@0- (1,-40) [-40|215] "degC" Dash
+
+BO_ 2566848766 Extended: 8 ECU
+ SG_ Mode M : 0|4@1+ (1,0) [0|15] "" Dash
+
+CM_ SG_ 256 Engine_RPM "Drehzahl";
+"#;
+
+    #[test]
+    fn parses_messages_and_signals() {
+        let signals = parse_dbc(DBC).unwrap();
+        assert_eq!(signals.len(), 3);
+
+        let rpm = &signals[0];
+        assert_eq!((rpm.name.as_str(), rpm.can_id, rpm.extended), ("Engine_RPM", 0x100, false));
+        assert_eq!((rpm.start_bit, rpm.length, rpm.signed), (24, 16, false));
+        assert!(matches!(rpm.byte_order, ByteOrder::LittleEndian));
+        assert_eq!((rpm.scale, rpm.offset, rpm.min_val, rpm.max_val), (0.125, 0.0, 0.0, 8031.875));
+        assert_eq!(rpm.unit, "rpm");
+
+        let temp = &signals[1];
+        assert_eq!((temp.name.as_str(), temp.can_id, temp.start_bit, temp.length), ("Coolant_Temp", 0x100, 7, 8));
+        assert!(matches!(temp.byte_order, ByteOrder::BigEndian));
+        assert!(temp.signed);
+        assert_eq!((temp.offset, temp.min_val), (-40.0, -40.0));
+
+        // Bit 31 gesetzt: Extended ID, Multiplexer-Kennung wird ignoriert
+        let mode = &signals[2];
+        assert_eq!((mode.name.as_str(), mode.can_id, mode.extended), ("Mode", 0x18FF_00FE, true));
+        assert_eq!(mode.unit, "");
+    }
+
+    #[test]
+    fn malformed_signal_reports_its_line() {
+        let src = "BO_ 256 EngineData: 8 ECU\n SG_ Engine_RPM : 24|16@1+ (0.125,0) [0|8031.875] \"rpm\" Dash\n SG_ Broken : 24|16@2+ (1,0) [0|1] \"\" Dash\n";
+        match parse_dbc(src) {
+            Err(DbcError::Parse { line, message }) => {
+                assert_eq!(line, 3);
+                assert!(message.contains("byte order"), "{}", message);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_message_ids_are_rejected() {
+        assert!(matches!(parse_dbc("BO_ 2048 TooLong: 8 ECU"), Err(DbcError::Parse { line: 1, .. })));
+        assert!(matches!(parse_dbc("BO_ x Name: 8 ECU"), Err(DbcError::Parse { line: 1, .. })));
+        // SG_ nach einer neuen Sektion gehört zu keiner Message mehr
+        let src = "BO_ 256 EngineData: 8 ECU\nCM_ \"\";\n SG_ Engine_RPM : 24|16@1+ (0.125,0) [0|8031.875] \"rpm\" Dash";
+        assert!(matches!(parse_dbc(src), Err(DbcError::Parse { line: 3, .. })));
+    }
+}
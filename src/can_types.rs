@@ -38,6 +38,7 @@ pub enum CANMessage {
         unit: String,          // "rpm", "km/h", "°C", etc.  
         bus_id: u8,
         source_id: u32,        // Original CAN ID
+        extended: bool,        // `source_id` ist eine 29-bit ID
         timestamp_us: u64,
     }
 }
@@ -78,9 +79,13 @@ pub struct CANMessageBatch {
 pub struct CANSignal {
     pub name: String,           // "Engine_RPM"
     pub can_id: u32,           // CAN Message ID
+    #[serde(default)]
+    pub extended: bool,        // Gilt nur für 29-bit Frames
     pub start_bit: u8,         // Start Bit im CAN Frame
     pub length: u8,            // Anzahl Bits
     pub byte_order: ByteOrder, // Big/Little Endian
+    #[serde(default)]
+    pub signed: bool,          // Zweierkomplement (DBC `-`)
     pub scale: f64,            // Skalierungsfaktor
     pub offset: f64,           // Offset
     pub unit: String,          // "rpm", "km/h"
@@ -106,6 +111,26 @@ pub struct CANPerformanceStats {
     pub avg_latency_us: u64,       // Durchschnittliche Latenz
 }
 
+/// Größte 11-bit (Standard) ID
+pub const STANDARD_ID_MAX: u32 = 0x7FF;
+/// Größte 29-bit (Extended) ID
+pub const EXTENDED_ID_MAX: u32 = 0x1FFF_FFFF;
+
+/// Ob `can_id` in das ID Feld des Frame Typs passt (11 bzw. 29 bit)
+pub fn valid_can_id(can_id: u32, extended: bool) -> bool {
+    can_id <= if extended { EXTENDED_ID_MAX } else { STANDARD_ID_MAX }
+}
+
+/// Anzeige einer CAN ID: `0x201` (11 bit) bzw. `0x18FEF100x` (29 bit),
+/// damit Standard und Extended IDs mit gleichen unteren Bits unterscheidbar sind
+pub fn format_can_id(can_id: u32, extended: bool) -> String {
+    if extended {
+        format!("0x{:08X}x", can_id)
+    } else {
+        format!("0x{:03X}", can_id)
+    }
+}
+
 // Beispiel: Typische Automotive CAN IDs
 pub mod automotive_ids {
     pub const ENGINE_RPM: u32 = 0x201;
@@ -114,35 +139,301 @@ pub mod automotive_ids {
     pub const BATTERY_VOLTAGE: u32 = 0x500;
     pub const GEAR_POSITION: u32 = 0x600;
     pub const STEERING_ANGLE: u32 = 0x700;
+    /// J1939 CCVS1 (PGN 65265) von Quelle 0x00, 29 bit
+    pub const WHEEL_SPEED: u32 = 0x18FE_F100;
 }
 
-// Beispiel: Signal Dekodierung
+// Signal Dekodierung
 impl CANSignal {
+    /// Ob das Signal in einem Frame mit dieser ID steckt. Standard und
+    /// Extended IDs sind getrennte Adressräume: `0x100` passt nicht auf
+    /// `0x100x`.
+    pub fn matches(&self, can_id: u32, extended: bool) -> bool {
+        self.can_id == can_id && self.extended == extended
+    }
+
     /// Dekodiert einen Wert aus CAN Data
+    ///
+    /// Extrahiert `length` Bits ab `start_bit` gemäß DBC Konvention:
+    /// - `LittleEndian` (Intel, `@1`): `start_bit` ist das LSB, Bits laufen
+    ///   aufsteigend über die Bytegrenzen (Bit n = Byte n/8, Bit n%8).
+    /// - `BigEndian` (Motorola, `@0`): `start_bit` ist das MSB, danach
+    ///   absteigend innerhalb des Bytes und weiter beim MSB des nächsten Bytes.
+    ///
+    /// Signale mit `signed` werden als Zweierkomplement über `length` Bits
+    /// gelesen. Danach wird `scale`/`offset` angewendet und auf `[min_val, max_val]`
+    /// begrenzt (nur wenn `min_val < max_val`, DBC nutzt `[0|0]` für "unbegrenzt").
+    /// `None` nur wenn `data` zu kurz ist (oder `length` nicht in 1..=64 liegt).
     pub fn decode(&self, data: &[u8]) -> Option<f64> {
-        if data.is_empty() {
+        let raw = self.extract_raw(data)?;
+        let raw = if self.signed {
+            // Vorzeichen von `length` auf 64 bit erweitern
+            let shift = 64 - self.length as u32;
+            (((raw << shift) as i64) >> shift) as f64
+        } else {
+            raw as f64
+        };
+        let value = raw * self.scale + self.offset;
+        if self.min_val < self.max_val {
+            Some(value.clamp(self.min_val, self.max_val))
+        } else {
+            Some(value)
+        }
+    }
+
+    /// Liest den unskalierten Rohwert des Signals aus dem Frame
+    fn extract_raw(&self, data: &[u8]) -> Option<u64> {
+        if self.length == 0 || self.length > 64 {
             return None;
         }
-        
-        // Vereinfachte Dekodierung für Demo
-        match self.can_id {
-            automotive_ids::ENGINE_RPM => {
-                if data.len() >= 2 {
-                    let raw = u16::from_be_bytes([data[0], data[1]]);
-                    Some(raw as f64 * self.scale + self.offset)
-                } else {
-                    None
+        let bit = |pos: usize| -> Option<u64> {
+            data.get(pos / 8).map(|byte| ((byte >> (pos % 8)) & 1) as u64)
+        };
+
+        let mut raw = 0u64;
+        match self.byte_order {
+            ByteOrder::LittleEndian => {
+                for i in 0..self.length as usize {
+                    raw |= bit(self.start_bit as usize + i)? << i;
                 }
             }
-            automotive_ids::VEHICLE_SPEED => {
-                if data.len() >= 2 {
-                    let raw = u16::from_be_bytes([data[0], data[1]]);
-                    Some(raw as f64 * 0.01) // km/h
-                } else {
-                    None
+            ByteOrder::BigEndian => {
+                let mut pos = self.start_bit as usize;
+                for _ in 0..self.length {
+                    raw = (raw << 1) | bit(pos)?;
+                    // Am LSB eines Bytes weiter zum MSB des nächsten Bytes
+                    pos = if pos.is_multiple_of(8) { pos + 15 } else { pos - 1 };
                 }
             }
-            _ => None
         }
+        Some(raw)
     }
-}
\ No newline at end of file
+}
+
+/// Dekodiert alle `DataFrame`s eines Batches mit der Signal Datenbank `db`
+///
+/// Pro Frame wird für jedes Signal mit passender `can_id` und passendem
+/// `extended` Flag ein `VehicleSignal` erzeugt (Zeitstempel, `bus_id` und
+/// `source_id` vom Frame). Frames ohne passendes Signal werden verworfen,
+/// ebenso Signale die nicht in den Frame passen. Frames deren ID nicht in
+/// ihr ID Feld passt (siehe [`valid_can_id`]) werden nicht dekodiert sondern
+/// als `FormError` gemeldet. Alle anderen Message Typen (Error Frames, Bus
+/// Status, bereits dekodierte Signale) werden unverändert durchgereicht.
+pub fn decode_batch(batch: &CANMessageBatch, db: &[CANSignal]) -> Vec<CANMessage> {
+    let mut out = Vec::with_capacity(batch.messages.len());
+    for msg in &batch.messages {
+        match msg {
+            CANMessage::DataFrame { bus_id, can_id, timestamp_us, extended, .. } if !valid_can_id(*can_id, *extended) => {
+                out.push(CANMessage::ErrorFrame {
+                    bus_id: *bus_id,
+                    error_type: CANErrorType::FormError,
+                    timestamp_us: *timestamp_us,
+                });
+            }
+            CANMessage::DataFrame { bus_id, can_id, data, timestamp_us, extended, .. } => {
+                for signal in db.iter().filter(|s| s.matches(*can_id, *extended)) {
+                    if let Some(value) = signal.decode(data) {
+                        out.push(CANMessage::VehicleSignal {
+                            signal_name: signal.name.clone(),
+                            value,
+                            unit: signal.unit.clone(),
+                            bus_id: *bus_id,
+                            source_id: *can_id,
+                            extended: *extended,
+                            timestamp_us: *timestamp_us,
+                        });
+                    }
+                }
+            }
+            other => out.push(other.clone()),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(start_bit: u8, length: u8, byte_order: ByteOrder) -> CANSignal {
+        CANSignal {
+            name: "test".into(),
+            can_id: 0x100,
+            extended: false,
+            start_bit,
+            length,
+            byte_order,
+            signed: false,
+            scale: 1.0,
+            offset: 0.0,
+            unit: String::new(),
+            min_val: 0.0,
+            max_val: 0.0,
+        }
+    }
+
+    #[test]
+    fn intel_signal_spanning_three_bytes() {
+        // 20 bit ab Bit 4: 0xABCDE steht in Byte 0 (oberes Nibble) bis Byte 2
+        let data = [0xE0, 0xCD, 0xAB, 0x00];
+        assert_eq!(signal(4, 20, ByteOrder::LittleEndian).decode(&data), Some(0xABCDE as f64));
+    }
+
+    #[test]
+    fn intel_signal_not_byte_aligned() {
+        // 5 bit ab Bit 3 = 0b10110
+        let data = [0b1011_0000];
+        assert_eq!(signal(3, 5, ByteOrder::LittleEndian).decode(&data), Some(0b10110 as f64));
+    }
+
+    #[test]
+    fn motorola_signal_across_bytes() {
+        // 12 bit mit MSB in Bit 7 von Byte 0: 0xABC = 0xAB, dann oberes Nibble 0xC
+        let data = [0xAB, 0xC0];
+        assert_eq!(signal(7, 12, ByteOrder::BigEndian).decode(&data), Some(0xABC as f64));
+        // 16 bit Motorola entspricht Big Endian Bytes
+        assert_eq!(signal(7, 16, ByteOrder::BigEndian).decode(&[0x12, 0x34]), Some(0x1234 as f64));
+    }
+
+    #[test]
+    fn signed_values_are_sign_extended() {
+        let mut sig = signal(0, 8, ByteOrder::LittleEndian);
+        sig.signed = true;
+        assert_eq!(sig.decode(&[0xFF]), Some(-1.0));
+        assert_eq!(sig.decode(&[0x80]), Some(-128.0));
+        assert_eq!(sig.decode(&[0x7F]), Some(127.0));
+        let mut sig = signal(7, 12, ByteOrder::BigEndian);
+        sig.signed = true;
+        assert_eq!(sig.decode(&[0xFF, 0xE0]), Some(-2.0));
+        // Ohne Vorzeichen bleibt derselbe Rohwert positiv
+        assert_eq!(signal(0, 8, ByteOrder::LittleEndian).decode(&[0xFF]), Some(255.0));
+    }
+
+    #[test]
+    fn scale_offset_and_clamp() {
+        let mut sig = signal(0, 16, ByteOrder::LittleEndian);
+        sig.scale = 0.125;
+        sig.offset = -40.0;
+        // 0x0190 = 400 → 400 * 0.125 - 40 = 10
+        assert_eq!(sig.decode(&[0x90, 0x01]), Some(10.0));
+        sig.min_val = 0.0;
+        sig.max_val = 5.0;
+        assert_eq!(sig.decode(&[0x90, 0x01]), Some(5.0));
+        assert_eq!(sig.decode(&[0x00, 0x00]), Some(0.0));
+    }
+
+    #[test]
+    fn short_data_is_none() {
+        assert_eq!(signal(4, 20, ByteOrder::LittleEndian).decode(&[0xFF, 0xFF]), None);
+        assert_eq!(signal(0, 0, ByteOrder::LittleEndian).decode(&[0xFF]), None);
+    }
+
+    fn frame(can_id: u32, extended: bool, data: &[u8], timestamp_us: u64) -> CANMessage {
+        CANMessage::DataFrame { bus_id: 1, can_id, data: data.to_vec(), timestamp_us, dlc: data.len() as u8, extended }
+    }
+
+    fn batch(messages: Vec<CANMessage>) -> CANMessageBatch {
+        CANMessageBatch { messages, batch_id: 1, start_timestamp: 0, end_timestamp: 0 }
+    }
+
+    /// (Name, Wert, Quell-ID, extended, Zeitstempel) der `VehicleSignal`s
+    fn vehicle_signals(messages: &[CANMessage]) -> Vec<(&str, f64, u32, bool, u64)> {
+        messages
+            .iter()
+            .filter_map(|m| match m {
+                CANMessage::VehicleSignal { signal_name, value, source_id, extended, timestamp_us, .. } => {
+                    Some((signal_name.as_str(), *value, *source_id, *extended, *timestamp_us))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_batch_with_two_signals() {
+        let mut rpm = signal(0, 16, ByteOrder::LittleEndian);
+        rpm.name = "rpm".into();
+        rpm.scale = 0.25;
+        let mut temp = signal(16, 8, ByteOrder::LittleEndian);
+        temp.name = "temp".into();
+        temp.offset = -40.0;
+        let db = [rpm, temp];
+
+        let out = decode_batch(
+            &batch(vec![
+                frame(0x100, false, &[0x40, 0x1F, 0x5A], 10),
+                // Keine passende ID: verworfen
+                frame(0x200, false, &[0xFF; 8], 20),
+                // Zu kurz für `temp`: nur `rpm`
+                frame(0x100, false, &[0x10, 0x00], 30),
+                CANMessage::BusStatus { bus_id: 1, load_percent: 12.5, error_count: 0, messages_per_sec: 3, timestamp_us: 40 },
+            ]),
+            &db,
+        );
+        assert_eq!(
+            vehicle_signals(&out),
+            [("rpm", 2000.0, 0x100, false, 10), ("temp", 50.0, 0x100, false, 10), ("rpm", 4.0, 0x100, false, 30)]
+        );
+        assert_eq!(out.len(), 4);
+        assert!(matches!(out[3], CANMessage::BusStatus { timestamp_us: 40, .. }));
+    }
+
+    #[test]
+    fn decode_batch_reports_invalid_ids_as_form_error() {
+        let out = decode_batch(&batch(vec![frame(0x800, false, &[0x00], 5)]), &[signal(0, 8, ByteOrder::LittleEndian)]);
+        assert!(matches!(
+            out[..],
+            [CANMessage::ErrorFrame { bus_id: 1, error_type: CANErrorType::FormError, timestamp_us: 5 }]
+        ));
+    }
+
+    #[test]
+    fn standard_and_extended_ids_are_separate() {
+        // Gleiche unteren Bits, einmal 11 bit und einmal 29 bit
+        let mut standard = signal(0, 8, ByteOrder::LittleEndian);
+        standard.name = "standard".into();
+        let mut extended = signal(0, 8, ByteOrder::LittleEndian);
+        extended.name = "extended".into();
+        extended.extended = true;
+        assert!(standard.matches(0x100, false) && !standard.matches(0x100, true));
+        assert!(extended.matches(0x100, true) && !extended.matches(0x100, false));
+
+        let out = decode_batch(
+            &batch(vec![frame(0x100, false, &[1], 10), frame(0x100, true, &[2], 20)]),
+            &[standard, extended],
+        );
+        assert_eq!(
+            vehicle_signals(&out),
+            [("standard", 1.0, 0x100, false, 10), ("extended", 2.0, 0x100, true, 20)]
+        );
+    }
+
+    #[test]
+    fn id_range_depends_on_the_frame_type() {
+        assert!(valid_can_id(0x7FF, false));
+        assert!(!valid_can_id(0x800, false));
+        assert!(valid_can_id(0x1FFF_FFFF, true));
+        assert!(!valid_can_id(0x2000_0000, true));
+
+        // 0x18FEF100 passt nur als Extended ID
+        let mut wheel = signal(0, 8, ByteOrder::LittleEndian);
+        wheel.can_id = automotive_ids::WHEEL_SPEED;
+        wheel.extended = true;
+        let out = decode_batch(
+            &batch(vec![
+                frame(automotive_ids::WHEEL_SPEED, true, &[7], 1),
+                frame(automotive_ids::WHEEL_SPEED, false, &[7], 2),
+            ]),
+            &[wheel],
+        );
+        assert_eq!(vehicle_signals(&out), [("test", 7.0, 0x18FE_F100, true, 1)]);
+        assert!(matches!(out[1], CANMessage::ErrorFrame { error_type: CANErrorType::FormError, timestamp_us: 2, .. }));
+    }
+
+    #[test]
+    fn display_marks_extended_ids() {
+        assert_eq!(format_can_id(0x201, false), "0x201");
+        assert_eq!(format_can_id(0x18FE_F100, true), "0x18FEF100x");
+        assert_eq!(format_can_id(0x100, true), "0x00000100x");
+    }
+}
@@ -0,0 +1,106 @@
+// 🧩 Decoder Registry for Custom Measurement Types
+
+use crate::multi_user_types::MeasurementType;
+use std::collections::HashMap;
+
+/// Turns the raw bytes of one sample into a value, `None` if malformed
+pub type DecodeFn = Box<dyn Fn(&[u8]) -> Option<f64> + Send + Sync>;
+
+/// Decoders for `MeasurementType::Custom`, keyed by the custom type `name`.
+///
+/// Plays the role `CANSignal::decode` has for CAN frames, but for sensor
+/// formats that are plugged in at runtime instead of being part of the enum.
+#[derive(Default)]
+pub struct DecoderRegistry {
+    decoders: HashMap<String, DecodeFn>,
+}
+
+impl DecoderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `f` for `name`, replacing any previous decoder of that name
+    pub fn register_decoder<F>(&mut self, name: impl Into<String>, f: F)
+    where
+        F: Fn(&[u8]) -> Option<f64> + Send + Sync + 'static,
+    {
+        self.decoders.insert(name.into(), Box::new(f));
+    }
+
+    pub fn unregister_decoder(&mut self, name: &str) -> bool {
+        self.decoders.remove(name).is_some()
+    }
+
+    pub fn has_decoder(&self, name: &str) -> bool {
+        self.decoders.contains_key(name)
+    }
+
+    /// `None` if no decoder is registered for `name` or it rejects `data`
+    pub fn decode_custom(&self, name: &str, data: &[u8]) -> Option<f64> {
+        self.decoders.get(name)?(data)
+    }
+
+    /// Decodes `data` for a `Custom` measurement type; built-in types carry
+    /// already decoded values and yield `None`.
+    pub fn decode(&self, measurement_type: &MeasurementType, data: &[u8]) -> Option<f64> {
+        match measurement_type {
+            MeasurementType::Custom { name, .. } => self.decode_custom(name, data),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for DecoderRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.decoders.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Signed big-endian 24-bit ADC reading
+    fn adc24(data: &[u8]) -> Option<f64> {
+        let bytes: [u8; 3] = data.get(..3)?.try_into().ok()?;
+        let raw = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], 0]) >> 8;
+        Some(raw as f64)
+    }
+
+    fn registry() -> DecoderRegistry {
+        let mut registry = DecoderRegistry::new();
+        registry.register_decoder("adc24", adc24);
+        registry
+    }
+
+    #[test]
+    fn adc24_sign_extension() {
+        let registry = registry();
+        assert_eq!(registry.decode_custom("adc24", &[0x00, 0x00, 0x01]), Some(1.0));
+        assert_eq!(registry.decode_custom("adc24", &[0x7F, 0xFF, 0xFF]), Some(8_388_607.0));
+        assert_eq!(registry.decode_custom("adc24", &[0x80, 0x00, 0x00]), Some(-8_388_608.0));
+        assert_eq!(registry.decode_custom("adc24", &[0xFF, 0xFF, 0xFF]), Some(-1.0));
+    }
+
+    #[test]
+    fn short_input_is_none() {
+        let registry = registry();
+        assert_eq!(registry.decode_custom("adc24", &[]), None);
+        assert_eq!(registry.decode_custom("adc24", &[0x12, 0x34]), None);
+    }
+
+    #[test]
+    fn decode_by_measurement_type() {
+        let mut registry = registry();
+        let adc = MeasurementType::Custom { name: "adc24".into(), unit: "counts".into() };
+        assert_eq!(registry.decode(&adc, &[0x00, 0x01, 0x00]), Some(256.0));
+        // Built-in types and unknown names have no decoder
+        assert_eq!(registry.decode(&MeasurementType::Voltage, &[0x00, 0x01, 0x00]), None);
+        assert_eq!(registry.decode_custom("adc16", &[0x00, 0x01, 0x00]), None);
+
+        assert!(registry.unregister_decoder("adc24"));
+        assert!(!registry.has_decoder("adc24"));
+        assert_eq!(registry.decode(&adc, &[0x00, 0x01, 0x00]), None);
+    }
+}
@@ -0,0 +1,75 @@
+// 🚦 Error Codes for Server Replies
+
+/// Machine-readable reason of an error reply, sent as its number: the
+/// `error_code` of `MultiUserMessage::Error` on `/mu` and the `code` of the
+/// `error` message on `/ws`. The values follow the HTTP status with the same
+/// meaning where one exists and never change, so clients can match on them
+/// instead of on the message text.
+///
+/// | Code | Variant          | Meaning                                              |
+/// |------|------------------|------------------------------------------------------|
+/// | 400  | `InvalidMessage` | the message couldn't be parsed                       |
+/// | 401  | `Unauthorized`   | no valid session, or wrong username/password; log in |
+/// | 404  | `NotFound`       | the referenced measurement isn't the session's       |
+/// | 409  | `Conflict`       | not possible in the current state (status, burst)    |
+/// | 422  | `InvalidConfig`  | the configuration itself is invalid                  |
+/// | 429  | `RateLimited`    | too many control messages, the message was dropped   |
+/// | 460  | `UnknownSymbol`  | a symbol the server doesn't stream                   |
+/// | 500  | `InternalError`  | the server failed; retrying may help                 |
+/// | 501  | `Unsupported`    | a well-formed command the server doesn't handle      |
+/// | 507  | `ResourceLimit`  | a limit of the user's permissions was reached        |
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    InvalidMessage,
+    Unauthorized,
+    NotFound,
+    Conflict,
+    InvalidConfig,
+    RateLimited,
+    UnknownSymbol,
+    InternalError,
+    Unsupported,
+    ResourceLimit,
+}
+
+impl ErrorCode {
+    const ALL: [ErrorCode; 10] = [
+        ErrorCode::InvalidMessage,
+        ErrorCode::Unauthorized,
+        ErrorCode::NotFound,
+        ErrorCode::Conflict,
+        ErrorCode::InvalidConfig,
+        ErrorCode::RateLimited,
+        ErrorCode::UnknownSymbol,
+        ErrorCode::InternalError,
+        ErrorCode::Unsupported,
+        ErrorCode::ResourceLimit,
+    ];
+
+    /// Number sent on the wire
+    pub fn code(self) -> u32 {
+        match self {
+            ErrorCode::InvalidMessage => 400,
+            ErrorCode::Unauthorized => 401,
+            ErrorCode::NotFound => 404,
+            ErrorCode::Conflict => 409,
+            ErrorCode::InvalidConfig => 422,
+            ErrorCode::RateLimited => 429,
+            ErrorCode::UnknownSymbol => 460,
+            ErrorCode::InternalError => 500,
+            ErrorCode::Unsupported => 501,
+            ErrorCode::ResourceLimit => 507,
+        }
+    }
+
+    /// `None` for codes of a newer server and for 0 (no code sent)
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code() == code)
+    }
+}
+
+impl From<ErrorCode> for u32 {
+    fn from(code: ErrorCode) -> u32 {
+        code.code()
+    }
+}
@@ -0,0 +1,13 @@
+// Gemeinsame Typen für CAN Bus und Multi-User Messsystem
+
+pub mod auto_scale;
+pub mod can_dbc;
+pub mod can_types;
+pub mod custom_decoder;
+pub mod error_code;
+pub mod multi_user_types;
+pub mod sample_gaps;
+pub mod shared_memory;
+#[cfg(feature = "storage")]
+pub mod storage;
+pub mod trigger;
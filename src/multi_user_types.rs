@@ -1,7 +1,6 @@
 // 🚀 Multi-User Measurement System - Core Types
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use uuid::Uuid;
 
 /// User Session Management
@@ -33,7 +32,7 @@ pub struct MeasurementSession {
 
 pub type MeasurementId = Uuid;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum MeasurementType {
     Voltage,
     Current, 
@@ -45,6 +44,7 @@ pub enum MeasurementType {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MeasurementConfig {
+    pub measurement_type: MeasurementType,
     pub sample_rate_hz: u32,        // 1 Hz - 100 kHz
     pub duration_seconds: Option<u32>, // None = continuous
     pub channels: Vec<ChannelConfig>,
@@ -53,7 +53,7 @@ pub struct MeasurementConfig {
     pub data_retention: DataRetention,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ChannelConfig {
     pub channel_id: u8,
     pub name: String,
@@ -63,6 +63,49 @@ pub struct ChannelConfig {
     pub enabled: bool,
 }
 
+/// Aufbewahrung der Messdaten nach Abschluss einer Messung
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DataRetention {
+    KeepAll,
+    Duration(u64),      // Sekunden
+    MaxSamples(u64),    // Nur die letzten N Samples
+    Discard,
+}
+
+impl DataRetention {
+    /// Verwirft die Samples, die die Aufbewahrung nicht mehr abdeckt.
+    /// `samples` ist nach Zeit sortiert, ältestes zuerst; `Duration` zählt
+    /// vom jüngsten Sample aus, `Discard` leert den Puffer.
+    pub fn evict(&self, samples: &mut std::collections::VecDeque<MeasurementData>) {
+        match *self {
+            DataRetention::KeepAll => {}
+            DataRetention::Duration(secs) => {
+                let Some(last) = samples.back().map(|s| s.timestamp_ns) else { return };
+                let cutoff = last.saturating_sub(secs.saturating_mul(1_000_000_000));
+                while samples.front().is_some_and(|s| s.timestamp_ns < cutoff) {
+                    samples.pop_front();
+                }
+            }
+            DataRetention::MaxSamples(n) => {
+                let excess = samples.len().saturating_sub(n as usize);
+                samples.drain(..excess);
+            }
+            DataRetention::Discard => samples.clear(),
+        }
+    }
+
+    /// Ob `count` Samples über `span_ns` mehr als das Doppelte dessen sind,
+    /// was aufbewahrt wird; ab dann lohnt sich das Verwerfen, z.B. das
+    /// Neuschreiben einer Datei.
+    pub fn exceeded(&self, count: u64, span_ns: u64) -> bool {
+        match *self {
+            DataRetention::KeepAll | DataRetention::Discard => false,
+            DataRetention::Duration(secs) => span_ns >= secs.saturating_mul(2_000_000_000),
+            DataRetention::MaxSamples(n) => count > n.saturating_mul(2),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]  
 pub enum MeasurementStatus {
     Configuring,
@@ -107,7 +150,7 @@ pub struct SampleMetadata {
 }
 
 /// Resource Management  
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ResourceUsage {
     pub cpu_percent: f32,
     pub memory_mb: u64,
@@ -126,6 +169,26 @@ pub struct UserPermissions {
     pub allowed_measurement_types: Vec<MeasurementType>,
 }
 
+impl Default for UserPermissions {
+    /// Standard-Limits pro User (siehe MULTI_USER_ARCHITECTURE.md)
+    fn default() -> Self {
+        Self {
+            max_concurrent_measurements: 4,
+            max_sample_rate_hz: 100_000,
+            max_bandwidth_bps: 10_000_000,
+            max_storage_mb: 1024,
+            max_session_duration_hours: 8,
+            allowed_measurement_types: vec![
+                MeasurementType::Voltage,
+                MeasurementType::Current,
+                MeasurementType::Temperature,
+                MeasurementType::Pressure,
+                MeasurementType::Acceleration,
+            ],
+        }
+    }
+}
+
 /// System-wide Statistics
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SystemStats {
@@ -146,12 +209,16 @@ pub enum MultiUserMessage {
     UserLogin { username: String, password: String },
     UserLogout,
     SessionHeartbeat,
+    SessionStarted { session: UserSession },
     
     // Measurement Control
     StartMeasurement { config: MeasurementConfig },
     StopMeasurement { measurement_id: MeasurementId },
     PauseMeasurement { measurement_id: MeasurementId },
     ResumeMeasurement { measurement_id: MeasurementId },
+    /// Übernimmt `sample_rate_hz` und `channels` in eine laufende Messung,
+    /// ohne sie neu zu starten; kommt mit der übernommenen Konfiguration zurück
+    UpdateMeasurementConfig { measurement_id: MeasurementId, config: MeasurementConfig },
     
     // Real-time Data
     MeasurementData(MeasurementData),
@@ -166,6 +233,7 @@ pub enum MultiUserMessage {
     },
     
     // Errors & Events
+    /// `error_code` ist der Zahlenwert eines [`crate::error_code::ErrorCode`]
     Error { message: String, error_code: u32 },
     ResourceLimitExceeded { resource: String, limit: u64 },
     SystemMaintenance { message: String, eta_seconds: u32 },
@@ -244,4 +312,73 @@ pub enum SystemEvent {
     DataOverrun,
     TimestampJump,
     ResourceWarning,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Samples im Abstand von `step_ns`, Index ab 0
+    fn samples(count: u64, step_ns: u64) -> VecDeque<MeasurementData> {
+        (0..count)
+            .map(|i| MeasurementData {
+                measurement_id: Uuid::nil(),
+                timestamp_ns: i * step_ns,
+                sample_index: i,
+                channels: Vec::new(),
+                metadata: SampleMetadata { trigger_events: Vec::new(), system_events: Vec::new() },
+            })
+            .collect()
+    }
+
+    fn indices(samples: &VecDeque<MeasurementData>) -> Vec<u64> {
+        samples.iter().map(|s| s.sample_index).collect()
+    }
+
+    #[test]
+    fn evict_keep_all_and_discard() {
+        let mut buf = samples(5, 1);
+        DataRetention::KeepAll.evict(&mut buf);
+        assert_eq!(buf.len(), 5);
+        DataRetention::Discard.evict(&mut buf);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn evict_max_samples_keeps_the_newest() {
+        let mut buf = samples(10, 1);
+        DataRetention::MaxSamples(3).evict(&mut buf);
+        assert_eq!(indices(&buf), [7, 8, 9]);
+        DataRetention::MaxSamples(5).evict(&mut buf);
+        assert_eq!(indices(&buf), [7, 8, 9]);
+        DataRetention::MaxSamples(0).evict(&mut buf);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn evict_duration_counts_from_the_newest() {
+        // 0.5 s Abstand: 0 s .. 4.5 s
+        let mut buf = samples(10, 500_000_000);
+        DataRetention::Duration(2).evict(&mut buf);
+        // Grenze inklusive: 2.5 s .. 4.5 s
+        assert_eq!(indices(&buf), [5, 6, 7, 8, 9]);
+        let mut empty = VecDeque::new();
+        DataRetention::Duration(2).evict(&mut empty);
+        assert!(empty.is_empty());
+        // Keine Überläufe bei riesigen Dauern
+        let mut buf = samples(3, 1);
+        DataRetention::Duration(u64::MAX).evict(&mut buf);
+        assert_eq!(buf.len(), 3);
+    }
+
+    #[test]
+    fn exceeded_at_twice_the_retention() {
+        assert!(!DataRetention::MaxSamples(100).exceeded(200, 0));
+        assert!(DataRetention::MaxSamples(100).exceeded(201, 0));
+        assert!(!DataRetention::Duration(1).exceeded(u64::MAX, 1_999_999_999));
+        assert!(DataRetention::Duration(1).exceeded(0, 2_000_000_000));
+        assert!(!DataRetention::KeepAll.exceeded(u64::MAX, u64::MAX));
+        assert!(!DataRetention::Discard.exceeded(u64::MAX, u64::MAX));
+    }
+}
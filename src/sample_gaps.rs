@@ -0,0 +1,81 @@
+// 🕳️ Gap Detection in Measurement Streams
+
+use crate::multi_user_types::{MeasurementData, SystemEvent};
+
+/// Compares two consecutive samples of one measurement as received.
+///
+/// - `DataOverrun`: `sample_index` skipped ahead, i.e. samples were lost
+///   between `prev` and `cur`.
+/// - `TimestampJump`: `timestamp_ns` went backwards, or advanced more than
+///   twice the time expected for the index step (`expected_dt_ns` per
+///   sample), so lost samples alone don't also count as a jump. An
+///   `expected_dt_ns` of 0 only checks for backward steps.
+pub fn detect_gaps(prev: &MeasurementData, cur: &MeasurementData, expected_dt_ns: u64) -> Vec<SystemEvent> {
+    let mut events = Vec::new();
+    let index_step = cur.sample_index.saturating_sub(prev.sample_index);
+    if index_step > 1 {
+        events.push(SystemEvent::DataOverrun);
+    }
+    let jumped = match cur.timestamp_ns.checked_sub(prev.timestamp_ns) {
+        None => true,
+        Some(dt) => expected_dt_ns > 0 && dt > expected_dt_ns.saturating_mul(2).saturating_mul(index_step.max(1)),
+    };
+    if jumped {
+        events.push(SystemEvent::TimestampJump);
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multi_user_types::SampleMetadata;
+    use uuid::Uuid;
+
+    fn sample(sample_index: u64, timestamp_ns: u64) -> MeasurementData {
+        MeasurementData {
+            measurement_id: Uuid::nil(),
+            timestamp_ns,
+            sample_index,
+            channels: Vec::new(),
+            metadata: SampleMetadata { trigger_events: Vec::new(), system_events: Vec::new() },
+        }
+    }
+
+    #[test]
+    fn consecutive_samples_have_no_gap() {
+        assert!(detect_gaps(&sample(4, 1_000), &sample(5, 2_000), 1_000).is_empty());
+        // Up to twice the expected time is jitter
+        assert!(detect_gaps(&sample(4, 1_000), &sample(5, 3_000), 1_000).is_empty());
+    }
+
+    #[test]
+    fn time_gap_is_a_jump() {
+        let events = detect_gaps(&sample(4, 1_000), &sample(5, 3_001), 1_000);
+        assert!(matches!(events[..], [SystemEvent::TimestampJump]));
+        let events = detect_gaps(&sample(4, 5_000), &sample(5, 4_000), 1_000);
+        assert!(matches!(events[..], [SystemEvent::TimestampJump]));
+    }
+
+    #[test]
+    fn index_gap_is_an_overrun() {
+        // Lost samples account for the time that passed
+        let events = detect_gaps(&sample(4, 1_000), &sample(8, 5_000), 1_000);
+        assert!(matches!(events[..], [SystemEvent::DataOverrun]));
+        let events = detect_gaps(&sample(4, 1_000), &sample(8, 100_000), 1_000);
+        assert!(matches!(events[..], [SystemEvent::DataOverrun, SystemEvent::TimestampJump]));
+    }
+
+    #[test]
+    fn zero_expected_dt_only_checks_backward_steps() {
+        assert!(detect_gaps(&sample(4, 1_000), &sample(5, u64::MAX), 0).is_empty());
+        let events = detect_gaps(&sample(4, 1_000), &sample(5, 999), 0);
+        assert!(matches!(events[..], [SystemEvent::TimestampJump]));
+    }
+
+    #[test]
+    fn huge_steps_do_not_overflow() {
+        let events = detect_gaps(&sample(0, 0), &sample(u64::MAX, u64::MAX), u64::MAX / 2);
+        assert!(matches!(events[..], [SystemEvent::DataOverrun]));
+    }
+}
@@ -0,0 +1,292 @@
+// 🧠 Shared-Memory Ring Buffer Reader for Kernel Samples
+
+use crate::multi_user_types::{
+    ChannelData, DataQuality, KernelSample, MeasurementData, SampleMetadata, SharedMemoryHeader,
+};
+use std::fmt;
+use std::mem::{offset_of, size_of};
+use uuid::Uuid;
+
+/// Expected `SharedMemoryHeader::magic` ("KSMP" in little endian)
+pub const SHM_MAGIC: u32 = 0x504D_534B;
+/// Layout version this reader understands
+pub const SHM_VERSION: u32 = 1;
+/// Bytes of the header at the start of the mapping
+pub const HEADER_SIZE: usize = size_of::<SharedMemoryHeader>();
+/// Bytes of one `KernelSample` slot, including padding
+pub const SAMPLE_SIZE: usize = size_of::<KernelSample>();
+
+/// Reasons a mapping is rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShmError {
+    /// Not even a header and one sample slot fit
+    TooSmall { len: usize },
+    BadMagic(u32),
+    UnsupportedVersion(u32),
+    /// `total_size` is larger than the mapped bytes
+    SizeMismatch { total_size: u64, len: usize },
+    /// An offset lies outside the ring or between two slots
+    BadOffset(u64),
+}
+
+impl fmt::Display for ShmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShmError::TooSmall { len } => write!(f, "shared memory too small: {} bytes", len),
+            ShmError::BadMagic(m) => write!(f, "bad shared memory magic {:#010x}", m),
+            ShmError::UnsupportedVersion(v) => write!(f, "unsupported shared memory version {}", v),
+            ShmError::SizeMismatch { total_size, len } => {
+                write!(f, "header claims {} bytes but only {} are mapped", total_size, len)
+            }
+            ShmError::BadOffset(o) => write!(f, "invalid ring offset {}", o),
+        }
+    }
+}
+
+impl std::error::Error for ShmError {}
+
+/// Reads `KernelSample`s from a shared-memory ring written by a kernel-space
+/// producer.
+///
+/// Layout: a `SharedMemoryHeader` at byte 0, followed by the ring of
+/// `SAMPLE_SIZE` byte slots up to `total_size` (the whole mapping including
+/// the header; a partial trailing slot is unused). `read_offset` and
+/// `write_offset` are byte offsets into the ring, always on a slot boundary;
+/// both equal means empty. All values are in native byte order and
+/// `#[repr(C)]` layout, read field by field so the mapping needs no alignment.
+///
+/// Each consumed sample advances `read_offset` in the mapping. The reader
+/// doesn't synchronize with a concurrently running producer; `write_offset`
+/// is re-read before every sample, so the caller only has to make sure the
+/// producer publishes it after writing the slot.
+pub struct KernelRingReader<'a> {
+    buf: &'a mut [u8],
+    ring_len: u64,
+}
+
+impl<'a> KernelRingReader<'a> {
+    /// Validates header and offsets of `buf`
+    pub fn new(buf: &'a mut [u8]) -> Result<Self, ShmError> {
+        if buf.len() < HEADER_SIZE + SAMPLE_SIZE {
+            return Err(ShmError::TooSmall { len: buf.len() });
+        }
+        let magic = read_u32(buf, offset_of!(SharedMemoryHeader, magic));
+        if magic != SHM_MAGIC {
+            return Err(ShmError::BadMagic(magic));
+        }
+        let version = read_u32(buf, offset_of!(SharedMemoryHeader, version));
+        if version != SHM_VERSION {
+            return Err(ShmError::UnsupportedVersion(version));
+        }
+        let total_size = read_u64(buf, offset_of!(SharedMemoryHeader, total_size));
+        if total_size > buf.len() as u64 || (total_size as usize) < HEADER_SIZE + SAMPLE_SIZE {
+            return Err(ShmError::SizeMismatch { total_size, len: buf.len() });
+        }
+        let slots = (total_size as usize - HEADER_SIZE) / SAMPLE_SIZE;
+        let reader = Self { buf, ring_len: (slots * SAMPLE_SIZE) as u64 };
+        reader.check_offset(reader.read_offset())?;
+        reader.check_offset(reader.write_offset())?;
+        Ok(reader)
+    }
+
+    /// Copy of the current header
+    pub fn header(&self) -> SharedMemoryHeader {
+        let u32_at = |off| read_u32(self.buf, off);
+        let u64_at = |off| read_u64(self.buf, off);
+        SharedMemoryHeader {
+            magic: u32_at(offset_of!(SharedMemoryHeader, magic)),
+            version: u32_at(offset_of!(SharedMemoryHeader, version)),
+            total_size: u64_at(offset_of!(SharedMemoryHeader, total_size)),
+            write_offset: u64_at(offset_of!(SharedMemoryHeader, write_offset)),
+            read_offset: u64_at(offset_of!(SharedMemoryHeader, read_offset)),
+            measurement_count: u32_at(offset_of!(SharedMemoryHeader, measurement_count)),
+            sample_rate: u32_at(offset_of!(SharedMemoryHeader, sample_rate)),
+        }
+    }
+
+    /// Samples written but not yet consumed
+    pub fn available(&self) -> usize {
+        let (read, write) = (self.read_offset(), self.write_offset());
+        let bytes = if write >= read { write - read } else { self.ring_len - read + write };
+        (bytes / SAMPLE_SIZE as u64) as usize
+    }
+
+    /// Consumes the next sample, or returns `Ok(None)` if the ring is empty
+    pub fn next_sample(&mut self) -> Result<Option<MeasurementData>, ShmError> {
+        let read = self.read_offset();
+        let write = self.write_offset();
+        self.check_offset(write)?;
+        if read == write {
+            return Ok(None);
+        }
+        let sample = self.sample_at(HEADER_SIZE + read as usize);
+        let next = (read + SAMPLE_SIZE as u64) % self.ring_len;
+        write_u64(self.buf, offset_of!(SharedMemoryHeader, read_offset), next);
+        Ok(Some(sample))
+    }
+
+    fn read_offset(&self) -> u64 {
+        read_u64(self.buf, offset_of!(SharedMemoryHeader, read_offset))
+    }
+
+    fn write_offset(&self) -> u64 {
+        read_u64(self.buf, offset_of!(SharedMemoryHeader, write_offset))
+    }
+
+    fn check_offset(&self, offset: u64) -> Result<(), ShmError> {
+        if offset >= self.ring_len || !offset.is_multiple_of(SAMPLE_SIZE as u64) {
+            return Err(ShmError::BadOffset(offset));
+        }
+        Ok(())
+    }
+
+    /// Decodes the slot at `base`; only the first `channel_count` (at most
+    /// 16) values are used, as channels `0..channel_count`.
+    fn sample_at(&self, base: usize) -> MeasurementData {
+        let id_at = base + offset_of!(KernelSample, measurement_id);
+        let mut id = [0u8; 16];
+        id.copy_from_slice(&self.buf[id_at..id_at + 16]);
+        let channel_count = self.buf[base + offset_of!(KernelSample, channel_count)].min(16);
+        let data_at = base + offset_of!(KernelSample, data);
+        let channels = (0..channel_count)
+            .map(|ch| ChannelData {
+                channel_id: ch,
+                value: read_f64(self.buf, data_at + ch as usize * 8),
+                quality: DataQuality::Good,
+            })
+            .collect();
+        MeasurementData {
+            measurement_id: Uuid::from_bytes(id),
+            timestamp_ns: read_u64(self.buf, base + offset_of!(KernelSample, timestamp_ns)),
+            sample_index: read_u64(self.buf, base + offset_of!(KernelSample, sample_index)),
+            channels,
+            metadata: SampleMetadata { trigger_events: Vec::new(), system_events: Vec::new() },
+        }
+    }
+}
+
+/// Drains the ring; a corrupted `write_offset` ends the iteration
+impl Iterator for KernelRingReader<'_> {
+    type Item = MeasurementData;
+
+    fn next(&mut self) -> Option<MeasurementData> {
+        self.next_sample().ok().flatten()
+    }
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_ne_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], at: usize) -> u64 {
+    u64::from_ne_bytes(buf[at..at + 8].try_into().unwrap())
+}
+
+fn read_f64(buf: &[u8], at: usize) -> f64 {
+    f64::from_ne_bytes(buf[at..at + 8].try_into().unwrap())
+}
+
+fn write_u64(buf: &mut [u8], at: usize, value: u64) {
+    buf[at..at + 8].copy_from_slice(&value.to_ne_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOTS: usize = 3;
+
+    fn write_u32(buf: &mut [u8], at: usize, value: u32) {
+        buf[at..at + 4].copy_from_slice(&value.to_ne_bytes());
+    }
+
+    /// Mapping with a ring of `SLOTS` slots plus a partial trailing slot
+    fn mapping(read_slot: usize, write_slot: usize) -> Vec<u8> {
+        let total_size = HEADER_SIZE + SLOTS * SAMPLE_SIZE + SAMPLE_SIZE / 2;
+        let mut buf = vec![0u8; total_size];
+        write_u32(&mut buf, offset_of!(SharedMemoryHeader, magic), SHM_MAGIC);
+        write_u32(&mut buf, offset_of!(SharedMemoryHeader, version), SHM_VERSION);
+        write_u64(&mut buf, offset_of!(SharedMemoryHeader, total_size), total_size as u64);
+        write_u64(&mut buf, offset_of!(SharedMemoryHeader, read_offset), (read_slot * SAMPLE_SIZE) as u64);
+        write_u64(&mut buf, offset_of!(SharedMemoryHeader, write_offset), (write_slot * SAMPLE_SIZE) as u64);
+        buf
+    }
+
+    /// Fills `slot` with sample `index` of `id`, channel values `index + ch / 10`
+    fn put_sample(buf: &mut [u8], slot: usize, id: Uuid, index: u64, channel_count: u8) {
+        let base = HEADER_SIZE + slot * SAMPLE_SIZE;
+        let id_at = base + offset_of!(KernelSample, measurement_id);
+        buf[id_at..id_at + 16].copy_from_slice(id.as_bytes());
+        write_u64(buf, base + offset_of!(KernelSample, timestamp_ns), 1_000 * index);
+        write_u64(buf, base + offset_of!(KernelSample, sample_index), index);
+        buf[base + offset_of!(KernelSample, channel_count)] = channel_count;
+        for ch in 0..16 {
+            let at = base + offset_of!(KernelSample, data) + ch * 8;
+            buf[at..at + 8].copy_from_slice(&(index as f64 + ch as f64 / 10.0).to_ne_bytes());
+        }
+    }
+
+    #[test]
+    fn reads_across_the_wrap_around() {
+        let id = Uuid::new_v4();
+        // Unread: slot 2, then slot 0 after wrapping
+        let mut buf = mapping(2, 1);
+        put_sample(&mut buf, 2, id, 7, 2);
+        put_sample(&mut buf, 0, id, 8, 20);
+
+        let mut reader = KernelRingReader::new(&mut buf).unwrap();
+        assert_eq!(reader.available(), 2);
+        let first = reader.next_sample().unwrap().unwrap();
+        assert_eq!((first.measurement_id, first.timestamp_ns, first.sample_index), (id, 7_000, 7));
+        let values: Vec<_> = first.channels.iter().map(|c| (c.channel_id, c.value)).collect();
+        assert_eq!(values, [(0, 7.0), (1, 7.1)]);
+        assert_eq!(reader.header().read_offset, 0);
+
+        // A channel count above 16 is capped
+        let second = reader.next_sample().unwrap().unwrap();
+        assert_eq!(second.sample_index, 8);
+        assert_eq!(second.channels.len(), 16);
+        assert_eq!(reader.available(), 0);
+        assert!(reader.next_sample().unwrap().is_none());
+        assert_eq!(reader.header().read_offset, SAMPLE_SIZE as u64);
+    }
+
+    #[test]
+    fn iterator_drains_the_ring() {
+        let id = Uuid::new_v4();
+        let mut buf = mapping(0, 2);
+        put_sample(&mut buf, 0, id, 1, 1);
+        put_sample(&mut buf, 1, id, 2, 1);
+        let indices: Vec<u64> = KernelRingReader::new(&mut buf).unwrap().map(|s| s.sample_index).collect();
+        assert_eq!(indices, [1, 2]);
+    }
+
+    #[test]
+    fn invalid_mappings_are_rejected() {
+        let mut buf = vec![0u8; HEADER_SIZE];
+        assert_eq!(KernelRingReader::new(&mut buf).err(), Some(ShmError::TooSmall { len: HEADER_SIZE }));
+
+        let mut buf = mapping(0, 0);
+        write_u32(&mut buf, offset_of!(SharedMemoryHeader, magic), 0);
+        assert_eq!(KernelRingReader::new(&mut buf).err(), Some(ShmError::BadMagic(0)));
+
+        let mut buf = mapping(0, 0);
+        write_u32(&mut buf, offset_of!(SharedMemoryHeader, version), 2);
+        assert_eq!(KernelRingReader::new(&mut buf).err(), Some(ShmError::UnsupportedVersion(2)));
+
+        let mut buf = mapping(0, 0);
+        let len = buf.len();
+        write_u64(&mut buf, offset_of!(SharedMemoryHeader, total_size), len as u64 + 1);
+        assert_eq!(
+            KernelRingReader::new(&mut buf).err(),
+            Some(ShmError::SizeMismatch { total_size: len as u64 + 1, len })
+        );
+
+        // The ring ends after the last whole slot
+        let mut buf = mapping(SLOTS, 0);
+        assert_eq!(KernelRingReader::new(&mut buf).err(), Some(ShmError::BadOffset((SLOTS * SAMPLE_SIZE) as u64)));
+        let mut buf = mapping(0, 0);
+        write_u64(&mut buf, offset_of!(SharedMemoryHeader, write_offset), 8);
+        assert_eq!(KernelRingReader::new(&mut buf).err(), Some(ShmError::BadOffset(8)));
+    }
+}
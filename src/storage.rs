@@ -0,0 +1,418 @@
+// 💾 Measurement Data Persistence (Arrow IPC)
+
+use crate::multi_user_types::{
+    ChannelData, DataQuality, DataRetention, MeasurementConfig, MeasurementData, MeasurementId,
+    SampleMetadata,
+};
+use arrow_array::{
+    cast::AsArray,
+    types::{Float64Type, UInt64Type, UInt8Type},
+    Array, ArrayRef, Float64Array, RecordBatch, UInt64Array, UInt8Array,
+};
+use arrow_ipc::{reader::FileReader, writer::FileWriter};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Rows buffered in memory before a record batch is written
+const FLUSH_ROWS: usize = 4096;
+/// Schema metadata key holding the measurement id
+const META_MEASUREMENT_ID: &str = "measurement_id";
+
+/// Writes the samples of one measurement to `<dir>/<measurement_id>.arrow`.
+///
+/// Columns: `timestamp_ns`, `sample_index`, then `ch<id>` (value) and
+/// `ch<id>_quality` for every enabled channel. A channel missing from a
+/// sample is stored as null. Sample metadata (trigger/system events) is not
+/// persisted.
+///
+/// `DataRetention` is applied in `finish`:
+/// - `KeepAll`: the file is kept as written.
+/// - `Duration(s)`: only samples within `s` seconds of the last one are kept.
+/// - `MaxSamples(n)`: only the last `n` samples are kept.
+/// - `Discard`: nothing is written to disk at all.
+///
+/// For `Duration` and `MaxSamples` the file is also rewritten with only the
+/// retained samples whenever it holds twice as much after a flush, so a
+/// long measurement doesn't grow it without bound.
+pub struct MeasurementWriter {
+    path: PathBuf,
+    measurement_id: MeasurementId,
+    retention: DataRetention,
+    schema: SchemaRef,
+    channels: Vec<u8>,
+    writer: Option<FileWriter<BufWriter<File>>>, // None for `Discard`
+    rows_written: u64,             // rows in the file so far
+    first_timestamp: Option<u64>,  // of the oldest row in the file
+    timestamps: Vec<u64>,
+    indices: Vec<u64>,
+    values: Vec<Vec<Option<f64>>>,    // per channel, parallel to `timestamps`
+    qualities: Vec<Vec<Option<u8>>>,
+}
+
+impl MeasurementWriter {
+    /// Creates the file for all enabled channels of `config`
+    pub fn create(
+        dir: impl AsRef<Path>,
+        measurement_id: MeasurementId,
+        config: &MeasurementConfig,
+    ) -> Result<Self, ArrowError> {
+        let mut channels: Vec<u8> = config
+            .channels
+            .iter()
+            .filter(|c| c.enabled)
+            .map(|c| c.channel_id)
+            .collect();
+        channels.sort_unstable();
+        channels.dedup();
+        Self::open(
+            measurement_path(dir, measurement_id),
+            measurement_id,
+            channels,
+            config.data_retention.clone(),
+        )
+    }
+
+    fn open(
+        path: PathBuf,
+        measurement_id: MeasurementId,
+        channels: Vec<u8>,
+        retention: DataRetention,
+    ) -> Result<Self, ArrowError> {
+        let mut fields = vec![
+            Field::new("timestamp_ns", DataType::UInt64, false),
+            Field::new("sample_index", DataType::UInt64, false),
+        ];
+        for id in &channels {
+            fields.push(Field::new(format!("ch{}", id), DataType::Float64, true));
+            fields.push(Field::new(format!("ch{}_quality", id), DataType::UInt8, true));
+        }
+        let metadata = HashMap::from([(META_MEASUREMENT_ID.to_string(), measurement_id.to_string())]);
+        let schema = Arc::new(Schema::new_with_metadata(fields, metadata));
+
+        let writer = match retention {
+            DataRetention::Discard => None,
+            _ => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let file = BufWriter::new(File::create(&path)?);
+                Some(FileWriter::try_new(file, &schema)?)
+            }
+        };
+
+        Ok(Self {
+            path,
+            measurement_id,
+            retention,
+            schema,
+            values: vec![Vec::new(); channels.len()],
+            qualities: vec![Vec::new(); channels.len()],
+            channels,
+            writer,
+            rows_written: 0,
+            first_timestamp: None,
+            timestamps: Vec::new(),
+            indices: Vec::new(),
+        })
+    }
+
+    /// Appends one sample. Channels that are not part of the file are ignored.
+    pub fn write_row(
+        &mut self,
+        timestamp_ns: u64,
+        sample_index: u64,
+        channels: &[ChannelData],
+    ) -> Result<(), ArrowError> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+        self.timestamps.push(timestamp_ns);
+        self.indices.push(sample_index);
+        for (col, id) in self.channels.iter().enumerate() {
+            let data = channels.iter().find(|c| c.channel_id == *id);
+            self.values[col].push(data.map(|c| c.value));
+            self.qualities[col].push(data.map(|c| quality_code(&c.quality)));
+        }
+        if self.timestamps.len() >= FLUSH_ROWS {
+            self.flush()?;
+            let span = timestamp_ns.saturating_sub(self.first_timestamp.unwrap_or(timestamp_ns));
+            if self.retention.exceeded(self.rows_written, span) {
+                self.compact()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write(&mut self, data: &MeasurementData) -> Result<(), ArrowError> {
+        self.write_row(data.timestamp_ns, data.sample_index, &data.channels)
+    }
+
+    /// Writes buffered rows as one record batch
+    pub fn flush(&mut self) -> Result<(), ArrowError> {
+        let Some(writer) = self.writer.as_mut() else { return Ok(()) };
+        if self.timestamps.is_empty() {
+            return Ok(());
+        }
+        self.rows_written += self.timestamps.len() as u64;
+        self.first_timestamp = self.first_timestamp.or(self.timestamps.first().copied());
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from(std::mem::take(&mut self.timestamps))),
+            Arc::new(UInt64Array::from(std::mem::take(&mut self.indices))),
+        ];
+        for (values, qualities) in self.values.iter_mut().zip(self.qualities.iter_mut()) {
+            columns.push(Arc::new(Float64Array::from(std::mem::take(values))));
+            columns.push(Arc::new(UInt8Array::from(std::mem::take(qualities))));
+        }
+        writer.write(&RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+
+    /// Closes the file and applies the retention policy.
+    /// Returns the file path, or `None` if nothing was kept.
+    pub fn finish(mut self) -> Result<Option<PathBuf>, ArrowError> {
+        self.flush()?;
+        match self.retention {
+            DataRetention::Discard => return Ok(None),
+            DataRetention::KeepAll => {}
+            DataRetention::Duration(_) | DataRetention::MaxSamples(_) => self.compact()?,
+        }
+        if let Some(mut writer) = self.writer.take() {
+            writer.finish()?;
+        }
+        Ok(Some(self.path))
+    }
+
+    /// Rewrites the file with only the retained samples and keeps writing
+    /// into the new one
+    fn compact(&mut self) -> Result<(), ArrowError> {
+        let Some(mut writer) = self.writer.take() else { return Ok(()) };
+        writer.finish()?;
+        drop(writer);
+        let mut samples = VecDeque::from(read_measurement(&self.path)?);
+        self.retention.evict(&mut samples);
+
+        // Rewrite next to the original, then swap it in
+        let tmp = self.path.with_extension("arrow.tmp");
+        let mut trimmed = Self::open(tmp.clone(), self.measurement_id, self.channels.clone(), DataRetention::KeepAll)?;
+        for sample in &samples {
+            trimmed.write(sample)?;
+        }
+        trimmed.flush()?;
+        fs::rename(&tmp, &self.path)?;
+        self.writer = trimmed.writer.take();
+        self.rows_written = trimmed.rows_written;
+        self.first_timestamp = trimmed.first_timestamp;
+        Ok(())
+    }
+}
+
+/// Location of the file written for `measurement_id`
+pub fn measurement_path(dir: impl AsRef<Path>, measurement_id: MeasurementId) -> PathBuf {
+    dir.as_ref().join(format!("{}.arrow", measurement_id))
+}
+
+/// Reads a file written by `MeasurementWriter` back into samples.
+/// Metadata is empty since it is not persisted.
+pub fn read_measurement(path: impl AsRef<Path>) -> Result<Vec<MeasurementData>, ArrowError> {
+    let reader = FileReader::try_new(File::open(path)?, None)?;
+    let schema = reader.schema();
+    let measurement_id: MeasurementId = schema
+        .metadata()
+        .get(META_MEASUREMENT_ID)
+        .ok_or_else(|| ArrowError::SchemaError("missing measurement_id".into()))?
+        .parse()
+        .map_err(|e| ArrowError::ParseError(format!("measurement_id: {}", e)))?;
+    let channels: Vec<u8> = schema
+        .fields()
+        .iter()
+        .filter_map(|f| f.name().strip_prefix("ch")?.parse().ok())
+        .collect();
+
+    let mut samples = Vec::new();
+    for batch in reader {
+        let batch = batch?;
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .cloned()
+                .ok_or_else(|| ArrowError::SchemaError(format!("missing column {}", name)))
+        };
+        let timestamps = column("timestamp_ns")?;
+        let timestamps = timestamps.as_primitive::<UInt64Type>();
+        let indices = column("sample_index")?;
+        let indices = indices.as_primitive::<UInt64Type>();
+        let mut channel_columns = Vec::with_capacity(channels.len());
+        for id in &channels {
+            channel_columns.push((*id, column(&format!("ch{}", id))?, column(&format!("ch{}_quality", id))?));
+        }
+
+        for row in 0..batch.num_rows() {
+            let channels = channel_columns
+                .iter()
+                .filter_map(|(id, values, qualities)| {
+                    let values = values.as_primitive::<Float64Type>();
+                    let qualities = qualities.as_primitive::<UInt8Type>();
+                    values.is_valid(row).then(|| ChannelData {
+                        channel_id: *id,
+                        value: values.value(row),
+                        quality: quality_from_code(qualities.value(row)),
+                    })
+                })
+                .collect();
+            samples.push(MeasurementData {
+                measurement_id,
+                timestamp_ns: timestamps.value(row),
+                sample_index: indices.value(row),
+                channels,
+                metadata: SampleMetadata { trigger_events: Vec::new(), system_events: Vec::new() },
+            });
+        }
+    }
+    Ok(samples)
+}
+
+fn quality_code(quality: &DataQuality) -> u8 {
+    match quality {
+        DataQuality::Good => 0,
+        DataQuality::Questionable => 1,
+        DataQuality::Bad => 2,
+        DataQuality::Overflow => 3,
+        DataQuality::Underflow => 4,
+    }
+}
+
+fn quality_from_code(code: u8) -> DataQuality {
+    match code {
+        0 => DataQuality::Good,
+        1 => DataQuality::Questionable,
+        3 => DataQuality::Overflow,
+        4 => DataQuality::Underflow,
+        _ => DataQuality::Bad,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multi_user_types::{ChannelConfig, MeasurementType};
+    use uuid::Uuid;
+
+    fn config(channels: &[(u8, bool)], data_retention: DataRetention) -> MeasurementConfig {
+        MeasurementConfig {
+            measurement_type: MeasurementType::Voltage,
+            sample_rate_hz: 1000,
+            duration_seconds: None,
+            channels: channels
+                .iter()
+                .map(|&(channel_id, enabled)| ChannelConfig {
+                    channel_id,
+                    name: format!("ch{}", channel_id),
+                    unit: "V".into(),
+                    range_min: -10.0,
+                    range_max: 10.0,
+                    enabled,
+                })
+                .collect(),
+            trigger_config: None,
+            auto_scale: false,
+            data_retention,
+        }
+    }
+
+    fn channel(channel_id: u8, value: f64, quality: DataQuality) -> ChannelData {
+        ChannelData { channel_id, value, quality }
+    }
+
+    /// Fresh directory under the system temp dir, removed by the caller
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("storage-test-{}", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn written_samples_read_back() {
+        let dir = temp_dir();
+        let id = Uuid::new_v4();
+        let mut writer =
+            MeasurementWriter::create(&dir, id, &config(&[(2, true), (0, true), (5, false)], DataRetention::KeepAll))
+                .unwrap();
+        writer
+            .write_row(1_000, 0, &[channel(0, 1.5, DataQuality::Good), channel(2, -3.25, DataQuality::Overflow)])
+            .unwrap();
+        // ch2 missing, ch5 not part of the file
+        writer
+            .write_row(2_000, 1, &[channel(0, 0.5, DataQuality::Questionable), channel(5, 9.0, DataQuality::Good)])
+            .unwrap();
+        let path = writer.finish().unwrap().unwrap();
+        assert_eq!(path, measurement_path(&dir, id));
+
+        let samples = read_measurement(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert!(samples.iter().all(|s| s.measurement_id == id && s.metadata.trigger_events.is_empty()));
+        assert_eq!((samples[0].timestamp_ns, samples[0].sample_index), (1_000, 0));
+        assert_eq!((samples[1].timestamp_ns, samples[1].sample_index), (2_000, 1));
+
+        let first: Vec<_> = samples[0].channels.iter().map(|c| (c.channel_id, c.value)).collect();
+        assert_eq!(first, [(0, 1.5), (2, -3.25)]);
+        assert!(matches!(samples[0].channels[0].quality, DataQuality::Good));
+        assert!(matches!(samples[0].channels[1].quality, DataQuality::Overflow));
+        let second: Vec<_> = samples[1].channels.iter().map(|c| (c.channel_id, c.value)).collect();
+        assert_eq!(second, [(0, 0.5)]);
+        assert!(matches!(samples[1].channels[0].quality, DataQuality::Questionable));
+    }
+
+    #[test]
+    fn discard_writes_no_file() {
+        let dir = temp_dir();
+        let id = Uuid::new_v4();
+        let mut writer = MeasurementWriter::create(&dir, id, &config(&[(0, true)], DataRetention::Discard)).unwrap();
+        writer.write_row(1_000, 0, &[channel(0, 1.0, DataQuality::Good)]).unwrap();
+        assert_eq!(writer.finish().unwrap(), None);
+        assert!(!measurement_path(&dir, id).exists());
+    }
+
+    /// Writes the samples of `range` 1 ms apart, value = index
+    fn write_samples(writer: &mut MeasurementWriter, range: std::ops::Range<u64>) {
+        for i in range {
+            writer.write_row(i * 1_000_000, i, &[channel(0, i as f64, DataQuality::Good)]).unwrap();
+        }
+    }
+
+    #[test]
+    fn max_samples_compacts_while_writing() {
+        let dir = temp_dir();
+        let id = Uuid::new_v4();
+        let mut writer =
+            MeasurementWriter::create(&dir, id, &config(&[(0, true)], DataRetention::MaxSamples(100))).unwrap();
+        // The first flush holds more than twice the retained samples
+        write_samples(&mut writer, 0..FLUSH_ROWS as u64);
+        assert_eq!(writer.rows_written, 100);
+        write_samples(&mut writer, FLUSH_ROWS as u64..5000);
+        let path = writer.finish().unwrap().unwrap();
+
+        let samples = read_measurement(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let indices: Vec<u64> = samples.iter().map(|s| s.sample_index).collect();
+        assert_eq!(indices, (4900..5000).collect::<Vec<_>>());
+        assert!(samples.iter().all(|s| s.channels[0].value == s.sample_index as f64));
+    }
+
+    #[test]
+    fn duration_keeps_the_last_seconds() {
+        let dir = temp_dir();
+        let id = Uuid::new_v4();
+        let mut writer = MeasurementWriter::create(&dir, id, &config(&[(0, true)], DataRetention::Duration(1))).unwrap();
+        write_samples(&mut writer, 0..5000);
+        let path = writer.finish().unwrap().unwrap();
+
+        let samples = read_measurement(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        // 3.999 s .. 4.999 s, both ends included
+        assert_eq!(samples.len(), 1001);
+        assert_eq!(samples[0].sample_index, 3999);
+        assert!(!path.with_extension("arrow.tmp").exists());
+    }
+}
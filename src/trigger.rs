@@ -0,0 +1,266 @@
+// 🎯 Trigger Evaluation for Measurement Channels
+
+use crate::multi_user_types::{MeasurementData, TriggerConfig, TriggerEvent, TriggerType};
+use std::collections::{HashMap, VecDeque};
+
+/// Per-channel state needed to detect crossings
+#[derive(Clone, Debug, Default)]
+struct ChannelState {
+    prev: Option<f64>,
+    rising_armed: bool,
+    falling_armed: bool,
+}
+
+/// Evaluates a `TriggerConfig` against incoming samples.
+///
+/// Semantics per `TriggerType` (`h` = `hysteresis`):
+/// - `Rising`: fires when the value crosses `threshold` upwards, then stays
+///   disarmed until it drops below `threshold - h`.
+/// - `Falling`: mirror image, re-arms above `threshold + h`.
+/// - `Both`: rising and falling edges, each with its own re-arm rule.
+/// - `Level`: fires as soon as the value is at or above `threshold` (no edge
+///   needed, so it can fire on the first sample), re-arms below `threshold - h`.
+/// - `Window { min, max }`: fires when the value leaves `[min, max]`, re-arms
+///   once it is back inside `[min + h, max - h]`. `threshold` is unused.
+#[derive(Clone, Debug)]
+pub struct TriggerEngine {
+    config: TriggerConfig,
+    channels: HashMap<u8, ChannelState>,
+}
+
+impl TriggerEngine {
+    pub fn new(config: TriggerConfig) -> Self {
+        Self { config, channels: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &TriggerConfig {
+        &self.config
+    }
+
+    /// Feeds one sample. Samples of channels other than the configured
+    /// `channel_id` are ignored.
+    pub fn evaluate(&mut self, channel_id: u8, value: f64, ts_ns: u64) -> Option<TriggerEvent> {
+        if channel_id != self.config.channel_id || !value.is_finite() {
+            return None;
+        }
+        let threshold = self.config.threshold;
+        let h = self.config.hysteresis.abs();
+        let state = self.channels.entry(channel_id).or_insert_with(|| ChannelState {
+            prev: None,
+            rising_armed: true,
+            falling_armed: true,
+        });
+        let prev = state.prev.replace(value);
+
+        // Re-arm first so a single sample can't both re-arm and fire
+        if value < threshold - h {
+            state.rising_armed = true;
+        }
+        if value > threshold + h {
+            state.falling_armed = true;
+        }
+
+        let rising = prev.is_some_and(|p| p < threshold) && value >= threshold;
+        let falling = prev.is_some_and(|p| p > threshold) && value <= threshold;
+
+        let fired = match self.config.trigger_type {
+            TriggerType::Rising => rising && std::mem::take(&mut state.rising_armed),
+            TriggerType::Falling => falling && std::mem::take(&mut state.falling_armed),
+            TriggerType::Both => {
+                (rising && std::mem::take(&mut state.rising_armed))
+                    || (falling && std::mem::take(&mut state.falling_armed))
+            }
+            TriggerType::Level => value >= threshold && std::mem::take(&mut state.rising_armed),
+            TriggerType::Window { min, max } => {
+                // `rising_armed` doubles as "inside the window" for this mode
+                if value >= min + h && value <= max - h {
+                    state.rising_armed = true;
+                }
+                (value < min || value > max) && std::mem::take(&mut state.rising_armed)
+            }
+        };
+
+        fired.then(|| TriggerEvent {
+            trigger_type: self.config.trigger_type.clone(),
+            timestamp_ns: ts_ns,
+            channel_id,
+            trigger_value: value,
+        })
+    }
+
+    /// Forgets all crossing history, e.g. after a measurement restart
+    pub fn reset(&mut self) {
+        self.channels.clear();
+    }
+}
+
+/// Pre/post-trigger capture for one measurement.
+///
+/// Keeps the last `pre_trigger_samples` samples in a ring buffer. When the
+/// trigger fires, the window is the buffered samples, the triggering sample
+/// and the next `post_trigger_samples` samples.
+///
+/// Triggers that fire while post-trigger samples are still being collected
+/// are ignored: the running window is completed unchanged and the next
+/// capture needs a fresh trigger afterwards.
+#[derive(Clone, Debug)]
+pub struct TriggerCapture {
+    engine: TriggerEngine,
+    pre: VecDeque<MeasurementData>,
+    window: Vec<MeasurementData>,
+    remaining: Option<usize>,
+    last_event: Option<TriggerEvent>,
+}
+
+impl TriggerCapture {
+    pub fn new(config: TriggerConfig) -> Self {
+        let pre = config.pre_trigger_samples as usize;
+        Self {
+            engine: TriggerEngine::new(config),
+            pre: VecDeque::with_capacity(pre),
+            window: Vec::new(),
+            remaining: None,
+            last_event: None,
+        }
+    }
+
+    /// Event that started the most recent capture
+    pub fn last_event(&self) -> Option<&TriggerEvent> {
+        self.last_event.as_ref()
+    }
+
+    /// True while post-trigger samples are being collected
+    pub fn is_collecting(&self) -> bool {
+        self.remaining.is_some()
+    }
+
+    /// Feeds one sample; returns the full capture window once it is complete.
+    pub fn feed(&mut self, data: MeasurementData) -> Option<Vec<MeasurementData>> {
+        // Always evaluate so the engine keeps tracking edges and hysteresis
+        let mut event = None;
+        for channel in &data.channels {
+            if let Some(e) = self.engine.evaluate(channel.channel_id, channel.value, data.timestamp_ns) {
+                event.get_or_insert(e);
+            }
+        }
+
+        let done = match self.remaining.as_mut() {
+            Some(remaining) => {
+                self.window.push(data.clone());
+                *remaining -= 1;
+                *remaining == 0
+            }
+            None => match event {
+                Some(e) => {
+                    self.last_event = Some(e);
+                    self.window.extend(self.pre.iter().cloned());
+                    self.window.push(data.clone());
+                    let post = self.engine.config().post_trigger_samples as usize;
+                    self.remaining = Some(post);
+                    post == 0
+                }
+                None => false,
+            },
+        };
+
+        // Collected samples also serve as history for the next capture
+        let capacity = self.engine.config().pre_trigger_samples as usize;
+        if capacity > 0 {
+            if self.pre.len() == capacity {
+                self.pre.pop_front();
+            }
+            self.pre.push_back(data);
+        }
+
+        if done {
+            self.remaining = None;
+            Some(std::mem::take(&mut self.window))
+        } else {
+            None
+        }
+    }
+
+    /// Drops buffered history and any partial window
+    pub fn reset(&mut self) {
+        self.engine.reset();
+        self.pre.clear();
+        self.window.clear();
+        self.remaining = None;
+        self.last_event = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(trigger_type: TriggerType, threshold: f64, hysteresis: f64) -> TriggerEngine {
+        TriggerEngine::new(TriggerConfig {
+            trigger_type,
+            channel_id: 0,
+            threshold,
+            hysteresis,
+            pre_trigger_samples: 0,
+            post_trigger_samples: 0,
+        })
+    }
+
+    /// Indices of the values that fired
+    fn fires(engine: &mut TriggerEngine, values: &[f64]) -> Vec<usize> {
+        values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| engine.evaluate(0, *v, i as u64).map(|_| i))
+            .collect()
+    }
+
+    #[test]
+    fn rising_edge_fires_once_until_rearmed() {
+        let mut e = engine(TriggerType::Rising, 1.0, 0.2);
+        // Chatter around the threshold stays within the hysteresis
+        assert_eq!(fires(&mut e, &[0.0, 1.1, 0.9, 1.1, 0.9, 1.2]), vec![1]);
+        // Below threshold - hysteresis re-arms
+        assert_eq!(fires(&mut e, &[0.7, 1.3]), vec![1]);
+    }
+
+    #[test]
+    fn falling_edge_fires_once_until_rearmed() {
+        let mut e = engine(TriggerType::Falling, 1.0, 0.2);
+        assert_eq!(fires(&mut e, &[2.0, 0.9, 1.1, 0.9]), vec![1]);
+        assert_eq!(fires(&mut e, &[1.3, 0.5]), vec![1]);
+        // Rising edges don't count
+        assert!(fires(&mut e, &[0.0, 2.0]).is_empty());
+    }
+
+    #[test]
+    fn both_edges_fire() {
+        let mut e = engine(TriggerType::Both, 1.0, 0.0);
+        assert_eq!(fires(&mut e, &[0.0, 2.0, 0.0, 2.0]), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn level_fires_without_edge_and_rearms_below() {
+        let mut e = engine(TriggerType::Level, 1.0, 0.5);
+        // First sample above the level fires, staying above doesn't again
+        assert_eq!(fires(&mut e, &[1.5, 2.0, 0.8, 1.5]), vec![0]);
+        assert_eq!(fires(&mut e, &[0.4, 1.5]), vec![1]);
+    }
+
+    #[test]
+    fn window_fires_on_leaving_and_rearms_inside() {
+        let mut e = engine(TriggerType::Window { min: 0.0, max: 10.0 }, 0.0, 1.0);
+        assert_eq!(fires(&mut e, &[5.0, 11.0, 12.0, 9.5, 11.0]), vec![1]);
+        // Back inside [min + h, max - h] re-arms
+        assert_eq!(fires(&mut e, &[5.0, -1.0]), vec![1]);
+    }
+
+    #[test]
+    fn other_channels_and_non_finite_values_are_ignored() {
+        let mut e = engine(TriggerType::Rising, 1.0, 0.0);
+        assert!(e.evaluate(0, 0.0, 0).is_none());
+        assert!(e.evaluate(1, 2.0, 1).is_none());
+        assert!(e.evaluate(0, f64::NAN, 2).is_none());
+        let event = e.evaluate(0, 2.0, 3).expect("rising edge");
+        assert_eq!((event.channel_id, event.timestamp_ns, event.trigger_value), (0, 3, 2.0));
+    }
+}